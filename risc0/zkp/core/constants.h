@@ -25,7 +25,8 @@ CONSTSCALAR size_t kQueries = 50; // ~100 bits of conjectured security
 
 CONSTSCALAR size_t kInvRate = 4;
 CONSTSCALAR size_t kMaxDegree = kInvRate + 1;
-CONSTSCALAR size_t kFriFold = 16;
+CONSTSCALAR size_t kFriFoldPo2 = 4;
+CONSTSCALAR size_t kFriFold = size_t(1) << kFriFoldPo2;
 CONSTSCALAR size_t kFriMinDegree = 256;
 
 CONSTSCALAR size_t kExtSize = 4;
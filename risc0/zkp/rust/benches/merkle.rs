@@ -0,0 +1,86 @@
+// Copyright 2022 Risc0, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::{thread_rng, RngCore};
+use risc0_zkp::{
+    core::{
+        blake3,
+        sha::{Digest, Sha},
+        sha_cpu,
+        sha_rng::ShaRng,
+    },
+    field::{
+        baby_bear::{BabyBear, Elem},
+        Elem as _,
+    },
+    hal::{cpu::CpuHal, Buffer, Hal},
+};
+
+const COLS: usize = 16;
+
+// Builds a tree the same way MerkleTreeProver does: hash each row of the
+// matrix into the leaves, then fold each layer into the one above it.
+fn bench_merkle_tree<S: Sha + Sync>(c: &mut Criterion, name: &str, sha: S) {
+    let mut group = c.benchmark_group(name);
+    for n in [10, 15, 20].iter() {
+        group.bench_function(BenchmarkId::from_parameter(n), |b| {
+            let rows = 1 << n;
+            let hal = CpuHal::<BabyBear, S>::new_with_sha(sha.clone());
+            let mut rng = thread_rng();
+            let data: Vec<Elem> = (0..rows * COLS).map(|_| Elem::random(&mut rng)).collect();
+            let matrix = hal.copy_fp_from(&data);
+            let nodes = hal.alloc_digest(rows * 2);
+            b.iter(|| {
+                hal.sha_rows(&nodes.slice(rows, rows), &matrix);
+                for i in (0..*n).rev() {
+                    let layer_size = 1 << i;
+                    hal.sha_fold(&nodes, layer_size * 2, layer_size);
+                }
+            });
+        });
+    }
+}
+
+pub fn merkle_tree(c: &mut Criterion) {
+    bench_merkle_tree(c, "merkle_tree", sha_cpu::Impl {});
+}
+
+pub fn merkle_tree_blake3(c: &mut Criterion) {
+    bench_merkle_tree(c, "merkle_tree_blake3", blake3::Impl {});
+}
+
+pub fn hash_pair(c: &mut Criterion) {
+    let sha = sha_cpu::Impl {};
+    let a = sha.hash_bytes(b"left");
+    let b = sha.hash_bytes(b"right");
+    c.bench_function("hash_pair", |bench| {
+        bench.iter(|| sha.hash_pair(&a, &b));
+    });
+}
+
+pub fn sha_rng(c: &mut Criterion) {
+    let sha = sha_cpu::Impl {};
+    let digest: Digest = sha.hash_bytes(b"seed");
+    c.bench_function("sha_rng", |b| {
+        let mut rng = ShaRng::new(&sha);
+        b.iter(|| {
+            rng.mix(&digest);
+            rng.next_u32()
+        });
+    });
+}
+
+criterion_group!(benches, merkle_tree, merkle_tree_blake3, hash_pair, sha_rng);
+criterion_main!(benches);
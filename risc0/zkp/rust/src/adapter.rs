@@ -19,7 +19,6 @@ use anyhow::Result;
 use crate::{
     core::{fp::Fp, fp4::Fp4},
     taps::{RegisterGroup, TapSet},
-    INV_RATE,
 };
 
 #[derive(Clone, Copy)]
@@ -68,6 +67,7 @@ pub struct PolyFpContext {
     pub size: usize,
     pub cycle: usize,
     pub mix: Fp4,
+    pub inv_rate: usize,
 }
 
 impl PolyFpContext {
@@ -81,7 +81,7 @@ impl PolyFpContext {
 
     pub fn _get(&self, base: &[Fp], offset: usize, back: usize, _tap: usize, _loc: &str) -> Fp {
         // Cycle here is over the expanded domain
-        let cycle = self.cycle.wrapping_sub(INV_RATE * back);
+        let cycle = self.cycle.wrapping_sub(self.inv_rate * back);
         base[offset * self.size + (cycle & self.mask())]
     }
 
@@ -240,7 +240,7 @@ impl PolyFpStep {
             }
             PolyFpStep::Get(base, offset, back, _loc) => {
                 // Cycle here is over the expanded domain
-                let cycle = ctx.cycle.wrapping_sub(INV_RATE * back);
+                let cycle = ctx.cycle.wrapping_sub(ctx.inv_rate * back);
                 let value = args[*base][offset * ctx.size + (cycle & ctx.mask())];
                 fp_vars.push(value);
             }
@@ -36,8 +36,12 @@ pub mod fp4 {
     /// to work with multiple fields.
     pub const EXT_SIZE: usize = Fp4::EXT_SIZE;
 }
+#[cfg(feature = "host")]
+pub mod blake3;
 pub mod ntt;
 pub mod poly;
+pub mod poseidon;
+pub mod poseidon_consts;
 pub mod rou;
 pub mod sha;
 #[cfg(feature = "prove")]
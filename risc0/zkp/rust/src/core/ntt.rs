@@ -18,12 +18,8 @@ use core::ops::{Add, Mul, Sub};
 
 use paste::paste;
 
-use super::{
-    fp::Fp,
-    log2_ceil,
-    rou::{ROU_FWD, ROU_REV},
-};
-use crate::field::Elem;
+use super::log2_ceil;
+use crate::field::{Elem, RootsOfUnity};
 
 /// Reverses the bits in a 32-bit number.
 /// # Example
@@ -77,12 +73,12 @@ pub fn bit_reverse<T: Copy>(io: &mut [T]) {
 }
 
 #[inline]
-fn fwd_butterfly_0<T>(_: &mut [T], _: usize) {
+fn fwd_butterfly_0<B, T>(_: &mut [T], _: usize) {
     // no-op base case
 }
 
 #[inline]
-fn rev_butterfly_0<T>(_: &mut [T]) {
+fn rev_butterfly_0<B, T>(_: &mut [T]) {
     // no-op base case
 }
 
@@ -90,18 +86,19 @@ macro_rules! butterfly {
     ($n:literal, $x:literal) => {
         paste! {
             #[inline]
-            fn [<fwd_butterfly_ $n>]<T>(io: &mut [T], expand_bits: usize)
+            fn [<fwd_butterfly_ $n>]<B, T>(io: &mut [T], expand_bits: usize)
             where
-                T: Copy + Mul<Fp, Output = T> + Add<Output = T> + Sub<Output = T>,
+                B: Elem + RootsOfUnity,
+                T: Copy + Mul<B, Output = T> + Add<Output = T> + Sub<Output = T>,
             {
                 if $n == expand_bits {
                     return;
                 }
                 let half = 1 << ($n - 1);
-                [<fwd_butterfly_ $x>](&mut io[..half], expand_bits);
-                [<fwd_butterfly_ $x>](&mut io[half..], expand_bits);
-                let step = Fp::new(ROU_FWD[$n]);
-                let mut cur = Fp::ONE;
+                [<fwd_butterfly_ $x>]::<B, T>(&mut io[..half], expand_bits);
+                [<fwd_butterfly_ $x>]::<B, T>(&mut io[half..], expand_bits);
+                let step = B::ROU_FWD[$n];
+                let mut cur = B::ONE;
                 for i in 0..half {
                     let a = io[i];
                     let b = io[i + half] * cur;
@@ -112,13 +109,14 @@ macro_rules! butterfly {
             }
 
             #[inline]
-            fn [<rev_butterfly_ $n>]<T>(io: &mut [T])
+            fn [<rev_butterfly_ $n>]<B, T>(io: &mut [T])
             where
-                T: Copy + Mul<Fp, Output = T> + Add<Output = T> + Sub<Output = T>,
+                B: Elem + RootsOfUnity,
+                T: Copy + Mul<B, Output = T> + Add<Output = T> + Sub<Output = T>,
             {
                 let half = 1 << ($n - 1);
-                let step = Fp::new(ROU_REV[$n]);
-                let mut cur = Fp::ONE;
+                let step = B::ROU_REV[$n];
+                let mut cur = B::ONE;
                 for i in 0..half {
                     let a = io[i];
                     let b = io[i + half];
@@ -126,8 +124,8 @@ macro_rules! butterfly {
                     io[i + half] = (a - b) * cur;
                     cur *= step;
                 }
-                [<rev_butterfly_ $x>](&mut io[..half]);
-                [<rev_butterfly_ $x>](&mut io[half..]);
+                [<rev_butterfly_ $x>]::<B, T>(&mut io[..half]);
+                [<rev_butterfly_ $x>]::<B, T>(&mut io[half..]);
             }
         }
     };
@@ -162,6 +160,8 @@ butterfly!(2, 1);
 butterfly!(1, 0);
 
 /// Perform a reverse butterfly transform of a buffer of (1 << n) numbers.
+/// The roots of unity come from the field `B`; the values in `io` may be
+/// elements of `B` or of an extension of it.
 /// The result of this computation is a discrete Fourier transform, but with
 /// changed indices. This is described [here](https://en.wikipedia.org/wiki/Cooley%E2%80%93Tukey_FFT_algorithm#Data_reordering,_bit_reversal,_and_in-place_algorithms).
 /// The output of `rev_butterfly(io, n)` at index i is the sum over k from 0 to
@@ -222,88 +222,91 @@ butterfly!(1, 0);
 /// The exponent multiplicands in the sum arise from reversing the indices as
 /// three-bit numbers. For example, 3 is 011 in binary, which reversed is 110,
 /// which is 6. So i' in the exponent of the index-3 value is 6.
-pub fn interpolate_ntt<T>(io: &mut [T])
+pub fn interpolate_ntt<B, T>(io: &mut [T])
 where
-    T: Copy + Mul<Fp, Output = T> + Add<Output = T> + Sub<Output = T>,
+    B: Elem + RootsOfUnity,
+    T: Copy + Mul<B, Output = T> + Add<Output = T> + Sub<Output = T>,
 {
     let size = io.len();
     let n = log2_ceil(size);
     assert_eq!(1 << n, size);
     match n {
-        0 => rev_butterfly_0(io),
-        1 => rev_butterfly_1(io),
-        2 => rev_butterfly_2(io),
-        3 => rev_butterfly_3(io),
-        4 => rev_butterfly_4(io),
-        5 => rev_butterfly_5(io),
-        6 => rev_butterfly_6(io),
-        7 => rev_butterfly_7(io),
-        8 => rev_butterfly_8(io),
-        9 => rev_butterfly_9(io),
-        10 => rev_butterfly_10(io),
-        11 => rev_butterfly_11(io),
-        12 => rev_butterfly_12(io),
-        13 => rev_butterfly_13(io),
-        14 => rev_butterfly_14(io),
-        15 => rev_butterfly_15(io),
-        16 => rev_butterfly_16(io),
-        17 => rev_butterfly_17(io),
-        18 => rev_butterfly_18(io),
-        19 => rev_butterfly_19(io),
-        20 => rev_butterfly_20(io),
-        21 => rev_butterfly_21(io),
-        22 => rev_butterfly_22(io),
-        23 => rev_butterfly_23(io),
-        24 => rev_butterfly_24(io),
-        25 => rev_butterfly_25(io),
-        26 => rev_butterfly_26(io),
-        27 => rev_butterfly_27(io),
+        0 => rev_butterfly_0::<B, T>(io),
+        1 => rev_butterfly_1::<B, T>(io),
+        2 => rev_butterfly_2::<B, T>(io),
+        3 => rev_butterfly_3::<B, T>(io),
+        4 => rev_butterfly_4::<B, T>(io),
+        5 => rev_butterfly_5::<B, T>(io),
+        6 => rev_butterfly_6::<B, T>(io),
+        7 => rev_butterfly_7::<B, T>(io),
+        8 => rev_butterfly_8::<B, T>(io),
+        9 => rev_butterfly_9::<B, T>(io),
+        10 => rev_butterfly_10::<B, T>(io),
+        11 => rev_butterfly_11::<B, T>(io),
+        12 => rev_butterfly_12::<B, T>(io),
+        13 => rev_butterfly_13::<B, T>(io),
+        14 => rev_butterfly_14::<B, T>(io),
+        15 => rev_butterfly_15::<B, T>(io),
+        16 => rev_butterfly_16::<B, T>(io),
+        17 => rev_butterfly_17::<B, T>(io),
+        18 => rev_butterfly_18::<B, T>(io),
+        19 => rev_butterfly_19::<B, T>(io),
+        20 => rev_butterfly_20::<B, T>(io),
+        21 => rev_butterfly_21::<B, T>(io),
+        22 => rev_butterfly_22::<B, T>(io),
+        23 => rev_butterfly_23::<B, T>(io),
+        24 => rev_butterfly_24::<B, T>(io),
+        25 => rev_butterfly_25::<B, T>(io),
+        26 => rev_butterfly_26::<B, T>(io),
+        27 => rev_butterfly_27::<B, T>(io),
         _ => unreachable!(),
     }
-    let norm = Fp::new(size as u32).inv();
+    let norm = B::from_u64(size as u64).inv();
     for i in 0..size {
         io[i] = io[i] * norm;
     }
 }
 
-/// Perform a forward butterfly transform of a buffer of (1 << n) numbers.
-pub fn evaluate_ntt<T>(io: &mut [T], expand_bits: usize)
+/// Perform a forward butterfly transform of a buffer of (1 << n) numbers,
+/// using the roots of unity of the field `B`.
+pub fn evaluate_ntt<B, T>(io: &mut [T], expand_bits: usize)
 where
-    T: Copy + Mul<Fp, Output = T> + Add<Output = T> + Sub<Output = T>,
+    B: Elem + RootsOfUnity,
+    T: Copy + Mul<B, Output = T> + Add<Output = T> + Sub<Output = T>,
 {
     // do_ntt::<T, false>(io, expand_bits);
     let size = io.len();
     let n = log2_ceil(size);
     assert_eq!(1 << n, size);
     match n {
-        0 => fwd_butterfly_0(io, expand_bits),
-        1 => fwd_butterfly_1(io, expand_bits),
-        2 => fwd_butterfly_2(io, expand_bits),
-        3 => fwd_butterfly_3(io, expand_bits),
-        4 => fwd_butterfly_4(io, expand_bits),
-        5 => fwd_butterfly_5(io, expand_bits),
-        6 => fwd_butterfly_6(io, expand_bits),
-        7 => fwd_butterfly_7(io, expand_bits),
-        8 => fwd_butterfly_8(io, expand_bits),
-        9 => fwd_butterfly_9(io, expand_bits),
-        10 => fwd_butterfly_10(io, expand_bits),
-        11 => fwd_butterfly_11(io, expand_bits),
-        12 => fwd_butterfly_12(io, expand_bits),
-        13 => fwd_butterfly_13(io, expand_bits),
-        14 => fwd_butterfly_14(io, expand_bits),
-        15 => fwd_butterfly_15(io, expand_bits),
-        16 => fwd_butterfly_16(io, expand_bits),
-        17 => fwd_butterfly_17(io, expand_bits),
-        18 => fwd_butterfly_18(io, expand_bits),
-        19 => fwd_butterfly_19(io, expand_bits),
-        20 => fwd_butterfly_20(io, expand_bits),
-        21 => fwd_butterfly_21(io, expand_bits),
-        22 => fwd_butterfly_22(io, expand_bits),
-        23 => fwd_butterfly_23(io, expand_bits),
-        24 => fwd_butterfly_24(io, expand_bits),
-        25 => fwd_butterfly_25(io, expand_bits),
-        26 => fwd_butterfly_26(io, expand_bits),
-        27 => fwd_butterfly_27(io, expand_bits),
+        0 => fwd_butterfly_0::<B, T>(io, expand_bits),
+        1 => fwd_butterfly_1::<B, T>(io, expand_bits),
+        2 => fwd_butterfly_2::<B, T>(io, expand_bits),
+        3 => fwd_butterfly_3::<B, T>(io, expand_bits),
+        4 => fwd_butterfly_4::<B, T>(io, expand_bits),
+        5 => fwd_butterfly_5::<B, T>(io, expand_bits),
+        6 => fwd_butterfly_6::<B, T>(io, expand_bits),
+        7 => fwd_butterfly_7::<B, T>(io, expand_bits),
+        8 => fwd_butterfly_8::<B, T>(io, expand_bits),
+        9 => fwd_butterfly_9::<B, T>(io, expand_bits),
+        10 => fwd_butterfly_10::<B, T>(io, expand_bits),
+        11 => fwd_butterfly_11::<B, T>(io, expand_bits),
+        12 => fwd_butterfly_12::<B, T>(io, expand_bits),
+        13 => fwd_butterfly_13::<B, T>(io, expand_bits),
+        14 => fwd_butterfly_14::<B, T>(io, expand_bits),
+        15 => fwd_butterfly_15::<B, T>(io, expand_bits),
+        16 => fwd_butterfly_16::<B, T>(io, expand_bits),
+        17 => fwd_butterfly_17::<B, T>(io, expand_bits),
+        18 => fwd_butterfly_18::<B, T>(io, expand_bits),
+        19 => fwd_butterfly_19::<B, T>(io, expand_bits),
+        20 => fwd_butterfly_20::<B, T>(io, expand_bits),
+        21 => fwd_butterfly_21::<B, T>(io, expand_bits),
+        22 => fwd_butterfly_22::<B, T>(io, expand_bits),
+        23 => fwd_butterfly_23::<B, T>(io, expand_bits),
+        24 => fwd_butterfly_24::<B, T>(io, expand_bits),
+        25 => fwd_butterfly_25::<B, T>(io, expand_bits),
+        26 => fwd_butterfly_26::<B, T>(io, expand_bits),
+        27 => fwd_butterfly_27::<B, T>(io, expand_bits),
         _ => unreachable!(),
     }
 }
@@ -329,7 +332,7 @@ mod tests {
         ntt::{bit_reverse, evaluate_ntt, interpolate_ntt},
         rou::ROU_FWD,
     };
-    use crate::field::Elem;
+    use crate::field::{goldilocks, Elem, RootsOfUnity};
 
     // Compare the complex version to the naive version
     #[test]
@@ -356,31 +359,40 @@ mod tests {
         }
         // Now compute multiEvaluate in place
         bit_reverse(&mut buf);
-        evaluate_ntt(&mut buf, 0);
+        evaluate_ntt::<Fp, Fp>(&mut buf, 0);
         // Compare
         assert_eq!(goal, buf);
     }
 
     // Make sure fwd + rev is identity
-    #[test]
-    fn roundtrip() {
+    fn roundtrip<B: Elem + RootsOfUnity>() {
         const N: usize = 10;
         const SIZE: usize = 1 << N;
         // Randomly fill buffer
         let mut rng = thread_rng();
-        let mut buf = [Fp::random(&mut rng); SIZE];
+        let mut buf = [B::random(&mut rng); SIZE];
         // Copy it
         let orig = buf.clone();
         // Now go backwards
-        interpolate_ntt(&mut buf);
+        interpolate_ntt::<B, B>(&mut buf);
         // Make sure something changed
         assert_ne!(orig, buf);
         // Now go forward
-        evaluate_ntt(&mut buf, 0);
+        evaluate_ntt::<B, B>(&mut buf, 0);
         // It should be back to identical
         assert_eq!(orig, buf);
     }
 
+    #[test]
+    fn roundtrip_baby_bear() {
+        roundtrip::<Fp>();
+    }
+
+    #[test]
+    fn roundtrip_goldilocks() {
+        roundtrip::<goldilocks::Elem>();
+    }
+
     #[test]
     fn expand() {
         const N: usize = 6;
@@ -391,11 +403,11 @@ mod tests {
         let mut cmp = [Fp::random(&mut rng); SIZE_IN];
         let mut buf = [Fp::ZERO; SIZE_OUT];
         // Do plain interpolate on cmp
-        interpolate_ntt(&mut cmp);
+        interpolate_ntt::<Fp, Fp>(&mut cmp);
         // Expand to buf
         super::expand(&mut buf, &cmp, L);
         // Evaluate over the larger space
-        evaluate_ntt(&mut buf, L);
+        evaluate_ntt::<Fp, Fp>(&mut buf, L);
         // Order cmp nicely for the check
         bit_reverse(&mut cmp);
         // Now verify by comparing with the slow way
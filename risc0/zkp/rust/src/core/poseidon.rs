@@ -0,0 +1,284 @@
+// Copyright 2022 Risc0, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A Poseidon hash over the base field of order `15*2^27 + 1`.
+//!
+//! This is an algebraic alternative to SHA-256 for Merkle trees and
+//! Fiat-Shamir.  It costs far more than SHA-256 to compute on a CPU, but far
+//! less to verify inside a circuit, which makes recursive verification
+//! cheaper.
+//!
+//! Each word of a [Digest] produced here holds one field element (as a
+//! canonical value less than the modulus), so a [Digest] is 8 field
+//! elements.  Hashing a pair of digests is a single permutation of the 24
+//! cell state, with both digests filling the 16 cell rate.  Words which are
+//! not canonical would be reduced, so verifiers reject digests holding them;
+//! see [Sha::is_canonical].
+
+use super::{
+    fp::Fp,
+    poseidon_consts::{CELLS, MDS, ROUNDS_FULL, ROUNDS_PARTIAL, ROUND_CONSTANTS},
+    sha::{Digest, Sha, DIGEST_WORDS},
+};
+use crate::field::{baby_bear::P, Elem};
+
+/// The number of cells absorbed by each permutation.
+const RATE: usize = 16;
+
+// Domain separation tags, stored in the capacity of the sponge alongside the
+// length of the input.  Compressing a pair of digests leaves the capacity
+// zero (or the given state), so it is distinct from both.
+const TAG_ELEMS: u32 = 1;
+const TAG_BYTES: u32 = 2;
+
+const ROUND_CONSTANTS_FP: [Fp; ROUND_CONSTANTS.len()] = to_elems(ROUND_CONSTANTS);
+const MDS_FP: [Fp; MDS.len()] = to_elems(MDS);
+
+const fn to_elems<const N: usize>(vals: [u32; N]) -> [Fp; N] {
+    let mut out = [Fp::new(0); N];
+    let mut i = 0;
+    while i < N {
+        out[i] = Fp::new(vals[i]);
+        i += 1;
+    }
+    out
+}
+
+/// The S-box, `x^7`.
+fn sbox(x: Fp) -> Fp {
+    let x2 = x * x;
+    let x4 = x2 * x2;
+    x4 * x2 * x
+}
+
+/// Apply the Poseidon permutation to `state`.
+pub fn permute(state: &mut [Fp; CELLS]) {
+    let half_full = ROUNDS_FULL / 2;
+    for round in 0..ROUNDS_FULL + ROUNDS_PARTIAL {
+        let constants = &ROUND_CONSTANTS_FP[round * CELLS..(round + 1) * CELLS];
+        for (cell, constant) in state.iter_mut().zip(constants) {
+            *cell += *constant;
+        }
+        if round < half_full || round >= half_full + ROUNDS_PARTIAL {
+            for cell in state.iter_mut() {
+                *cell = sbox(*cell);
+            }
+        } else {
+            state[0] = sbox(state[0]);
+        }
+        let mut mixed = [Fp::ZERO; CELLS];
+        for (out, row) in mixed.iter_mut().zip(MDS_FP.chunks_exact(CELLS)) {
+            for (cell, coeff) in state.iter().zip(row) {
+                *out += *cell * *coeff;
+            }
+        }
+        *state = mixed;
+    }
+}
+
+fn from_digest(digest: &Digest) -> impl Iterator<Item = Fp> + '_ {
+    digest.get().iter().map(|word| Fp::new(*word))
+}
+
+fn to_digest(elems: &[Fp]) -> Digest {
+    let mut digest = Digest::default();
+    for (word, elem) in digest.as_mut_slice().iter_mut().zip(elems) {
+        *word = elem.into();
+    }
+    digest
+}
+
+/// Absorb `len` field elements into a fresh sponge, and squeeze a digest.
+fn sponge(tag: u32, len: usize, elems: impl Iterator<Item = Fp>) -> Digest {
+    absorb([Fp::ZERO; CELLS], tag, len, elems)
+}
+
+/// Absorb `len` field elements into a sponge which starts out as `state`,
+/// and squeeze a digest.
+fn absorb(mut state: [Fp; CELLS], tag: u32, len: usize, elems: impl Iterator<Item = Fp>) -> Digest {
+    state[RATE] += Fp::new(tag);
+    state[RATE + 1] += Fp::from(len as u64);
+    let mut pos = 0;
+    for elem in elems {
+        if pos == RATE {
+            permute(&mut state);
+            pos = 0;
+        }
+        state[pos] += elem;
+        pos += 1;
+    }
+    permute(&mut state);
+    to_digest(&state[..DIGEST_WORDS])
+}
+
+/// A [Sha] implementation which hashes with Poseidon.
+///
+/// Bytes are absorbed two at a time, as 16-bit field elements.  Slices of
+/// plain old data whose words are all less than the modulus, such as slices
+/// of field elements or Poseidon digests, are absorbed one word per field
+/// element instead.
+#[derive(Debug, Clone)]
+pub struct Impl {}
+
+/// Split `bytes` into 16-bit field elements.
+fn byte_elems(bytes: &[u8]) -> impl Iterator<Item = Fp> + '_ {
+    bytes.chunks(2).map(|chunk| {
+        let low = chunk[0] as u32;
+        let high = chunk.get(1).copied().unwrap_or(0) as u32;
+        Fp::new(low | high << 8)
+    })
+}
+
+impl Sha for Impl {
+    fn hash_bytes(&self, bytes: &[u8]) -> Digest {
+        sponge(TAG_BYTES, bytes.len(), byte_elems(bytes))
+    }
+
+    fn hash_raw_words(&self, words: &[u32]) -> Digest {
+        self.hash_words(words)
+    }
+
+    /// Absorb `bytes` as [Impl::hash_bytes] does, but into a sponge whose
+    /// capacity starts out as `state`, as in [Impl::compress].
+    fn update(&self, state: &Digest, bytes: &[u8]) -> Digest {
+        let mut cells = [Fp::ZERO; CELLS];
+        for (cell, elem) in cells[RATE..].iter_mut().zip(from_digest(state)) {
+            *cell = elem;
+        }
+        absorb(cells, TAG_BYTES, bytes.len(), byte_elems(bytes))
+    }
+
+    fn hash_pair(&self, a: &Digest, b: &Digest) -> Digest {
+        self.compress(&Digest::default(), a, b)
+    }
+
+    fn compress(&self, state: &Digest, block_half1: &Digest, block_half2: &Digest) -> Digest {
+        let mut cells = [Fp::ZERO; CELLS];
+        for (cell, elem) in cells.iter_mut().zip(
+            from_digest(block_half1)
+                .chain(from_digest(block_half2))
+                .chain(from_digest(state)),
+        ) {
+            *cell = elem;
+        }
+        permute(&mut cells);
+        to_digest(&cells[..DIGEST_WORDS])
+    }
+
+    fn hash_raw_pod_slice<T: bytemuck::Pod>(&self, pod: &[T]) -> Digest {
+        let bytes: &[u8] = bytemuck::cast_slice(pod);
+        let words = bytes
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()));
+        if bytes.len() % 4 == 0 && words.clone().all(|word| word < P) {
+            sponge(TAG_ELEMS, bytes.len() / 4, words.map(Fp::new))
+        } else {
+            self.hash_bytes(bytes)
+        }
+    }
+
+    fn is_canonical(&self, digest: &Digest) -> bool {
+        digest.get().iter().all(|word| *word < P)
+    }
+
+    /// Mix by adding the digests as vectors of field elements, which keeps
+    /// the pool made of field elements.
+    fn mix(&self, pool: &mut Digest, val: &Digest) {
+        for (pool_word, val_word) in pool.get_mut().iter_mut().zip(val.get()) {
+            *pool_word = (Fp::new(*pool_word) + Fp::new(*val_word)).into();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    #[test]
+    fn permutation_vector() {
+        // Computed with an independent implementation of the permutation
+        // from the same constants.
+        let mut state = [Fp::ZERO; CELLS];
+        for (i, cell) in state.iter_mut().enumerate() {
+            *cell = Fp::new(i as u32);
+        }
+        permute(&mut state);
+        assert_eq!(
+            state.map(u32::from),
+            [
+                1823570832, 281226057, 341900918, 1960227681, 807758871, 1928903029, 1257882478,
+                1751183462, 49480803, 1043996915, 672304627, 827603689, 1607112681, 620420109,
+                33779496, 1309939535, 393972541, 963464894, 53942285, 312568320, 1341522488,
+                640636383, 883124199, 1734468391,
+            ]
+        );
+    }
+
+    #[test]
+    fn digests_are_field_elements() {
+        let sha = Impl {};
+        let digest = sha.hash_bytes(b"Hello");
+        assert!(digest.get().iter().all(|word| *word < P));
+        let pair = sha.hash_pair(&digest, &digest);
+        assert!(pair.get().iter().all(|word| *word < P));
+    }
+
+    #[test]
+    fn lengths_are_distinct() {
+        let sha = Impl {};
+        assert_ne!(sha.hash_bytes(b""), sha.hash_bytes(b"\0"));
+        assert_ne!(sha.hash_bytes(b"\0"), sha.hash_bytes(b"\0\0"));
+        assert_ne!(
+            sha.hash_raw_pod_slice(&[Fp::ZERO; 16]),
+            sha.hash_raw_pod_slice(&[Fp::ZERO; 17])
+        );
+    }
+
+    #[test]
+    fn update_starts_from_state() {
+        let sha = Impl {};
+        let state = sha.hash_bytes(b"state");
+        let digest = sha.update(&state, b"Hello");
+        assert!(sha.is_canonical(&digest));
+        assert_ne!(digest, sha.hash_bytes(b"Hello"));
+        assert_ne!(digest, sha.update(&sha.hash_bytes(b"other"), b"Hello"));
+    }
+
+    #[test]
+    fn non_canonical_digests() {
+        let sha = Impl {};
+        assert!(sha.is_canonical(&sha.hash_bytes(b"Hello")));
+        let mut words = *sha.hash_bytes(b"Hello").get();
+        words[3] += P;
+        assert!(!sha.is_canonical(&Digest::new(words)));
+    }
+
+    #[test]
+    fn pod_stride() {
+        let sha = Impl {};
+        let elems: Vec<Fp> = (0..30).map(Fp::new).collect();
+        let column: Vec<Fp> = elems.iter().skip(2).step_by(3).copied().collect();
+        assert_eq!(
+            sha.hash_pod_stride(&elems, 2, 10, 3),
+            sha.hash_raw_pod_slice(&column)
+        );
+        let words = [u32::MAX, 1, 2];
+        assert_eq!(
+            sha.hash_pod_stride(&words, 0, 3, 1),
+            sha.hash_raw_pod_slice(&words)
+        );
+    }
+}
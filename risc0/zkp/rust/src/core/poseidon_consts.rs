@@ -0,0 +1,201 @@
+// Copyright 2022 Risc0, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Constants of the Poseidon permutation over the base field of order
+//! `15*2^27 + 1`.
+//!
+//! Both tables are drawn from the Grain LFSR of the Poseidon paper,
+//! initialized with the field size (31 bits), the width (24), and the number
+//! of full (8) and partial (21) rounds: first the round constants,
+//! rejecting samples which are not less than the modulus, then the `2 * 24`
+//! distinct points `x_i`, `y_j` of the Cauchy matrix `1 / (x_i + y_j)`.
+
+/// The state width of the permutation, in field elements.
+pub const CELLS: usize = 24;
+
+/// The number of full rounds, half of which come before the partial rounds
+/// and half after.
+pub const ROUNDS_FULL: usize = 8;
+
+/// The number of partial rounds, which only apply the S-box to the first
+/// cell.
+pub const ROUNDS_PARTIAL: usize = 21;
+
+/// The constants added to the state before each round, [CELLS] per round.
+pub const ROUND_CONSTANTS: [u32; CELLS * (ROUNDS_FULL + ROUNDS_PARTIAL)] = [
+    262278199, 127253399, 314968988, 246143118, 157582794, 118043943, 454905424, 815798990,
+    1004040026, 1773108264, 1066694495, 1930780904, 1180307149, 1464793095, 1660766320, 1389166148,
+    343354132, 1307439985, 638242172, 525458520, 1964135730, 1751797115, 1421525369, 831813382,
+    695835963, 1845603984, 540703332, 1333667262, 1917861751, 1170029417, 1989924532, 1518763784,
+    1339793538, 622609176, 686842369, 1737016378, 1282239129, 897025192, 716894289, 1997503974,
+    395622276, 1201063290, 1917549072, 1150912935, 1687379185, 1507936940, 241306552, 989176635,
+    1147522062, 27129487, 1257820264, 142102402, 217046702, 1664590951, 855276054, 1215259350,
+    946500736, 552696906, 1424297384, 538103555, 1608853840, 162510541, 623051854, 1549062383,
+    1908416316, 1622328571, 1079030649, 1584033957, 1099252725, 1910423126, 447555988, 862495875,
+    128479034, 1587822577, 608401422, 1290028279, 342857858, 825405577, 427731030, 1718628547,
+    588764636, 204228775, 1454563174, 1740472809, 1338899225, 1269493554, 53007114, 1647670797,
+    306391314, 172614232, 51256176, 1221257987, 1239734761, 273790406, 1781980094, 1291790245,
+    497520322, 1930103076, 1052077299, 1540960371, 924863639, 1365519753, 1726563304, 440300254,
+    1891545577, 822033215, 1111544260, 308575117, 1708681573, 1240419708, 1199068823, 1186174623,
+    1551596046, 1886977120, 1327682690, 1210751726, 1810596765, 53041581, 723038058, 1439947916,
+    1136469704, 205609311, 1883820770, 14387587, 720724951, 1854174607, 1629316321, 530151394,
+    1679178250, 1549779579, 48375137, 976057819, 463976218, 875839332, 1946596189, 434078361,
+    1878280202, 1363837384, 1470845646, 1792450386, 1040977421, 1209164052, 714957516, 390340387,
+    1213686459, 790726260, 117294666, 140621810, 993455846, 1889603648, 78845751, 925018226,
+    708123747, 1647665372, 1649953458, 942439428, 1006235079, 238616145, 930036496, 1401020792,
+    989618631, 1545325389, 1715719711, 755691969, 150307788, 1567618575, 1663353317, 1950429111,
+    1891637550, 192082241, 1080533265, 1463323727, 890243564, 158646617, 1402624179, 59510015,
+    1198261138, 1065075039, 1150410028, 1293938517, 76770019, 1478577620, 1748789933, 457372011,
+    1841795381, 760115692, 1042892522, 1507649755, 1827572010, 1206940496, 1896271507, 1003792297,
+    738091882, 1124078057, 1889898, 813674331, 228520958, 1832911930, 781141772, 459826664,
+    202271745, 1296144415, 1111203133, 1090783436, 641665156, 1393671120, 1303271640, 809508074,
+    162506101, 1262312258, 1672219447, 1608891156, 1380248020, 555490988, 112090494, 1351808603,
+    671614470, 1987330347, 128914032, 1130840447, 1355488298, 264825773, 672574396, 1058448794,
+    1298349090, 1910233239, 1865103244, 154592857, 195689763, 1234605904, 1274894892, 530266673,
+    1631576439, 773264541, 88285098, 117895227, 1518407379, 877929693, 823242958, 192369619,
+    1759254541, 1028743759, 527617667, 1869138177, 972035075, 1044999316, 260923426, 999354987,
+    180682521, 951079326, 516337512, 1177699304, 894244668, 331131557, 675338309, 869676561,
+    1734188505, 1846653664, 1029860243, 101912695, 1045466369, 1609474319, 180316556, 172701782,
+    307097061, 1745215045, 1942994748, 1207489031, 1200291995, 1740512302, 733389557, 1660566353,
+    1092211140, 1162053316, 1173292890, 1493869455, 266137465, 92403633, 332882885, 102653983,
+    870122976, 603971906, 724151438, 1203995382, 1048083590, 1059106957, 1301503482, 977019871,
+    1302179073, 1314774492, 1915464449, 441570766, 669341367, 1067552737, 1829710168, 811202513,
+    587536855, 961737002, 1180802342, 1655237624, 1682944171, 580973526, 570259023, 847667273,
+    1083257841, 375892130, 111593399, 1867716111, 658182610, 51866718, 1928969210, 1942928018,
+    1558116382, 20525702, 1188752903, 106789799, 1389833584, 1001081700, 1792686147, 801504237,
+    1997365681, 1461037802, 65998481, 1974912881, 606789472, 13683277, 918610825, 1711450203,
+    438976048, 149438510, 1329755157, 1285591243, 288088075, 1754541169, 1525262809, 265815808,
+    1896429495, 572931466, 1765770021, 1524042136, 863795251, 1693706410, 1356741673, 1531789568,
+    1694313935, 1994637140, 472205185, 1426107658, 557777376, 92668567, 168822475, 1996786329,
+    1566713982, 364497158, 524906240, 12634080, 841704562, 663708359, 230812601, 1305848222,
+    853915962, 1580727053, 1208240351, 457653276, 1324360850, 30624868, 405006464, 358974769,
+    1528376025, 1498776016, 324505992, 1250803755, 293184808, 842187205, 1979871109, 600879446,
+    924154107, 1411089103, 1893083281, 907740982, 1955983665, 1298086959, 348728829, 146038311,
+    980751231, 1195128645, 1267846180, 215427161, 236971760, 325938356, 1988847481, 213503360,
+    334261497, 583555654, 1058048827, 413245576, 1046105695, 1134466690, 1634189787, 66021417,
+    1206665593, 887797965, 1903483286, 1735422201, 999301583, 1602908558, 1119705912, 496066664,
+    1195996835, 1963589100, 322973716, 1740477364, 490289563, 1066595225, 1210199014, 1628817086,
+    782318021, 1283307421, 1528195503, 1337337976, 1657827562, 1662824841, 136210395, 933134360,
+    325488629, 930204972, 1661646646, 1403419116, 308104397, 156475913, 922601695, 2008966811,
+    1531881466, 401227607, 1872774479, 133107365, 333164731, 422745921, 17310320, 1040042426,
+    89038513, 1608282027, 1757743453, 941665211, 415538854, 782112906, 719764110, 857139636,
+    295236934, 1833479308, 299528607, 1300853225, 1051566517, 841734586, 693288998, 907389318,
+    474871249, 1023570336, 293755700, 1140983186, 279825535, 1953645858, 444415175, 1006968146,
+    1753913242, 433318172, 1867339844, 993419909, 1001103348, 1336617011, 1404086572, 255173107,
+    174522548, 563930063, 1487580563, 22833522, 1656698707, 1275863723, 102079916, 1954183131,
+    304801400, 67830907, 225462907, 1098094157, 1574131931, 310658596, 434309073, 1416420948,
+    542938582, 1867123776, 1531134459, 1718319318, 71548538, 999823361, 1642260784, 141982099,
+    1929491374, 864691069, 1549940176, 1317484777, 985634333, 138022525, 838942164, 108106978,
+    211131242, 1666815111, 1784530620, 585566970, 830018372, 417046673, 1336987604, 1970003816,
+    1681775946, 389521626, 1268898383, 1046021040, 420757312, 1076039398, 881633447, 249620674,
+    802343609, 567820249, 372575445, 712308945, 1061489096, 1576988309, 1615578985, 1950891042,
+    1918433357, 954873624, 1561051207, 1192206504, 874998007, 1853702084, 742068765, 1826308784,
+    1367191182, 57118950, 905300999, 188948710, 1539716463, 1038024658, 1661421044, 1026114709,
+    207822320, 934840306, 327450161, 555203968, 833722432, 1019879911, 899546883, 889947965,
+    1846126060, 1915218698, 750132296, 1119439704, 1952718685, 726374159, 1591529737, 373757480,
+    1320019259, 360156120, 258201324, 1967259437, 1719829466, 1879386795, 1684272452, 366311406,
+    1666148280, 965070984, 72728031, 1009530448, 1379832268, 332627408, 1841622256, 30571725,
+    158921081, 1784548032, 90363221, 691600719, 1143770775, 674145770, 651382740, 1303038925,
+    1261087588, 435350602, 121422235, 2004294850, 1890436135, 1252722819, 1335110360, 1745807512,
+    1943888031, 792225600, 1514435954, 1180826620, 288942111, 776716325, 1263449775, 1138880868,
+    264564686, 1659681241, 872257220, 1771526689, 340306609, 539079469, 41295736, 919808922,
+    1240628843, 1799096581, 1052997216, 692820719, 1871820490, 1922132774, 99656842, 6444290,
+    368947973, 377311764, 1182396016, 1953338291, 1039435657, 1331586806, 1460667993, 1834032191,
+    1095002134, 883532894, 293115906, 242120003, 893626516, 656057743, 1813576382, 1729552513,
+    1316928927, 1791226258, 1535232125, 1297716477, 186507834, 1470213001, 761600655, 485391603,
+    783994008, 258112136, 283076917, 242313101, 886333754, 1736121148, 1199646756, 1103537534,
+    353638884, 1334953001, 1593112576, 1718547577, 849442650, 1913448800, 507484918, 1905100877,
+    437636391, 861766924, 1237946275, 1774979787, 486486841, 1409868548, 1315866222, 1004756277,
+    1042229509, 603052045, 1714300831, 339076259, 1977801989, 159933002, 930749189, 1982393493,
+    1627457601, 1119603897, 126680411, 1229946045, 1743259203, 700962576, 879695426, 1582346377,
+    1748671772, 1837486239, 1959272838, 705285120, 1459837508, 1481753234, 53211633, 697434228,
+    296949829, 979641589, 366122795, 1061145335, 65771057, 1738923932, 1807253439, 1255733430,
+];
+
+/// The MDS matrix applied to the state after each S-box layer, in row-major
+/// order.
+pub const MDS: [u32; CELLS * CELLS] = [
+    153481303, 1540555896, 132890210, 746672677, 1300552385, 1283037370, 981461577, 984196225,
+    1237391902, 81303453, 1606397409, 606724055, 657390254, 1190785002, 582774106, 1331887980,
+    215632861, 590427919, 39992173, 424966321, 937199544, 1508537932, 14997823, 704681371,
+    467790361, 576924517, 725198640, 1503609338, 734809842, 903163908, 1896430955, 369219819,
+    419387114, 1169364590, 644311628, 1818349672, 134503494, 1658530301, 1004902154, 1320148764,
+    1463814263, 1165584115, 1365655071, 675822513, 26652223, 1892372966, 106189022, 922084880,
+    229075283, 1667245692, 1687932734, 1561619478, 732304549, 1649717194, 1363270952, 974854188,
+    1507310988, 1507382868, 764614412, 1515690475, 412659791, 74252928, 1934021039, 1591588956,
+    237969, 360017305, 216885729, 392943938, 860669599, 81160067, 421221846, 88752048, 1930485397,
+    143037732, 355753896, 1313595994, 1161579682, 1693312473, 1885419819, 599047326, 630472158,
+    1123302431, 472779195, 453202984, 680253944, 1325243596, 757939784, 997169008, 1147047857,
+    880501047, 1255067853, 1254707151, 1241236065, 1870434948, 1176657132, 305858974, 1046238189,
+    1602372304, 1458457879, 510414795, 1597216916, 1330731507, 1727437509, 570739732, 1578431918,
+    1928013200, 1906907543, 726152558, 382594307, 123725931, 647441343, 986720350, 1102486893,
+    1468273893, 739318749, 1323156025, 1821095278, 340099303, 724982042, 1239803740, 1771684114,
+    1698772485, 818782714, 1025874533, 1480416927, 198190675, 27002951, 1112031315, 420110805,
+    259471849, 1887143173, 1062124713, 1672295590, 665386001, 1407029387, 1523325919, 679278283,
+    803214451, 1667983427, 1571811429, 1225576599, 1987492448, 795314073, 633823673, 27280307,
+    168211025, 1643778654, 1422550389, 120942526, 870459109, 1374034227, 1919470959, 455159853,
+    256571034, 1351594388, 1824846158, 1927118820, 1370812791, 903110147, 1778158747, 673091872,
+    533577850, 1990890082, 674193400, 1954584988, 1944309032, 736472604, 1572421398, 915734724,
+    692185335, 670350700, 55114456, 1784440779, 926528721, 661478238, 1624604994, 1321072920,
+    936363339, 726524111, 282460629, 982380557, 904436543, 1418570097, 66451863, 1751287175,
+    949166349, 604257454, 1528984434, 1055452550, 1086046011, 1828270988, 1875895448, 1514721120,
+    444561944, 1114675506, 451206296, 1764289324, 233067406, 1747354184, 1857874472, 238496784,
+    972370981, 634466254, 1293936293, 1272039009, 1126958916, 947131295, 353454830, 801552119,
+    1766462629, 1194681835, 958574117, 746929089, 599304967, 289460537, 1689809181, 1892990345,
+    675549392, 1265184976, 903327636, 1302583732, 1967829766, 986405731, 768083427, 480278373,
+    1453084370, 609724838, 313735625, 312408361, 1150796128, 1883970277, 1738544773, 323157041,
+    1527680681, 972763420, 1414086992, 972358828, 574701017, 1706834612, 1181944224, 1009947700,
+    1624133242, 1521139546, 884818862, 923678452, 1422813223, 1529840902, 1141035970, 703056349,
+    635920423, 1474806144, 1627047790, 22357931, 336834677, 955426529, 319684947, 210439173,
+    1945093828, 111018403, 997259576, 1889990328, 858339715, 1912495394, 523947210, 1518032784,
+    148717887, 161927282, 1735136690, 1102175905, 1595475530, 1851849987, 1958729676, 877445033,
+    1662817942, 690281572, 856907908, 219399076, 1333622442, 1351211554, 1495659171, 1984569779,
+    1497458122, 652196420, 1353595960, 1681739269, 1761991666, 63270235, 763193969, 1096523108,
+    452813812, 1980077187, 956156416, 188937496, 894848920, 1610944564, 1799794328, 442281591,
+    806724402, 394627710, 1156522478, 1029340448, 615812805, 1886695054, 1766745325, 1591071909,
+    130677688, 641761128, 781656811, 1762388394, 1538284646, 1936468541, 524534338, 509515690,
+    1738662902, 979591428, 1074585532, 272758994, 1492641702, 1377820617, 1408157519, 1244411993,
+    1657836345, 2005835488, 855615948, 1939972337, 1394570886, 689683192, 714803609, 786016615,
+    894997613, 1369533166, 1844346316, 1603153488, 488485640, 954754448, 1045774209, 867232631,
+    1828590865, 1405793383, 1640928173, 1405268671, 480901410, 820817296, 1165097670, 146719768,
+    217987573, 1478109177, 1935568870, 108818022, 1852451428, 1842511362, 606681042, 1003838164,
+    1452216987, 1676784126, 556534783, 447820605, 1417112429, 1113742750, 1004951993, 912757376,
+    172021880, 694252941, 1285001440, 267322913, 804862196, 1726574014, 1695909404, 1538717495,
+    270701444, 102214306, 1218606135, 620902960, 554618895, 458495324, 618494871, 1973659762,
+    886788193, 194158674, 576943785, 1090163100, 505220088, 405224837, 1544915308, 6984353,
+    989386173, 1149742430, 956857617, 1326813520, 732971223, 838178237, 1243629562, 633145153,
+    906071962, 1544347754, 496934168, 1108262415, 1485381549, 269636899, 1930344061, 1372603039,
+    182121887, 1908118589, 182417268, 559740320, 1682051811, 1122239108, 1014708824, 1665292653,
+    1720881914, 777199848, 1248010960, 810145172, 1496851510, 1283886106, 1257603048, 732314143,
+    895650045, 853788549, 138812370, 1863575494, 1140222531, 863676756, 2001840449, 32944814,
+    339098406, 1089605333, 1041006272, 1463686656, 1061326658, 1802512771, 1879332606, 730780941,
+    2001948975, 149552539, 594795950, 499063891, 1828693360, 885160342, 248949863, 736873507,
+    1771790144, 1946275373, 950059751, 1134639362, 950129195, 266973961, 787366707, 225837155,
+    1425962023, 1030236328, 429292305, 1708211140, 1312621090, 110982851, 786040474, 1222490496,
+    338272569, 867468028, 1318856168, 1785057197, 1255150785, 1250425178, 1308092714, 31228815,
+    74004742, 1079297487, 1493717949, 790951096, 1965185793, 583467679, 1206609292, 1841544153,
+    1260919387, 148690512, 224153857, 1862614450, 1477963677, 687019100, 314464781, 1235878775,
+    190443007, 381204201, 1424795696, 454522703, 655881523, 1738985393, 1219680617, 630386285,
+    109241620, 793476499, 697859653, 478844361, 1858424673, 180376006, 189089772, 664094865,
+    1406692304, 26260275, 1029572383, 533260681, 1225882743, 1614403919, 1003719904, 547696523,
+    554781301, 352277625, 88205077, 1201554227, 1353089754, 718454937, 1612781790, 1619931039,
+    1744310098, 1696349102, 601479432, 1341747980, 1032191625, 1319416990, 1610804988, 473674409,
+    1825314401, 1550093141, 666789407, 11216905, 444945790, 1229192332, 1687188001, 1191359148,
+    397851562, 750860471, 1365309965, 487159821, 296277642, 96862345, 318232168, 420424550,
+    1970421616, 924584605, 1952848464, 636800916, 1642333739, 1506286284, 805749276, 640825323,
+    311045100, 1846249113, 939127081, 915216261, 1577135488, 576613066, 666370052, 377590652,
+    700195557, 1053485006, 1860706443, 1536568126, 293107396, 1837873800, 1871229157, 1103112553,
+    1532995811, 1387530748, 792478511, 955984941, 1640744449, 756393954, 890505021, 480994682,
+    105918967, 167012547, 1777508114, 1972473225, 1795898368, 445250277, 405262907,
+];
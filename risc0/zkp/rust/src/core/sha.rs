@@ -18,7 +18,6 @@ use alloc::{string::String, vec::Vec};
 use core::{
     fmt::{Debug, Display, Formatter},
     mem,
-    ops::Deref,
 };
 
 use anyhow::{Error, Result};
@@ -146,55 +145,85 @@ impl Clone for Digest {
 }
 
 /// An implementation that provides SHA-256 hashing services.
+///
+/// Despite the name, the Merkle trees and Fiat-Shamir RNG of a seal only
+/// need a collision resistant hash with 256-bit digests, so other hashes
+/// can implement this too; see [poseidon](super::poseidon).
+///
+/// Digests are returned by value, so hashing never needs to allocate; this
+/// matters for Merkle trees and the Fiat-Shamir RNG, which hash millions of
+/// times per proof.
 pub trait Sha: Clone + Debug {
-    /// A pointer to the created digest.
-    ///
-    /// This may either be a Box<Digest> or some other pointer in case the
-    /// implementation wants to manage its own memory.
-    type DigestPtr: Deref<Target = Digest> + Debug;
-
     /// Generate a SHA from a slice of bytes, padding to block size
     /// and adding the SHA trailer.
-    fn hash_bytes(&self, bytes: &[u8]) -> Self::DigestPtr;
+    fn hash_bytes(&self, bytes: &[u8]) -> Digest;
 
     /// Generate a SHA from a slice of words, padding to block size
     /// and adding the SHA trailer.
-    fn hash_words(&self, words: &[u32]) -> Self::DigestPtr {
+    fn hash_words(&self, words: &[u32]) -> Digest {
         self.hash_bytes(bytemuck::cast_slice(words) as &[u8])
     }
 
     /// Generate a SHA from a slice of words without adding padding or
     /// length.
-    fn hash_raw_words(&self, words: &[u32]) -> Self::DigestPtr;
+    fn hash_raw_words(&self, words: &[u32]) -> Digest;
 
     /// Update a SHA digest with zero or more new blocks, zero padded
-    /// up to the next block boundry.  Not all implementations provide
-    /// this.
-    fn update(&self, state: &Digest, bytes: &[u8]) -> Self::DigestPtr;
+    /// up to the next block boundry.  Hashes without blocks absorb `bytes`
+    /// starting from `state` in their own way.
+    fn update(&self, state: &Digest, bytes: &[u8]) -> Digest;
 
     /// Generate a SHA from a pair of [Digests](Digest).
-    fn hash_pair(&self, a: &Digest, b: &Digest) -> Self::DigestPtr {
+    fn hash_pair(&self, a: &Digest, b: &Digest) -> Digest {
         self.compress(&SHA256_INIT, a, b)
     }
 
     /// Execute the sha256 "compress" operation.  The block is
-    /// specified as two half-blocks.  Not all implementations provide
-    /// this.
-    fn compress(
-        &self,
-        state: &Digest,
-        block_half1: &Digest,
-        block_half2: &Digest,
-    ) -> Self::DigestPtr;
+    /// specified as two half-blocks.  Hashes without blocks hash the
+    /// half-blocks starting from `state` in their own way.
+    fn compress(&self, state: &Digest, block_half1: &Digest, block_half2: &Digest) -> Digest;
+
+    /// Returns false if `digest` can't have been produced by this hash.
+    /// Verifiers reject seals holding such digests, since hashing them
+    /// further may treat them the same as some other digest.
+    fn is_canonical(&self, _digest: &Digest) -> bool {
+        true
+    }
 
     /// Generate a SHA from a slice of anything that can be
     /// represented as plain old data.  Pads up to the Sha block
     /// boundry, but does not add the standard SHA trailer.
-    fn hash_raw_pod_slice<T: bytemuck::Pod>(&self, fps: &[T]) -> Self::DigestPtr;
+    fn hash_raw_pod_slice<T: bytemuck::Pod>(&self, fps: &[T]) -> Digest;
+
+    /// Compute the hash of a slice of plain-old-data using the
+    /// specified offset and stride.  'size' specifies the number of
+    /// elements to hash.  This is the same as [Sha::hash_raw_pod_slice] of
+    /// the selected elements, which are collected into a new vector unless
+    /// the implementation can hash them in place.
+    fn hash_pod_stride<T: bytemuck::Pod>(
+        &self,
+        pods: &[T],
+        offset: usize,
+        size: usize,
+        stride: usize,
+    ) -> Digest {
+        let selected: Vec<T> = pods
+            .iter()
+            .skip(offset)
+            .step_by(stride)
+            .take(size)
+            .copied()
+            .collect();
+        self.hash_raw_pod_slice(&selected)
+    }
 
     /// Generate a new digest by mixing two digests together via XOR,
     /// and storing into the first digest.
-    fn mix(&self, pool: &mut Self::DigestPtr, val: &Digest);
+    fn mix(&self, pool: &mut Digest, val: &Digest) {
+        for (pool_word, val_word) in pool.get_mut().iter_mut().zip(val.get()) {
+            *pool_word ^= *val_word;
+        }
+    }
 }
 
 // Default implementation is CPU-based.
@@ -241,27 +270,27 @@ pub mod testutil {
     fn test_sha_basics<S: Sha>(sha: &S) {
         // Standard test vectors
         assert_eq!(
-            *sha.hash_bytes("abc".as_bytes()),
+            sha.hash_bytes("abc".as_bytes()),
             Digest::new([
                 0xba7816bf, 0x8f01cfea, 0x414140de, 0x5dae2223, 0xb00361a3, 0x96177a9c, 0xb410ff61,
                 0xf20015ad
             ])
         );
         assert_eq!(
-            *sha.hash_bytes("".as_bytes()),
+            sha.hash_bytes("".as_bytes()),
             Digest::new([
                 0xe3b0c442, 0x98fc1c14, 0x9afbf4c8, 0x996fb924, 0x27ae41e4, 0x649b934c, 0xa495991b,
                 0x7852b855
             ])
         );
         assert_eq!(
-            *sha.hash_bytes("abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq".as_bytes()),
+            sha.hash_bytes("abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq".as_bytes()),
             Digest::new([
                 0x248d6a61, 0xd20638b8, 0xe5c02693, 0x0c3e6039, 0xa33ce459, 0x64ff2167, 0xf6ecedd4,
                 0x19db06c1
             ])
         );
-        assert_eq!(*sha.hash_bytes(
+        assert_eq!(sha.hash_bytes(
             "abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu" .as_bytes()),
             Digest::new([0xcf5b16a7,
                        0x78af8380,
@@ -283,7 +312,7 @@ pub mod testutil {
 
     fn hash_fpvec<S: Sha>(sha: &S, len: usize) -> Digest {
         let items: Vec<Fp> = (0..len as u32).into_iter().map(|x| Fp::new(x)).collect();
-        *sha.hash_raw_pod_slice(items.as_slice())
+        sha.hash_raw_pod_slice(items.as_slice())
     }
 
     fn hash_fp4vec<S: Sha>(sha: &S, len: usize) -> Digest {
@@ -298,7 +327,7 @@ pub mod testutil {
                 )
             })
             .collect();
-        *sha.hash_raw_pod_slice(items.as_slice())
+        sha.hash_raw_pod_slice(items.as_slice())
     }
 
     fn test_fps<S: Sha>(sha: &S) {
@@ -335,7 +364,7 @@ pub mod testutil {
 
     fn test_hash_pair<S: Sha>(sha: &S) {
         assert_eq!(
-            *sha.hash_pair(
+            sha.hash_pair(
                 &Digest::from_str(
                     "6a09e667bb67ae853c6ef372a54ff53a510e527f9b05688c1f83d9ab5be0cd19"
                 ),
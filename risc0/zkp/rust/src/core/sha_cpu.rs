@@ -14,7 +14,7 @@
 
 //! Simple wrappers for a CPU-based SHA-256 implementation.
 
-use alloc::{boxed::Box, vec::Vec};
+use alloc::vec::Vec;
 use core::slice;
 
 use sha2::{
@@ -33,60 +33,11 @@ fn set_word(buf: &mut [u8], idx: usize, word: u32) {
     buf[(4 * idx)..(4 * idx + 4)].copy_from_slice(&word.to_le_bytes());
 }
 
-impl Impl {
-    /// Compute the hash of a slice of plain-old-data using the
-    /// specified offset and stride.  'size' specifies the number of
-    /// elements to hash.
-    pub fn hash_pod_stride<T: bytemuck::Pod>(
-        &self,
-        pods: &[T],
-        offset: usize,
-        size: usize,
-        stride: usize,
-    ) -> Box<Digest> {
-        let mut state = *SHA256_INIT.get();
-        let mut block: GenericArray<u8, U64> = GenericArray::default();
-
-        let mut u8s = pods
-            .iter()
-            .skip(offset)
-            .step_by(stride)
-            .take(size)
-            .flat_map(|pod| bytemuck::cast_slice(slice::from_ref(pod)) as &[u8])
-            .cloned()
-            .fuse();
-
-        let mut off = 0;
-        while let Some(b1) = u8s.next() {
-            let b2 = u8s.next().unwrap_or(0);
-            let b3 = u8s.next().unwrap_or(0);
-            let b4 = u8s.next().unwrap_or(0);
-            set_word(
-                block.as_mut_slice(),
-                off,
-                u32::from_le_bytes([b1, b2, b3, b4]),
-            );
-            off += 1;
-            if off == 16 {
-                compress256(&mut state, slice::from_ref(&block));
-                off = 0;
-            }
-        }
-        if off != 0 {
-            block[off * 4..].fill(0);
-            compress256(&mut state, slice::from_ref(&block));
-        }
-        Box::new(Digest::new(state))
-    }
-}
-
 impl Sha for Impl {
-    type DigestPtr = Box<Digest>;
-
-    fn hash_bytes(&self, bytes: &[u8]) -> Self::DigestPtr {
+    fn hash_bytes(&self, bytes: &[u8]) -> Digest {
         let mut hasher = Sha256::new();
         hasher.update(bytes);
-        Box::new(Digest::new(
+        Digest::new(
             hasher
                 .finalize()
                 .as_slice()
@@ -95,14 +46,14 @@ impl Sha for Impl {
                 .collect::<Vec<u32>>()
                 .try_into()
                 .unwrap(),
-        ))
+        )
     }
 
-    fn hash_words(&self, words: &[u32]) -> Self::DigestPtr {
+    fn hash_words(&self, words: &[u32]) -> Digest {
         self.hash_bytes(bytemuck::cast_slice(words) as &[u8])
     }
 
-    fn hash_raw_words(&self, words: &[u32]) -> Self::DigestPtr {
+    fn hash_raw_words(&self, words: &[u32]) -> Digest {
         assert!(
             words.len() % 16 == 0,
             "{} should be a multiple of 16, the number of words per SHA block",
@@ -116,10 +67,10 @@ impl Sha for Impl {
                 slice::from_ref(GenericArray::from_slice(block_u8)),
             )
         }
-        Box::new(Digest::new(state))
+        Digest::new(state)
     }
 
-    fn hash_raw_pod_slice<T: bytemuck::Pod>(&self, pod: &[T]) -> Self::DigestPtr {
+    fn hash_raw_pod_slice<T: bytemuck::Pod>(&self, pod: &[T]) -> Digest {
         let u8s: &[u8] = bytemuck::cast_slice(pod);
         let mut state = *SHA256_INIT.get();
         let mut blocks = u8s.chunks_exact(64);
@@ -133,16 +84,53 @@ impl Sha for Impl {
                 .clone_from_slice(remainder);
             compress256(&mut state, slice::from_ref(&last_block));
         }
-        Box::new(Digest::new(state))
+        Digest::new(state)
     }
 
-    // Digest two digest into one
-    fn compress(
+    fn hash_pod_stride<T: bytemuck::Pod>(
         &self,
-        orig_state: &Digest,
-        block_half1: &Digest,
-        block_half2: &Digest,
-    ) -> Self::DigestPtr {
+        pods: &[T],
+        offset: usize,
+        size: usize,
+        stride: usize,
+    ) -> Digest {
+        let mut state = *SHA256_INIT.get();
+        let mut block: GenericArray<u8, U64> = GenericArray::default();
+
+        let mut u8s = pods
+            .iter()
+            .skip(offset)
+            .step_by(stride)
+            .take(size)
+            .flat_map(|pod| bytemuck::cast_slice(slice::from_ref(pod)) as &[u8])
+            .cloned()
+            .fuse();
+
+        let mut off = 0;
+        while let Some(b1) = u8s.next() {
+            let b2 = u8s.next().unwrap_or(0);
+            let b3 = u8s.next().unwrap_or(0);
+            let b4 = u8s.next().unwrap_or(0);
+            set_word(
+                block.as_mut_slice(),
+                off,
+                u32::from_le_bytes([b1, b2, b3, b4]),
+            );
+            off += 1;
+            if off == 16 {
+                compress256(&mut state, slice::from_ref(&block));
+                off = 0;
+            }
+        }
+        if off != 0 {
+            block[off * 4..].fill(0);
+            compress256(&mut state, slice::from_ref(&block));
+        }
+        Digest::new(state)
+    }
+
+    // Digest two digest into one
+    fn compress(&self, orig_state: &Digest, block_half1: &Digest, block_half2: &Digest) -> Digest {
         let mut state: [u32; DIGEST_WORDS] = *orig_state.get();
         let mut block: GenericArray<u8, U64> = GenericArray::default();
         for i in 0..8 {
@@ -150,10 +138,10 @@ impl Sha for Impl {
             set_word(block.as_mut_slice(), 8 + i, block_half2.as_slice()[i]);
         }
         compress256(&mut state, slice::from_ref(&block));
-        Box::new(Digest::new(state))
+        Digest::new(state)
     }
 
-    fn update(&self, orig_state: &Digest, bytes: &[u8]) -> Self::DigestPtr {
+    fn update(&self, orig_state: &Digest, bytes: &[u8]) -> Digest {
         let mut state = *orig_state.get();
         let mut block: GenericArray<u8, U64> = GenericArray::default();
 
@@ -178,16 +166,7 @@ impl Sha for Impl {
             block[off * 4..].fill(0);
             compress256(&mut state, slice::from_ref(&block));
         }
-        Box::new(Digest::new(state))
-    }
-
-    // Generate a new digest by mixing two digests together via XOR,
-    // and stores it back in the pool.
-    fn mix(&self, pool: &mut Self::DigestPtr, val: &Digest) {
-        // CPU based sha can do this in place without generating another digest pointer.
-        for (pool_word, val_word) in pool.get_mut().iter_mut().zip(val.get()) {
-            *pool_word ^= *val_word;
-        }
+        Digest::new(state)
     }
 }
 
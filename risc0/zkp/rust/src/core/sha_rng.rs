@@ -23,8 +23,8 @@ use super::sha::{Digest, Sha, DIGEST_WORDS};
 #[derive(Clone, Debug)]
 pub struct ShaRng<S: Sha> {
     sha: S,
-    pool0: S::DigestPtr,
-    pool1: S::DigestPtr,
+    pool0: Digest,
+    pool1: Digest,
     pool_used: usize,
 }
 
@@ -91,7 +91,7 @@ pub mod testutil {
             x.next_u32();
         }
         assert_eq!(x.next_u32(), 1826198275);
-        x.mix(&*sha.hash_bytes(b"foo"));
+        x.mix(&sha.hash_bytes(b"foo"));
         assert_eq!(x.next_u32(), 1753965479);
     }
 }
@@ -19,6 +19,7 @@ use alloc::{fmt, vec::Vec};
 use core::ops;
 
 use bytemuck::{Pod, Zeroable};
+use serde::{Deserialize, Serialize};
 
 use crate::field::{self, Elem as FieldElem};
 
@@ -54,7 +55,7 @@ const R2: u32 = 1172168163;
 ///
 /// The Fp class wraps all the standard arithmetic operations to make the finite
 /// field elements look basically like ordinary numbers (which they mostly are).
-#[derive(Eq, PartialEq, Clone, Copy, Pod, Zeroable)]
+#[derive(Eq, PartialEq, Clone, Copy, Pod, Zeroable, Serialize, Deserialize)]
 #[repr(transparent)]
 pub struct Elem(u32);
 
@@ -71,7 +72,7 @@ impl fmt::Debug for Elem {
 }
 
 /// The modulus of the field.
-const P: u32 = 15 * (1 << 27) + 1;
+pub const P: u32 = 15 * (1 << 27) + 1;
 
 /// The modulus of the field as a u64.
 const P_U64: u64 = P as u64;
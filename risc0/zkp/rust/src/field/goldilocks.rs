@@ -22,6 +22,15 @@ use bytemuck::{Pod, Zeroable};
 
 use crate::field::{self, Elem as FieldElem};
 
+/// Definition of this field for operations that operate on the Goldilocks
+/// field and its 2nd degree extension.
+pub struct Goldilocks;
+
+impl field::Field for Goldilocks {
+    type Elem = Elem;
+    type ExtElem = ExtElem;
+}
+
 /// The Goldilocks class is an element of the finite field F_p, where P is the
 /// prime number 2^64 - 2^32 + 1. Here we implement integer
 /// arithmetic modulo P for both Goldilocks and for a field extension of
@@ -36,8 +45,11 @@ use crate::field::{self, Elem as FieldElem};
 /// The Fp class wraps all standard arithmetic operations to make finite
 /// field elements appear like ordinary numbers (which, for the most part, they
 /// are).
+///
+/// Elements are only 4-byte aligned, like the u32 words of a seal, so that
+/// they can be read from a seal in place.
 #[derive(Eq, PartialEq, Clone, Copy, Debug, Pod, Zeroable)]
-#[repr(transparent)]
+#[repr(C, packed(4))]
 pub struct Elem(u64);
 
 impl Default for Elem {
@@ -35,16 +35,26 @@ use crate::{
         sha_cpu,
     },
     field::{Elem, ExtElem, Field},
-    FRI_FOLD,
 };
 
-pub struct CpuHal<F: Field> {
+/// A [Hal] which runs on the CPU, and builds Merkle trees with the hash `S`.
+pub struct CpuHal<F: Field, S: Sha = sha_cpu::Impl> {
+    sha: S,
     _phantom: PhantomData<F>,
 }
 
 impl<F: Field> CpuHal<F> {
     pub fn new() -> Self {
+        Self::new_with_sha(sha_cpu::Impl {})
+    }
+}
+
+impl<F: Field, S: Sha> CpuHal<F, S> {
+    /// Like [CpuHal::new], but builds Merkle trees with `sha`.  The prover
+    /// must use the same hash for its IOP.
+    pub fn new_with_sha(sha: S) -> Self {
         CpuHal {
+            sha,
             _phantom: PhantomData,
         }
     }
@@ -137,7 +147,7 @@ impl<T: Pod> Buffer<T> for CpuBuffer<T> {
     }
 }
 
-impl<F: Field> Hal for CpuHal<F> {
+impl<F: Field, S: Sha + Sync> Hal for CpuHal<F, S> {
     type Field = F;
 
     type BufferFp = CpuBuffer<F::Elem>;
@@ -199,7 +209,7 @@ impl<F: Field> Hal for CpuHal<F> {
         io.as_slice_mut()
             .par_chunks_exact_mut(row_size)
             .for_each(|row| {
-                evaluate_ntt(Self::to_baby_bear_fp_slice_mut(row), expand_bits);
+                evaluate_ntt::<F::Elem, F::Elem>(row, expand_bits);
             });
     }
 
@@ -209,7 +219,7 @@ impl<F: Field> Hal for CpuHal<F> {
         io.as_slice_mut()
             .par_chunks_exact_mut(row_size)
             .for_each(|row| {
-                interpolate_ntt(Self::to_baby_bear_fp_slice_mut(row));
+                interpolate_ntt::<F::Elem, F::Elem>(row);
             });
     }
 
@@ -363,7 +373,9 @@ impl<F: Field> Hal for CpuHal<F> {
     fn fri_fold(&self, output: &CpuBuffer<F::Elem>, input: &CpuBuffer<F::Elem>, mix: &F::ExtElem) {
         let count = output.size() / F::ExtElem::EXT_SIZE;
         assert_eq!(output.size(), count * F::ExtElem::EXT_SIZE);
-        assert_eq!(input.size(), output.size() * FRI_FOLD);
+        let fold = input.size() / output.size();
+        assert!(fold.is_power_of_two());
+        assert_eq!(input.size(), output.size() * fold);
         let mut output = output.as_slice_mut();
         let input = input.as_slice();
 
@@ -371,11 +383,11 @@ impl<F: Field> Hal for CpuHal<F> {
         for idx in 0..count {
             let mut tot = F::ExtElem::ZERO;
             let mut cur_mix = F::ExtElem::ONE;
-            for i in 0..FRI_FOLD {
-                let rev_i = bit_rev_32(i as u32) >> (32 - log2_ceil(FRI_FOLD));
+            for i in 0..fold {
+                let rev_i = bit_rev_32(i as u32) >> (32 - log2_ceil(fold));
                 let rev_idx = rev_i as usize * count + idx;
                 let factor = F::ExtElem::from_subelems(
-                    (0..F::ExtElem::EXT_SIZE).map(|i| input[i * count * FRI_FOLD + rev_idx]),
+                    (0..F::ExtElem::EXT_SIZE).map(|i| input[i * count * fold + rev_idx]),
                 );
                 tot += cur_mix * factor;
                 cur_mix *= *mix;
@@ -392,17 +404,16 @@ impl<F: Field> Hal for CpuHal<F> {
         assert_eq!(matrix.size(), col_size * count);
         let mut output = output.as_slice_mut();
         let matrix = matrix.as_slice().to_vec(); // TODO: avoid copy
-        let fp_matrix = CpuHal::<F>::to_baby_bear_fp_slice(matrix.as_slice());
-        let sha = sha_cpu::Impl {};
+        let sha = &self.sha;
         output.par_iter_mut().enumerate().for_each(|(idx, output)| {
-            *output = *sha.hash_pod_stride(fp_matrix, idx, col_size, count);
+            *output = sha.hash_pod_stride(&matrix, idx, col_size, count);
         });
     }
 
     fn sha_fold(&self, io: &CpuBuffer<Digest>, input_size: usize, output_size: usize) {
         assert_eq!(input_size, 2 * output_size);
         let mut io = io.as_slice_mut();
-        let sha = sha_cpu::Impl {};
+        let sha = &self.sha;
         let (output, input) = unsafe {
             (
                 from_raw_parts_mut(io.as_mut_ptr().add(output_size), output_size),
@@ -413,7 +424,7 @@ impl<F: Field> Hal for CpuHal<F> {
             .par_iter_mut()
             .zip(input.par_chunks_exact(2))
             .for_each(|(output, input)| {
-                *output = *sha.hash_pair(&input[0], &input[1]);
+                *output = sha.hash_pair(&input[0], &input[1]);
             });
     }
 }
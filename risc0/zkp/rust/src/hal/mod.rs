@@ -16,10 +16,7 @@
 
 pub mod cpu;
 
-use crate::{
-    core::sha::Digest,
-    field::{self, baby_bear::BabyBear},
-};
+use crate::{core::sha::Digest, field};
 
 pub trait Buffer<T>: Clone {
     fn size(&self) -> usize;
@@ -31,59 +28,6 @@ pub trait Buffer<T>: Clone {
     fn view_mut<F: FnOnce(&mut [T])>(&self, f: F);
 }
 
-// Baby bear adapters to facilitate migration to generics.  They
-// convert between a genericizied baby bear type and a concreate baby
-// bear type.  Once everything uses genericized types, we can get rid
-// of these.
-//
-// If called with a non-baby-bear field, they will produce an error at
-// runtime.
-//
-// TODO: Make it so these aren't necessary anymore.
-macro_rules! baby_bear_adapter {
-    ($fn_name:ident, $slice_fn_name:ident, $mut_slice_fn_name:ident, $from_ty:ty, $to_ty: ty $(,)?) => {
-        fn $fn_name(val: $from_ty) -> $to_ty {
-            let val_any = &val as &dyn core::any::Any;
-            if let Some(val) = val_any.downcast_ref::<$to_ty>() {
-                *val
-            } else {
-                panic!("Unable to convert between baby bear types {} and {}; other fields not supported yet.",
-                       core::any::type_name::<$from_ty>(),
-                       core::any::type_name::<$to_ty>());
-            }
-        }
-
-        // Rust doesn't let us put a reference in an any, so we have
-        // to resort to unsafe.  Specify lifetimes explicitly here
-        // to avoid any confusion.
-        fn $slice_fn_name<'a>(val: &'a [$from_ty]) -> &'a [$to_ty] {
-            let len = val.len();
-            if core::any::TypeId::of::<$from_ty>() == core::any::TypeId::of::<$to_ty>() {
-                let ptr: *const $from_ty = val.as_ptr();
-                let out: *const $to_ty = ptr.cast();
-                unsafe { core::slice::from_raw_parts(out, len) }
-            } else {
-                panic!("Unable to convert between baby bear types &[{}] and &[{}]; other fields not supported yet.",
-                       core::any::type_name::<$from_ty>(),
-                       core::any::type_name::<$to_ty>());
-            }
-        }
-
-        fn $mut_slice_fn_name<'a>(val: &'a mut [$from_ty]) -> &'a mut [$to_ty] {
-            let len = val.len();
-            if core::any::TypeId::of::<$from_ty>() == core::any::TypeId::of::<$to_ty>() {
-                let ptr: *mut $from_ty = val.as_mut_ptr();
-                let out: *mut $to_ty = ptr.cast();
-                unsafe { core::slice::from_raw_parts_mut(out, len) }
-            } else {
-                panic!("Unable to convert between baby bear types &mut [{}] and &mut [{}]; other fields not supported yet.",
-                       core::any::type_name::<$from_ty>(),
-                       core::any::type_name::<$to_ty>());
-            }
-        }
-    };
-}
-
 pub trait Hal {
     type Field: field::Field;
     type BufferDigest: Buffer<Digest>;
@@ -144,6 +88,9 @@ pub trait Hal {
 
     fn eltwise_copy_digest(&self, output: &Self::BufferDigest, input: &Self::BufferDigest);
 
+    /// Fold the FRI polynomial `input` into `output`, mixing together each
+    /// group of coefficients with powers of `mix`.  The number of coefficients
+    /// in each group is the ratio of the sizes of `input` and `output`.
     fn fri_fold(
         &self,
         output: &Self::BufferFp,
@@ -154,40 +101,11 @@ pub trait Hal {
     fn sha_rows(&self, output: &Self::BufferDigest, matrix: &Self::BufferFp);
 
     fn sha_fold(&self, io: &Self::BufferDigest, input_size: usize, output_size: usize);
-
-    // Adapters to convert to/from baby bear field until all code is migrated.
-    baby_bear_adapter!(
-        to_baby_bear_fp,
-        to_baby_bear_fp_slice,
-        to_baby_bear_fp_slice_mut,
-        <Self::Field as field::Field>::Elem,
-        <BabyBear as field::Field>::Elem,
-    );
-    baby_bear_adapter!(
-        to_baby_bear_fp4,
-        to_baby_bear_fp4_slice,
-        to_baby_bear_fp4_slice_mut,
-        <Self::Field as field::Field>::ExtElem,
-        <BabyBear as field::Field>::ExtElem,
-    );
-    baby_bear_adapter!(
-        from_baby_bear_fp,
-        from_baby_bear_fp_slice,
-        from_baby_bear_fp_slice_mut,
-        <BabyBear as field::Field>::Elem,
-        <Self::Field as field::Field>::Elem,
-    );
-    baby_bear_adapter!(
-        from_baby_bear_fp4,
-        from_baby_bear_fp4_slice,
-        from_baby_bear_fp4_slice_mut,
-        <BabyBear as field::Field>::ExtElem,
-        <Self::Field as field::Field>::ExtElem,
-    );
 }
 
 pub trait EvalCheck<H: Hal> {
-    /// Compute check polynomial.
+    /// Compute check polynomial, over a domain of `steps * inv_rate`
+    /// points.
     fn eval_check(
         &self,
         check: &H::BufferFp,
@@ -199,5 +117,6 @@ pub trait EvalCheck<H: Hal> {
         poly_mix: <H::Field as field::Field>::ExtElem,
         po2: usize,
         steps: usize,
+        inv_rate: usize,
     );
 }
@@ -30,22 +30,23 @@ pub mod taps;
 pub mod verify;
 
 pub mod field;
+mod params;
+
+pub use params::ProofParams;
 
 pub const MIN_CYCLES: usize = 512;
 pub const MAX_CYCLES_PO2: usize = 24;
 pub const MAX_CYCLES: usize = 1 << MAX_CYCLES_PO2;
 
-/// ~100 bits of conjectured security
+/// The default number of queries; with the default [INV_RATE], this gives
+/// ~100 bits of conjectured security.  See [ProofParams].
 pub const QUERIES: usize = 50;
+/// The number of cycles of random padding at the end of the trace, which is
+/// also the most queries a proof can make while staying zero-knowledge.
 pub const ZK_CYCLES: usize = QUERIES;
 pub const MIN_PO2: usize = core::log2_ceil(1 + ZK_CYCLES);
 
+/// The default inverse rate.  See [ProofParams].
 pub const INV_RATE: usize = 4;
-#[cfg(any(feature = "prove", feature = "verify"))]
 const FRI_FOLD_PO2: usize = 4;
-#[cfg(any(feature = "prove", feature = "verify"))]
-const FRI_FOLD: usize = 1 << FRI_FOLD_PO2;
-#[cfg(any(feature = "prove", feature = "verify"))]
 const FRI_MIN_DEGREE: usize = 256;
-#[cfg(any(feature = "prove", feature = "verify"))]
-const CHECK_SIZE: usize = INV_RATE * core::fp4::EXT_SIZE;
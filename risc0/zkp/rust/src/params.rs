@@ -0,0 +1,201 @@
+// Copyright 2022 Risc0, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parameters which trade off proof size, prover time and security.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::log2_ceil,
+    field::{Field, RootsOfUnity},
+    FRI_FOLD_PO2, FRI_MIN_DEGREE, INV_RATE, MAX_CYCLES_PO2, QUERIES, ZK_CYCLES,
+};
+
+// Bounds the folding factor, so that a malformed seal can't make the
+// verifier allocate huge FRI rounds.
+const MAX_FRI_FOLD_PO2: usize = 8;
+
+/// The parameters of a proof, which are recorded at the end of its seal.
+///
+/// The conjectured security of a proof is [ProofParams::security_bits], so
+/// for example a higher `inv_rate` allows fewer `queries`, which gives a
+/// smaller seal in exchange for more proving time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofParams {
+    /// The number of FRI queries.  The trace reserves [ZK_CYCLES] cycles
+    /// of random padding to keep queries zero-knowledge, so this may be at
+    /// most [ZK_CYCLES].
+    pub queries: usize,
+
+    /// The inverse of the Reed-Solomon code rate, i.e. the blowup of each
+    /// polynomial when it is committed.  This must be a power of two, and at
+    /// least the degree of the circuit's constraints.
+    pub inv_rate: usize,
+
+    /// The log base 2 of the number of elements folded together by each
+    /// FRI round.
+    pub fri_fold_po2: usize,
+
+    /// FRI stops folding once the degree of the polynomial is at most this,
+    /// and sends its coefficients instead.  This must be a power of two, no
+    /// less than the folding factor.
+    pub fri_min_degree: usize,
+}
+
+impl ProofParams {
+    /// The number of words at the end of a seal which record its
+    /// [ProofParams].
+    pub const SEAL_WORDS: usize = 4;
+
+    /// The number of elements folded together by each FRI round.
+    pub fn fri_fold(&self) -> usize {
+        1 << self.fri_fold_po2
+    }
+
+    /// The conjectured bits of security: each query has a chance of
+    /// `1 / inv_rate` to miss a cheating prover.
+    pub fn security_bits(&self) -> usize {
+        self.queries * log2_ceil(self.inv_rate)
+    }
+
+    /// The largest po2 which can be proven over the field `F` with these
+    /// parameters, since the domain of each polynomial is `inv_rate` times
+    /// larger than the trace and needs a root of unity of that order.
+    pub fn max_po2<F: Field>(&self) -> usize {
+        let rou_po2 = F::Elem::MAX_ROU_PO2.saturating_sub(log2_ceil(self.inv_rate));
+        MAX_CYCLES_PO2.min(rou_po2)
+    }
+
+    /// Check that these parameters can be used for a proof.
+    pub fn validate(&self) -> Result<()> {
+        if self.queries == 0 || self.queries > ZK_CYCLES {
+            bail!(
+                "queries must be between 1 and {ZK_CYCLES}, not {}",
+                self.queries
+            );
+        }
+        if self.inv_rate < 2 || !self.inv_rate.is_power_of_two() {
+            bail!("inv_rate must be a power of two, not {}", self.inv_rate);
+        }
+        if self.fri_fold_po2 == 0 || self.fri_fold_po2 > MAX_FRI_FOLD_PO2 {
+            bail!(
+                "fri_fold_po2 must be between 1 and {MAX_FRI_FOLD_PO2}, not {}",
+                self.fri_fold_po2
+            );
+        }
+        if !self.fri_min_degree.is_power_of_two() || self.fri_min_degree < self.fri_fold() {
+            bail!(
+                "fri_min_degree must be a power of two no less than {}, not {}",
+                self.fri_fold(),
+                self.fri_min_degree
+            );
+        }
+        Ok(())
+    }
+
+    /// Encode these parameters as they are recorded in a seal.
+    pub fn to_words(&self) -> [u32; Self::SEAL_WORDS] {
+        [
+            self.queries as u32,
+            self.inv_rate as u32,
+            self.fri_fold_po2 as u32,
+            self.fri_min_degree as u32,
+        ]
+    }
+
+    /// Whether the prover commits these parameters to the transcript before
+    /// anything else, binding the Fiat-Shamir challenges to them.  The
+    /// defaults are left out, so that seals made before the parameters were
+    /// configurable still verify; relabelling a seal with other parameters
+    /// changes its transcript either way.
+    pub fn is_committed(&self) -> bool {
+        *self != Self::default()
+    }
+
+    /// Read and validate the parameters recorded at the end of `seal`.
+    pub fn from_seal(seal: &[u32]) -> Result<Self> {
+        if seal.len() < Self::SEAL_WORDS {
+            bail!("Seal is too short to hold proof parameters");
+        }
+        let words = &seal[seal.len() - Self::SEAL_WORDS..];
+        let params = ProofParams {
+            queries: words[0] as usize,
+            inv_rate: words[1] as usize,
+            fri_fold_po2: words[2] as usize,
+            fri_min_degree: words[3] as usize,
+        };
+        params.validate()?;
+        Ok(params)
+    }
+}
+
+impl Default for ProofParams {
+    fn default() -> Self {
+        ProofParams {
+            queries: QUERIES,
+            inv_rate: INV_RATE,
+            fri_fold_po2: FRI_FOLD_PO2,
+            fri_min_degree: FRI_MIN_DEGREE,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::ProofParams;
+
+    #[test]
+    fn default_is_valid() {
+        let params = ProofParams::default();
+        params.validate().unwrap();
+        assert_eq!(params.security_bits(), 100);
+        let mut seal = vec![7, 8, 9];
+        seal.extend(params.to_words());
+        assert_eq!(ProofParams::from_seal(&seal).unwrap(), params);
+    }
+
+    #[test]
+    fn invalid() {
+        let default = ProofParams::default();
+        for params in [
+            ProofParams {
+                queries: 0,
+                ..default
+            },
+            ProofParams {
+                queries: crate::ZK_CYCLES + 1,
+                ..default
+            },
+            ProofParams {
+                inv_rate: 6,
+                ..default
+            },
+            ProofParams {
+                fri_fold_po2: 64,
+                ..default
+            },
+            ProofParams {
+                fri_min_degree: 8,
+                ..default
+            },
+        ] {
+            assert!(params.validate().is_err());
+            assert!(ProofParams::from_seal(&params.to_words()).is_err());
+        }
+        assert!(ProofParams::from_seal(&[1, 2]).is_err());
+    }
+}
@@ -14,12 +14,12 @@
 
 use core::marker::PhantomData;
 
-use rand::thread_rng;
+use rand::{thread_rng, RngCore};
 
 use crate::{
     adapter::{CircuitDef, CircuitStepContext, CustomStep},
     core::{fp::Fp, sha::Sha},
-    field::Elem,
+    field::{baby_bear::BabyBear, Elem},
     prove::{executor::Executor, write_iop::WriteIOP, Circuit},
     taps::{RegisterGroup, TapSet},
     ZK_CYCLES,
@@ -30,23 +30,34 @@ pub struct ProveAdapter<'a, C: 'static + CircuitDef<S>, S: CustomStep> {
     mix: Vec<Fp>,
     accum: Vec<Fp>,
     steps: usize,
+    // The source of the noise which makes the accum columns zero-knowledge.
+    rng: Box<dyn RngCore + 'a>,
     _custom: PhantomData<S>,
 }
 
 impl<'a, C: CircuitDef<S>, S: CustomStep> ProveAdapter<'a, C, S> {
     pub fn new(exec: &'a mut Executor<C, S>) -> Self {
+        Self::new_with_rng(exec, thread_rng())
+    }
+
+    /// Like [ProveAdapter::new], but draws the random noise which makes the
+    /// proof zero-knowledge from `rng`.  A seeded `rng` makes the seal
+    /// reproducible, but then the noise hides nothing, so this is only for
+    /// testing.
+    pub fn new_with_rng(exec: &'a mut Executor<C, S>, rng: impl RngCore + 'a) -> Self {
         let steps = exec.steps;
         ProveAdapter {
             exec,
             mix: Vec::new(),
             accum: Vec::new(),
             steps,
+            rng: Box::new(rng),
             _custom: PhantomData,
         }
     }
 }
 
-impl<'a, C: CircuitDef<CS>, CS: CustomStep> Circuit for ProveAdapter<'a, C, CS> {
+impl<'a, C: CircuitDef<CS>, CS: CustomStep> Circuit<BabyBear> for ProveAdapter<'a, C, CS> {
     fn get_taps(&self) -> &'static TapSet<'static> {
         self.exec.circuit.get_taps()
     }
@@ -88,10 +99,9 @@ impl<'a, C: CircuitDef<CS>, CS: CustomStep> Circuit for ProveAdapter<'a, C, CS>
                 .unwrap();
         }
         // Add random noise to end of accum
-        let mut rng = thread_rng();
         for i in self.steps - ZK_CYCLES..self.steps {
             for j in 0..accum_size {
-                self.accum[j * self.steps + i] = Fp::random(&mut rng);
+                self.accum[j * self.steps + i] = Fp::random(&mut self.rng);
             }
         }
     }
@@ -16,7 +16,7 @@ use core::cmp::max;
 
 use anyhow::{bail, Result};
 use log::debug;
-use rand::thread_rng;
+use rand::{thread_rng, Rng};
 
 use crate::{
     adapter::{CircuitDef, CircuitStepContext, CustomStep},
@@ -39,6 +39,8 @@ pub struct Executor<C: 'static + CircuitDef<S>, S: CustomStep> {
     pub halted: bool,
     max_po2: usize,
     pub cycle: usize,
+    /// The cycle at which the circuit first reported a halt.
+    pub halt_cycle: usize,
 }
 
 impl<C: 'static + CircuitDef<S>, S: CustomStep> Executor<C, S> {
@@ -64,9 +66,47 @@ impl<C: 'static + CircuitDef<S>, S: CustomStep> Executor<C, S> {
             halted: false,
             max_po2,
             cycle: 0,
+            halt_cycle: 0,
         }
     }
 
+    /// Reconstruct a finalized executor from a previously recorded trace so
+    /// that it can be proven.
+    pub fn from_trace(
+        circuit: &'static C,
+        custom: S,
+        code: Vec<Fp>,
+        data: Vec<Fp>,
+        output: Vec<Fp>,
+        po2: usize,
+    ) -> Result<Self> {
+        let taps = circuit.get_taps();
+        let code_size = taps.group_size(RegisterGroup::Code);
+        let data_size = taps.group_size(RegisterGroup::Data);
+        let steps = 1 << po2;
+        if code.len() != steps * code_size
+            || data.len() != steps * data_size
+            || output.len() != C::OUTPUT_SIZE
+        {
+            bail!("Trace does not match circuit with po2 of {po2}");
+        }
+        Ok(Executor {
+            circuit,
+            custom,
+            code,
+            code_size,
+            data,
+            data_size,
+            output,
+            po2,
+            steps,
+            halted: true,
+            max_po2: po2,
+            cycle: steps - ZK_CYCLES,
+            halt_cycle: steps - ZK_CYCLES,
+        })
+    }
+
     pub fn step(&mut self, code: &[Fp], needed_fini: usize) -> Result<bool> {
         // debug!("code: {:?}", code);
         let next_cycles = self.cycle + needed_fini + ZK_CYCLES;
@@ -97,7 +137,10 @@ impl<C: 'static + CircuitDef<S>, S: CustomStep> Executor<C, S> {
         ];
         let result = self.circuit.step_exec(&ctx, &mut self.custom, args)?;
         // debug!("result: {:?}", result);
-        self.halted = self.halted || result == Fp::ZERO;
+        if !self.halted && result == Fp::ZERO {
+            self.halted = true;
+            self.halt_cycle = self.cycle;
+        }
         self.cycle += 1;
         Ok(true)
     }
@@ -129,17 +172,24 @@ impl<C: 'static + CircuitDef<S>, S: CustomStep> Executor<C, S> {
     }
 
     pub fn finalize(&mut self) {
+        self.finalize_with_rng(&mut thread_rng());
+    }
+
+    /// Like [Executor::finalize], but draws the random padding which makes
+    /// the trace zero-knowledge from `rng`.  A seeded `rng` makes the trace
+    /// reproducible, but then the padding hides nothing, so this is only for
+    /// testing.
+    pub fn finalize_with_rng(&mut self, rng: &mut impl Rng) {
         debug!("finalize");
         assert!(self.halted);
         assert_eq!(self.cycle, self.steps - ZK_CYCLES);
-        let mut rng = thread_rng();
         // Make code be all zeros of zk cycles, and data be random
         for i in self.cycle..self.steps {
             for j in 0..self.code_size {
                 self.code[j * self.steps + i] = Fp::ZERO;
             }
             for j in 0..self.data_size {
-                self.data[j * self.steps + i] = Fp::random(&mut rng);
+                self.data[j * self.steps + i] = Fp::random(rng);
             }
         }
         // Do the verify cycles
@@ -135,8 +135,11 @@ mod tests {
 
     use super::*;
     use crate::{
-        core::{fp::Fp, sha_cpu},
-        field::{baby_bear::BabyBear, Elem},
+        core::{fp::Fp, poseidon, sha::DIGEST_WORDS, sha_cpu},
+        field::{
+            baby_bear::{BabyBear, P},
+            Elem,
+        },
         hal::cpu::CpuHal,
         verify::{merkle::MerkleTreeVerifier, read_iop::ReadIOP, VerificationError},
     };
@@ -198,7 +201,7 @@ mod tests {
                 iop.proof[manip_idx] ^= 1;
             }
             let mut r_iop = ReadIOP::new(sha, &iop.proof);
-            let verifier = MerkleTreeVerifier::new(&mut r_iop, rows, cols, queries);
+            let verifier = MerkleTreeVerifier::new(&mut r_iop, rows, cols, queries).unwrap();
             assert_eq!(verifier.root(), prover.root());
             let mut err = false;
             for query in 0..queries {
@@ -375,4 +378,24 @@ mod tests {
             possibly_bad_verify(&sha, &hal, rows, cols, queries, queries + 1, true);
         }
     }
+
+    #[test]
+    fn merkle_poseidon_non_canonical_top() {
+        let sha = poseidon::Impl {};
+        let (rows, cols, queries) = (16, 1, 1);
+        let top_size = MerkleTreeParams::new(rows, cols, queries).top_size;
+        let mut proof = vec![0; top_size * DIGEST_WORDS];
+        {
+            let mut r_iop = ReadIOP::new(&sha, &proof);
+            assert!(MerkleTreeVerifier::new(&mut r_iop, rows, cols, queries).is_ok());
+        }
+        // The same digest, but with a word which reduces to the same field
+        // element.
+        proof[1] += P;
+        let mut r_iop = ReadIOP::new(&sha, &proof);
+        assert!(matches!(
+            MerkleTreeVerifier::new(&mut r_iop, rows, cols, queries),
+            Err(VerificationError::InvalidProof)
+        ));
+    }
 }
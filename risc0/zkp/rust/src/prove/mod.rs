@@ -21,25 +21,22 @@ pub mod write_iop;
 
 use alloc::{vec, vec::Vec};
 
-use array_init::array_init;
 use log::debug;
 
 use crate::{
     core::{
-        fp::Fp,
-        fp4::{Fp4, EXT_SIZE},
         poly::{poly_divide, poly_interpolate},
-        rou::ROU_REV,
         sha::Sha,
     },
-    field::Elem,
+    field::{Elem, ExtElem, Field, RootsOfUnity},
     hal::{Buffer, EvalCheck, Hal},
     prove::{fri::fri_prove, poly_group::PolyGroup, write_iop::WriteIOP},
     taps::{RegisterGroup, TapSet},
-    CHECK_SIZE, INV_RATE, MAX_CYCLES_PO2,
+    ProofParams,
 };
 
-pub trait Circuit {
+/// A circuit whose execution trace is over the field `F`.
+pub trait Circuit<F: Field> {
     fn get_taps(&self) -> &'static TapSet<'static>;
 
     /// Perform initial 'execution' setting code + data.
@@ -51,50 +48,64 @@ pub trait Circuit {
 
     fn po2(&self) -> u32;
 
-    fn get_code(&self) -> &[Fp];
+    fn get_code(&self) -> &[F::Elem];
 
-    fn get_data(&self) -> &[Fp];
+    fn get_data(&self) -> &[F::Elem];
 
-    fn get_accum(&self) -> &[Fp];
+    fn get_accum(&self) -> &[F::Elem];
 
-    fn get_mix(&self) -> &[Fp];
+    fn get_mix(&self) -> &[F::Elem];
 
-    fn get_output(&self) -> &[Fp];
+    fn get_output(&self) -> &[F::Elem];
 
     fn get_steps(&self) -> usize;
 }
 
-pub fn prove_without_seal<S: Sha, C: Circuit>(sha: &S, circuit: &mut C) {
+/// Run only the execute phase of [prove], returning the words `circuit`
+/// wrote to the IOP, which are the globals at the start of the seal.
+pub fn prove_without_seal<F: Field, S: Sha, C: Circuit<F>>(sha: &S, circuit: &mut C) -> Vec<u32> {
     let mut iop = WriteIOP::new(sha);
     circuit.execute(&mut iop);
+    iop.proof
 }
 
-pub fn prove<H: Hal, S: Sha, C: Circuit, E: EvalCheck<H>>(
+/// Prove the execution of `circuit`, returning a seal which records
+/// `params`.
+pub fn prove<H: Hal, S: Sha, C: Circuit<H::Field>, E: EvalCheck<H>>(
     hal: &H,
     sha: &S,
     circuit: &mut C,
     eval: &E,
+    params: &ProofParams,
 ) -> Vec<u32> {
+    params.validate().unwrap();
+    let inv_rate = params.inv_rate;
     let taps = circuit.get_taps();
     let code_size = taps.group_size(RegisterGroup::Code);
     let data_size = taps.group_size(RegisterGroup::Data);
     let accum_size = taps.group_size(RegisterGroup::Accum);
     let mut iop = WriteIOP::new(sha);
 
+    // Bind the Fiat-Shamir challenges to the parameters, which are only
+    // recorded at the end of the seal.  See [ProofParams::is_committed].
+    if params.is_committed() {
+        iop.commit(&sha.hash_words(&params.to_words()));
+    }
+
     circuit.execute(&mut iop);
 
     let po2 = circuit.po2();
-    assert!(po2 as usize <= MAX_CYCLES_PO2);
+    assert!(po2 as usize <= params.max_po2::<H::Field>());
     let size = 1 << po2;
 
     // Make code + data PolyGroups + commit them
     let code_coeffs = make_coeffs(hal, circuit.get_code(), code_size);
-    let code_group = PolyGroup::new(hal, &code_coeffs, code_size, size);
+    let code_group = PolyGroup::new(hal, &code_coeffs, code_size, size, params);
     code_group.merkle.commit(hal, &mut iop);
     debug!("codeGroup: {}", code_group.merkle.root());
 
     let data_coeffs = make_coeffs(hal, circuit.get_data(), data_size);
-    let data_group = PolyGroup::new(hal, &data_coeffs, data_size, size);
+    let data_group = PolyGroup::new(hal, &data_coeffs, data_size, size, params);
     data_group.merkle.commit(hal, &mut iop);
     debug!("dataGroup: {}", data_group.merkle.root());
 
@@ -104,18 +115,20 @@ pub fn prove<H: Hal, S: Sha, C: Circuit, E: EvalCheck<H>>(
     debug!("size = {size}, accumSize = {accum_size}");
     debug!("getAccum.size() = {}", circuit.get_accum().len());
     let accum_coeffs = make_coeffs(hal, circuit.get_accum(), accum_size);
-    let accum_group = PolyGroup::new(hal, &accum_coeffs, accum_size, size);
+    let accum_group = PolyGroup::new(hal, &accum_coeffs, accum_size, size, params);
     accum_group.merkle.commit(hal, &mut iop);
     debug!("accumGroup: {}", accum_group.merkle.root());
 
     // Set the poly mix value
-    let poly_mix = Fp4::random(&mut iop.rng);
+    let poly_mix = <H::Field as Field>::ExtElem::random(&mut iop.rng);
 
     // Now generate the check polynomial
-    let domain = size * INV_RATE;
-    let check_poly = hal.alloc_fp(EXT_SIZE * domain);
-    let mix = hal.copy_fp_from(H::from_baby_bear_fp_slice(circuit.get_mix()));
-    let out = hal.copy_fp_from(H::from_baby_bear_fp_slice(circuit.get_output()));
+    let ext_size = <H::Field as Field>::ExtElem::EXT_SIZE;
+    let check_size = inv_rate * ext_size;
+    let domain = size * inv_rate;
+    let check_poly = hal.alloc_fp(ext_size * domain);
+    let mix = hal.copy_fp_from(circuit.get_mix());
+    let out = hal.copy_fp_from(circuit.get_output());
     eval.eval_check(
         &check_poly,
         &code_group.evaluated,
@@ -123,9 +136,10 @@ pub fn prove<H: Hal, S: Sha, C: Circuit, E: EvalCheck<H>>(
         &accum_group.evaluated,
         &mix,
         &out,
-        H::from_baby_bear_fp4(poly_mix),
+        poly_mix,
         po2 as usize,
         circuit.get_steps(),
+        inv_rate,
     );
 
     // #ifdef CIRCUIT_DEBUG
@@ -147,7 +161,7 @@ pub fn prove<H: Hal, S: Sha, C: Circuit, E: EvalCheck<H>>(
     // roots of unity (which are the only thing that and values get multiplied
     // by) are in Fp, Fp4 values act like simple vectors of Fp for the
     // purposes of interpolate/evaluate.
-    hal.batch_interpolate_ntt(&check_poly, EXT_SIZE);
+    hal.batch_interpolate_ntt(&check_poly, ext_size);
 
     // The next step is to convert the degree 4*n check polynomial into 4 degreen n
     // polynomials so that f(x) = g0(x^4) + g1(x^4) x + g2(x^4) x^2 + g3(x^4)
@@ -161,12 +175,12 @@ pub fn prove<H: Hal, S: Sha, C: Circuit, E: EvalCheck<H>>(
     // invRate*size to 16 polys of size, without actually doing anything.
 
     // Make the PolyGroup + add it to the IOP;
-    let check_group = PolyGroup::new(hal, &check_poly, CHECK_SIZE, size);
+    let check_group = PolyGroup::new(hal, &check_poly, check_size, size, params);
     check_group.merkle.commit(hal, &mut iop);
     debug!("checkGroup: {}", check_group.merkle.root());
 
     // Now pick a value for Z
-    let z = Fp4::random(&mut iop.rng);
+    let z = <H::Field as Field>::ExtElem::random(&mut iop.rng);
     // #ifdef CIRCUIT_DEBUG
     //   if (badZ != Fp4(0)) {
     //     Z = badZ;
@@ -176,26 +190,26 @@ pub fn prove<H: Hal, S: Sha, C: Circuit, E: EvalCheck<H>>(
     //   LOG(1, "Z = " << Z);
 
     // Get rev rou for size
-    let back_one = Fp4::from_u32(ROU_REV[po2 as usize]);
+    let back_one = <H::Field as Field>::Elem::ROU_REV[po2 as usize];
     let mut all_xs = Vec::new();
 
     // Do evaluations of all of the various polynomials at the appropriate points.
-    let mut eval_u: Vec<Fp4> = Vec::new();
+    let mut eval_u: Vec<<H::Field as Field>::ExtElem> = Vec::new();
     let mut eval_group = |id: RegisterGroup, pg: &PolyGroup<H>| {
         let mut which = Vec::new();
         let mut xs = Vec::new();
         for tap in taps.group_taps(id) {
             which.push(tap.offset() as u32);
-            let x = back_one.pow(tap.back()) * z;
+            let x = z * back_one.pow(tap.back());
             xs.push(x);
             all_xs.push(x);
         }
         let which = hal.copy_u32_from(which.as_slice());
-        let xs = hal.copy_fp4_from(H::from_baby_bear_fp4_slice(xs.as_slice()));
+        let xs = hal.copy_fp4_from(xs.as_slice());
         let out = hal.alloc_fp4(which.size());
         hal.batch_evaluate_any(&pg.coeffs, pg.count, &which, &xs, &out);
         out.view(|view| {
-            eval_u.extend(H::to_baby_bear_fp4_slice(view));
+            eval_u.extend(view);
         });
     };
 
@@ -210,7 +224,7 @@ pub fn prove<H: Hal, S: Sha, C: Circuit, E: EvalCheck<H>>(
 
     // Now, convert the values to coefficients via interpolation
     let mut pos = 0;
-    let mut coeff_u = vec![Fp4::ZERO; eval_u.len()];
+    let mut coeff_u = vec![<H::Field as Field>::ExtElem::ZERO; eval_u.len()];
     for reg in taps.regs() {
         poly_interpolate(
             &mut coeff_u[pos..],
@@ -222,15 +236,15 @@ pub fn prove<H: Hal, S: Sha, C: Circuit, E: EvalCheck<H>>(
     }
 
     // Add in the coeffs of the check polynomials.
-    let z4 = z.pow(EXT_SIZE);
-    let which: [u32; CHECK_SIZE] = array_init(|i| i as u32);
-    let xs = [z4; CHECK_SIZE];
-    let out = hal.alloc_fp4(CHECK_SIZE);
+    let z4 = z.pow(inv_rate);
+    let which: Vec<u32> = (0..check_size as u32).collect();
+    let xs = vec![z4; check_size];
+    let out = hal.alloc_fp4(check_size);
     let which = hal.copy_u32_from(which.as_slice());
-    let xs = hal.copy_fp4_from(H::from_baby_bear_fp4_slice(xs.as_slice()));
-    hal.batch_evaluate_any(&check_group.coeffs, CHECK_SIZE, &which, &xs, &out);
+    let xs = hal.copy_fp4_from(xs.as_slice());
+    hal.batch_evaluate_any(&check_group.coeffs, check_size, &which, &xs, &out);
     out.view(|view| {
-        coeff_u.extend(H::to_baby_bear_fp4_slice(view));
+        coeff_u.extend(view);
     });
 
     debug!("Size of U = {}", coeff_u.len());
@@ -239,15 +253,15 @@ pub fn prove<H: Hal, S: Sha, C: Circuit, E: EvalCheck<H>>(
     iop.commit(&hash_u);
 
     // Set the mix mix value
-    let mix = Fp4::random(&mut iop.rng);
+    let mix = <H::Field as Field>::ExtElem::random(&mut iop.rng);
     debug!("Mix = {mix:?}");
 
     // Do the coefficent mixing
     // Begin by making a zeroed output buffer
     let combo_count = taps.combos_size();
-    let combos = vec![Fp4::ZERO; size * (combo_count + 1)];
-    let combos = hal.copy_fp4_from(H::from_baby_bear_fp4_slice(combos.as_slice()));
-    let mut cur_mix = Fp4::ONE;
+    let combos = vec![<H::Field as Field>::ExtElem::ZERO; size * (combo_count + 1)];
+    let combos = hal.copy_fp4_from(combos.as_slice());
+    let mut cur_mix = <H::Field as Field>::ExtElem::ONE;
 
     let mut mix_group = |id: RegisterGroup, pg: &PolyGroup<H>| {
         let mut which = Vec::new();
@@ -257,13 +271,7 @@ pub fn prove<H: Hal, S: Sha, C: Circuit, E: EvalCheck<H>>(
         let which_buf = hal.copy_u32_from(which.as_slice());
         let group_size = taps.group_size(id);
         hal.mix_poly_coeffs(
-            &combos,
-            &H::from_baby_bear_fp4(cur_mix),
-            &H::from_baby_bear_fp4(mix),
-            &pg.coeffs,
-            &which_buf,
-            group_size,
-            size,
+            &combos, &cur_mix, &mix, &pg.coeffs, &which_buf, group_size, size,
         );
         cur_mix *= mix.pow(group_size);
     };
@@ -272,24 +280,23 @@ pub fn prove<H: Hal, S: Sha, C: Circuit, E: EvalCheck<H>>(
     mix_group(RegisterGroup::Code, &code_group);
     mix_group(RegisterGroup::Data, &data_group);
 
-    let which = [combo_count as u32; CHECK_SIZE];
+    let which = vec![combo_count as u32; check_size];
     let which_buf = hal.copy_u32_from(which.as_slice());
     hal.mix_poly_coeffs(
         &combos,
-        &H::from_baby_bear_fp4(cur_mix),
-        &H::from_baby_bear_fp4(mix),
+        &cur_mix,
+        &mix,
         &check_group.coeffs,
         &which_buf,
-        CHECK_SIZE,
+        check_size,
         size,
     );
 
     // Load the near final coefficients back to the CPU
     combos.view_mut(|combos| {
-        let combos = H::to_baby_bear_fp4_slice_mut(combos);
         // Subtract the U coeffs from the combos
         let mut cur_pos = 0;
-        let mut cur = Fp4::ONE;
+        let mut cur = <H::Field as Field>::ExtElem::ONE;
         for reg in taps.regs() {
             for i in 0..reg.size() {
                 combos[size * reg.combo_id() + i] -= cur * coeff_u[cur_pos + i];
@@ -298,7 +305,7 @@ pub fn prove<H: Hal, S: Sha, C: Circuit, E: EvalCheck<H>>(
             cur_pos += reg.size();
         }
         // Subtract the final 'check' coefficents
-        for _ in 0..CHECK_SIZE {
+        for _ in 0..check_size {
             combos[size * combo_count] -= cur * coeff_u[cur_pos];
             cur_pos += 1;
             cur *= mix;
@@ -311,7 +318,7 @@ pub fn prove<H: Hal, S: Sha, C: Circuit, E: EvalCheck<H>>(
                         &mut combos[combo * size..combo * size + size],
                         z * back_one.pow((*back).into())
                     ),
-                    Fp4::ZERO
+                    <H::Field as Field>::ExtElem::ZERO
                 );
             }
         }
@@ -321,35 +328,36 @@ pub fn prove<H: Hal, S: Sha, C: Circuit, E: EvalCheck<H>>(
                 &mut combos[combo_count * size..combo_count * size + size],
                 z4
             ),
-            Fp4::ZERO
+            <H::Field as Field>::ExtElem::ZERO
         );
     });
 
     // Sum the combos up into one final polynomial + make it into 4 Fp polys.
     // Additionally, it needs to be bit reversed to make everyone happy
-    let final_poly_coeffs = hal.alloc_fp(size * EXT_SIZE);
+    let final_poly_coeffs = hal.alloc_fp(size * ext_size);
     hal.eltwise_sum_fp4(&final_poly_coeffs, &combos);
 
     // Finally do the FRI protocol to prove the degree of the polynomial
-    hal.batch_bit_reverse(&final_poly_coeffs, EXT_SIZE);
-    debug!("FRI-proof, size = {}", final_poly_coeffs.size() / EXT_SIZE);
+    hal.batch_bit_reverse(&final_poly_coeffs, ext_size);
+    debug!("FRI-proof, size = {}", final_poly_coeffs.size() / ext_size);
 
-    fri_prove(hal, &mut iop, &final_poly_coeffs, |iop, idx| {
+    fri_prove(hal, &mut iop, params, &final_poly_coeffs, |iop, idx| {
         accum_group.merkle.prove(iop, idx);
         code_group.merkle.prove(iop, idx);
         data_group.merkle.prove(iop, idx);
         check_group.merkle.prove(iop, idx);
     });
 
-    // Return final proof
-    let proof = iop.proof;
+    // Return final proof, followed by the parameters it was made with
+    let mut proof = iop.proof;
+    proof.extend(params.to_words());
     debug!("Proof size = {}", proof.len());
     proof
 }
 
-fn make_coeffs<H: Hal>(hal: &H, input: &[Fp], count: usize) -> H::BufferFp {
+fn make_coeffs<H: Hal>(hal: &H, input: &[<H::Field as Field>::Elem], count: usize) -> H::BufferFp {
     // Copy into accel buffer
-    let buf = hal.copy_fp_from(H::from_baby_bear_fp_slice(input));
+    let buf = hal.copy_fp_from(input);
     // Do interpolate
     hal.batch_interpolate_ntt(&buf, count);
     // Convert f(x) -> f(3x), which effective multiplies cofficent c_i by 3^i.
@@ -357,3 +365,332 @@ fn make_coeffs<H: Hal>(hal: &H, input: &[Fp], count: usize) -> H::BufferFp {
     hal.zk_shift(&buf, count);
     buf
 }
+
+#[cfg(test)]
+mod test {
+    use alloc::boxed::Box;
+    use core::marker::PhantomData;
+
+    use rand::{thread_rng, Rng};
+
+    use super::*;
+    use crate::{
+        adapter::{CircuitInfo, TapsProvider},
+        core::{
+            blake3, log2_ceil,
+            ntt::{bit_reverse, interpolate_ntt},
+            poseidon, sha_cpu,
+        },
+        field::{baby_bear::BabyBear, goldilocks::Goldilocks},
+        hal::cpu::{CpuBuffer, CpuHal},
+        taps::{Tap, TapSetOwned},
+        verify::{inspect_seal, verify, VerifyHal},
+    };
+
+    const PO2: usize = 10;
+
+    // One register in each group, each of which is only read on the current
+    // cycle.
+    fn taps() -> &'static TapSet<'static> {
+        let owned = Box::leak(Box::new(TapSetOwned::new(&[
+            Tap::new(RegisterGroup::Accum, 0, 0, 0),
+            Tap::new(RegisterGroup::Code, 0, 0, 1),
+            Tap::new(RegisterGroup::Data, 0, 0, 2),
+        ])));
+        Box::leak(Box::new(TapSet::from(&*owned)))
+    }
+
+    // The constraint of the test circuit: both the code and the data
+    // registers hold bits.
+    fn constraint<F: Field>(poly_mix: F::ExtElem, code: F::Elem, data: F::Elem) -> F::ExtElem {
+        F::ExtElem::from_subfield(&(data * data - data)) + poly_mix * (code * code - code)
+    }
+
+    struct TestCircuit<F: Field> {
+        taps: &'static TapSet<'static>,
+        code: Vec<F::Elem>,
+        data: Vec<F::Elem>,
+        accum: Vec<F::Elem>,
+        mix: Vec<F::Elem>,
+        output: Vec<F::Elem>,
+    }
+
+    impl<F: Field> TestCircuit<F> {
+        fn new() -> Self {
+            let mut rng = thread_rng();
+            let steps = 1 << PO2;
+            TestCircuit {
+                taps: taps(),
+                code: (0..steps)
+                    .map(|i| F::Elem::from_u64(i as u64 % 2))
+                    .collect(),
+                data: (0..steps)
+                    .map(|_| F::Elem::from_u64(rng.gen_range(0..2)))
+                    .collect(),
+                accum: Vec::new(),
+                mix: Vec::new(),
+                output: vec![F::Elem::random(&mut rng)],
+            }
+        }
+    }
+
+    impl<F: Field> CircuitInfo for TestCircuit<F> {
+        const OUTPUT_SIZE: usize = 1;
+        const MIX_SIZE: usize = 1;
+    }
+
+    impl<F: Field> TapsProvider for TestCircuit<F> {
+        fn get_taps(&self) -> &'static TapSet<'static> {
+            self.taps
+        }
+    }
+
+    impl<F: Field> Circuit<F> for TestCircuit<F> {
+        fn get_taps(&self) -> &'static TapSet<'static> {
+            self.taps
+        }
+
+        fn execute<S: Sha>(&mut self, iop: &mut WriteIOP<S>) {
+            iop.write_pod_slice(&self.output);
+            iop.write_u32_slice(&[PO2 as u32]);
+        }
+
+        fn accumulate<S: Sha>(&mut self, iop: &mut WriteIOP<S>) {
+            self.mix = (0..Self::MIX_SIZE)
+                .map(|_| F::Elem::random(&mut iop.rng))
+                .collect();
+            let mut rng = thread_rng();
+            self.accum = (0..self.get_steps())
+                .map(|_| F::Elem::random(&mut rng))
+                .collect();
+        }
+
+        fn po2(&self) -> u32 {
+            PO2 as u32
+        }
+
+        fn get_code(&self) -> &[F::Elem] {
+            &self.code
+        }
+
+        fn get_data(&self) -> &[F::Elem] {
+            &self.data
+        }
+
+        fn get_accum(&self) -> &[F::Elem] {
+            &self.accum
+        }
+
+        fn get_mix(&self) -> &[F::Elem] {
+            &self.mix
+        }
+
+        fn get_output(&self) -> &[F::Elem] {
+            &self.output
+        }
+
+        fn get_steps(&self) -> usize {
+            1 << PO2
+        }
+    }
+
+    struct TestEvalCheck;
+
+    impl<F: Field, S: Sha + Sync> EvalCheck<CpuHal<F, S>> for TestEvalCheck {
+        fn eval_check(
+            &self,
+            check: &CpuBuffer<F::Elem>,
+            code: &CpuBuffer<F::Elem>,
+            data: &CpuBuffer<F::Elem>,
+            _accum: &CpuBuffer<F::Elem>,
+            _mix: &CpuBuffer<F::Elem>,
+            _out: &CpuBuffer<F::Elem>,
+            poly_mix: F::ExtElem,
+            po2: usize,
+            steps: usize,
+            inv_rate: usize,
+        ) {
+            let domain = steps * inv_rate;
+            let code = code.as_slice();
+            let data = data.as_slice();
+            let mut check = check.as_slice_mut();
+            let three = F::Elem::from_u64(3);
+            for cycle in 0..domain {
+                let cond = constraint::<F>(poly_mix, code[cycle], data[cycle]);
+                let x = F::Elem::ROU_FWD[po2 + log2_ceil(inv_rate)].pow(cycle);
+                let y = (three * x).pow(1 << po2);
+                let ret = cond * (y - F::Elem::ONE).inv();
+                for (i, elem) in ret.subelems().iter().enumerate() {
+                    check[i * domain + cycle] = *elem;
+                }
+            }
+        }
+    }
+
+    struct TestVerifyHal<F: Field, S: Sha> {
+        sha: S,
+        _field: PhantomData<F>,
+    }
+
+    impl<F: Field, S: Sha> VerifyHal for TestVerifyHal<F, S> {
+        type Sha = S;
+        type Field = F;
+
+        fn sha(&self) -> &Self::Sha {
+            &self.sha
+        }
+
+        fn debug(&self, _msg: &str) {}
+
+        fn compute_polynomial(
+            &self,
+            u: &[F::ExtElem],
+            poly_mix: F::ExtElem,
+            _out: &[F::Elem],
+            _mix: &[F::Elem],
+        ) -> F::ExtElem {
+            let (code, data) = (u[1], u[2]);
+            (data * data - data) + poly_mix * (code * code - code)
+        }
+
+        fn fold_eval(&self, io: &mut [F::ExtElem], mix: F::ExtElem, inv_wk: F::Elem) -> F::ExtElem {
+            interpolate_ntt::<F::Elem, F::ExtElem>(io);
+            bit_reverse(io);
+            self.poly_eval(io, mix, inv_wk)
+        }
+    }
+
+    fn prove_and_verify<F: Field, S: Sha + Sync>(sha: S) {
+        prove_and_verify_with_params::<F, S>(sha, &ProofParams::default());
+    }
+
+    fn prove_and_verify_with_params<F: Field, S: Sha + Sync>(sha: S, params: &ProofParams) {
+        let hal = CpuHal::<F, S>::new_with_sha(sha.clone());
+        let mut circuit = TestCircuit::<F>::new();
+        let seal = prove(&hal, &sha, &mut circuit, &TestEvalCheck, params);
+        assert_eq!(ProofParams::from_seal(&seal).unwrap(), *params);
+
+        let verify_hal = TestVerifyHal::<F, S> {
+            sha: sha.clone(),
+            _field: PhantomData,
+        };
+        let circuit = TestCircuit::<F>::new();
+        verify(&verify_hal, &circuit, &seal, params, |_, _| true).unwrap();
+
+        // Any change to the seal must be caught.
+        let mut bad_seal = seal.clone();
+        let last = bad_seal.len() - ProofParams::SEAL_WORDS - 1;
+        bad_seal[last] ^= 1;
+        assert!(verify(&verify_hal, &circuit, &bad_seal, params, |_, _| true).is_err());
+    }
+
+    #[test]
+    fn prove_baby_bear() {
+        prove_and_verify::<BabyBear, _>(sha_cpu::Impl {});
+    }
+
+    #[test]
+    fn prove_goldilocks() {
+        prove_and_verify::<Goldilocks, _>(sha_cpu::Impl {});
+    }
+
+    #[test]
+    fn prove_poseidon() {
+        prove_and_verify::<BabyBear, _>(poseidon::Impl {});
+    }
+
+    #[test]
+    fn prove_blake3() {
+        prove_and_verify::<BabyBear, _>(blake3::Impl {});
+    }
+
+    #[test]
+    fn prove_with_params() {
+        let params = ProofParams {
+            queries: 20,
+            inv_rate: 8,
+            fri_fold_po2: 2,
+            fri_min_degree: 16,
+        };
+        prove_and_verify_with_params::<BabyBear, _>(sha_cpu::Impl {}, &params);
+
+        // The seal may only be verified with the parameters it records.
+        let sha = sha_cpu::Impl {};
+        let hal = CpuHal::<BabyBear, _>::new_with_sha(sha.clone());
+        let mut circuit = TestCircuit::<BabyBear>::new();
+        let seal = prove(&hal, &sha, &mut circuit, &TestEvalCheck, &params);
+        let verify_hal = TestVerifyHal::<BabyBear, _> {
+            sha,
+            _field: PhantomData,
+        };
+        let default = ProofParams::default();
+        assert!(verify(&verify_hal, &circuit, &seal, &default, |_, _| true).is_err());
+
+        // A seal which is too short is rejected, rather than read past its
+        // end.
+        let mut short_seal = seal.clone();
+        short_seal.remove(0);
+        assert!(verify(&verify_hal, &circuit, &short_seal, &params, |_, _| true).is_err());
+    }
+
+    #[test]
+    fn prove_fri_fold_bounds() {
+        let default = ProofParams::default();
+        for (fri_fold_po2, fri_min_degree) in [(1, 2), (8, 256), (8, 1 << PO2)] {
+            let params = ProofParams {
+                fri_fold_po2,
+                fri_min_degree,
+                ..default
+            };
+            prove_and_verify_with_params::<BabyBear, _>(sha_cpu::Impl {}, &params);
+        }
+    }
+
+    #[test]
+    fn verify_pre_params_seal() {
+        // A seal of the test circuit made with the default parameters before
+        // they were recorded in the seal or committed to the transcript.
+        // Such seals must still verify once the parameters are appended, as
+        // receipts older than version 3 are when they are read.
+        let mut seal: Vec<u32> = include_bytes!("testdata/pre_params_seal.bin")
+            .chunks_exact(4)
+            .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+            .collect();
+        let default = ProofParams::default();
+        seal.extend(default.to_words());
+        let verify_hal = TestVerifyHal::<BabyBear, _> {
+            sha: sha_cpu::Impl {},
+            _field: PhantomData,
+        };
+        let circuit = TestCircuit::<BabyBear>::new();
+        verify(&verify_hal, &circuit, &seal, &default, |_, _| true).unwrap();
+    }
+
+    #[test]
+    fn inspect() {
+        let sha = sha_cpu::Impl {};
+        let hal = CpuHal::<BabyBear, _>::new_with_sha(sha.clone());
+        let default = ProofParams::default();
+        let smaller = ProofParams {
+            queries: 25,
+            inv_rate: 16,
+            fri_fold_po2: 2,
+            fri_min_degree: 16,
+        };
+        for (params, fri_rounds) in [(default, 1), (smaller, 3)] {
+            let mut circuit = TestCircuit::<BabyBear>::new();
+            let seal = prove(&hal, &sha, &mut circuit, &TestEvalCheck, &params);
+            let info = inspect_seal::<BabyBear, _>(&circuit, &seal).unwrap();
+            assert_eq!(info.po2, PO2 as u32);
+            assert_eq!(info.params, params);
+            assert_eq!(info.fri_rounds, fri_rounds);
+            assert_eq!(info.security_bits, 100);
+            assert_eq!(info.total_bytes(), seal.len() * 4);
+
+            // A seal whose layout doesn't match its parameters is rejected.
+            let mut bad_seal = seal.clone();
+            bad_seal.remove(0);
+            assert!(inspect_seal::<BabyBear, _>(&circuit, &bad_seal).is_err());
+        }
+    }
+}
@@ -13,24 +13,26 @@
 // limitations under the License.
 
 use alloc::vec::Vec;
+use core::marker::PhantomData;
 
 use crate::{
     adapter::{CircuitInfo, TapsProvider},
-    core::{fp::Fp, sha::Sha},
-    field::Elem,
+    core::sha::Sha,
+    field::{Elem, Field},
     taps::TapSet,
     verify::read_iop::ReadIOP,
 };
 
-pub struct VerifyAdapter<'a, C: CircuitInfo + TapsProvider> {
+pub struct VerifyAdapter<'a, F: Field, C: CircuitInfo + TapsProvider> {
     circuit: &'a C,
     po2: u32,
     steps: usize,
-    pub out: Option<&'a [Fp]>,
-    pub mix: Vec<Fp>,
+    pub out: Option<&'a [F::Elem]>,
+    pub mix: Vec<F::Elem>,
+    _field: PhantomData<F>,
 }
 
-impl<'a, C: CircuitInfo + TapsProvider> VerifyAdapter<'a, C> {
+impl<'a, F: Field, C: CircuitInfo + TapsProvider> VerifyAdapter<'a, F, C> {
     pub fn new(circuit: &'a C) -> Self {
         VerifyAdapter {
             circuit,
@@ -38,6 +40,7 @@ impl<'a, C: CircuitInfo + TapsProvider> VerifyAdapter<'a, C> {
             steps: 0,
             out: None,
             mix: Vec::new(),
+            _field: PhantomData,
         }
     }
 
@@ -57,7 +60,7 @@ impl<'a, C: CircuitInfo + TapsProvider> VerifyAdapter<'a, C> {
 
     pub fn accumulate<S: Sha>(&mut self, iop: &mut ReadIOP<'a, S>) {
         // Fill in accum mix
-        self.mix = (0..C::MIX_SIZE).map(|_| Fp::random(iop)).collect();
+        self.mix = (0..C::MIX_SIZE).map(|_| F::Elem::random(iop)).collect();
     }
 
     pub fn po2(&self) -> u32 {
@@ -18,66 +18,69 @@ use rand::RngCore;
 
 use super::VerifyHal;
 use crate::{
-    core::{
-        fp::Fp,
-        fp4::{Fp4, EXT_SIZE},
-        log2_ceil,
-        rou::{ROU_FWD, ROU_REV},
-        sha::Sha,
-    },
-    field::{baby_bear::BabyBear, Elem},
+    core::{log2_ceil, sha::Sha},
+    field::{Elem, ExtElem, Field, RootsOfUnity},
     verify::{merkle::MerkleTreeVerifier, read_iop::ReadIOP, VerificationError},
-    FRI_FOLD, FRI_FOLD_PO2, FRI_MIN_DEGREE, INV_RATE, QUERIES,
+    ProofParams,
 };
 
 /// VerifyRoundInfo contains the data against which the queries for a particular
 /// round are checked. This includes the Merkle tree top row data, as well as
 /// the size of the domain of the polynomial, and the mixing parameter.
-struct VerifyRoundInfo<'a, S: Sha> {
+struct VerifyRoundInfo<'a, S: Sha, F: Field> {
     domain: usize,
+    fold: usize,
     merkle: MerkleTreeVerifier<'a, S>,
-    mix: Fp4,
+    mix: F::ExtElem,
 }
 
-impl<'a, S: Sha> VerifyRoundInfo<'a, S> {
-    pub fn new(iop: &mut ReadIOP<'a, S>, in_domain: usize) -> Self {
-        let domain = in_domain / FRI_FOLD;
-        VerifyRoundInfo {
+impl<'a, S: Sha, F: Field> VerifyRoundInfo<'a, S, F> {
+    pub fn new(
+        iop: &mut ReadIOP<'a, S>,
+        params: &ProofParams,
+        in_domain: usize,
+    ) -> Result<Self, VerificationError> {
+        let fold = params.fri_fold();
+        let domain = in_domain / fold;
+        Ok(VerifyRoundInfo {
             domain,
-            merkle: MerkleTreeVerifier::new(iop, domain, FRI_FOLD * EXT_SIZE, QUERIES),
-            mix: Fp4::random(iop),
-        }
+            fold,
+            merkle: MerkleTreeVerifier::new(
+                iop,
+                domain,
+                fold * F::ExtElem::EXT_SIZE,
+                params.queries,
+            )?,
+            mix: F::ExtElem::random(iop),
+        })
     }
 
-    pub fn verify_query<H: VerifyHal>(
+    pub fn verify_query<H: VerifyHal<Field = F>>(
         &mut self,
         hal: &H,
         iop: &mut ReadIOP<'a, S>,
         pos: &mut usize,
-        goal: &mut Fp4,
+        goal: &mut F::ExtElem,
     ) -> Result<(), VerificationError> {
         let quot = *pos / self.domain;
         let group = *pos % self.domain;
         // Get the column data
-        let data = self.merkle.verify::<BabyBear>(iop, group)?;
-        let mut data4: Vec<_> = (0..FRI_FOLD)
+        let data = self.merkle.verify::<F>(iop, group)?;
+        let mut data_ext: Vec<_> = (0..self.fold)
             .map(|i| {
-                Fp4::new(
-                    data[0 * FRI_FOLD + i],
-                    data[1 * FRI_FOLD + i],
-                    data[2 * FRI_FOLD + i],
-                    data[3 * FRI_FOLD + i],
+                F::ExtElem::from_subelems(
+                    (0..F::ExtElem::EXT_SIZE).map(|j| data[j * self.fold + i]),
                 )
             })
             .collect();
         // Check the existing goal
-        if data4[quot] != *goal {
+        if data_ext[quot] != *goal {
             return Err(VerificationError::InvalidProof);
         }
         // Compute the new goal + pos
-        let root_po2 = log2_ceil(FRI_FOLD * self.domain);
-        let inv_wk: Fp = Fp::new(ROU_REV[root_po2]).pow(group);
-        *goal = hal.fold_eval(&mut data4, self.mix, inv_wk);
+        let root_po2 = log2_ceil(self.fold * self.domain);
+        let inv_wk = F::Elem::ROU_REV[root_po2].pow(group);
+        *goal = hal.fold_eval(&mut data_ext, self.mix, inv_wk);
         *pos = group;
         Ok(())
     }
@@ -86,40 +89,35 @@ impl<'a, S: Sha> VerifyRoundInfo<'a, S> {
 pub fn fri_verify<'a, H: VerifyHal, F>(
     hal: &H,
     iop: &mut ReadIOP<'a, H::Sha>,
+    params: &ProofParams,
     mut degree: usize,
     mut inner: F,
 ) -> Result<(), VerificationError>
 where
-    F: FnMut(&mut ReadIOP<'a, H::Sha>, usize) -> Result<Fp4, VerificationError>,
+    F: FnMut(
+        &mut ReadIOP<'a, H::Sha>,
+        usize,
+    ) -> Result<<H::Field as Field>::ExtElem, VerificationError>,
 {
-    let orig_domain = INV_RATE * degree;
+    let ext_size = <H::Field as Field>::ExtElem::EXT_SIZE;
+    let fold = params.fri_fold();
+    let orig_domain = params.inv_rate * degree;
     let mut domain = orig_domain;
     // Prep the folding verfiers
-    let rounds_capacity =
-        (log2_ceil((degree + FRI_FOLD - 1) / FRI_FOLD) + FRI_FOLD_PO2 - 1) / FRI_FOLD_PO2;
-    let mut rounds = Vec::with_capacity(rounds_capacity);
-    while degree > FRI_MIN_DEGREE {
-        rounds.push(VerifyRoundInfo::new(iop, domain));
-        domain /= FRI_FOLD;
-        degree /= FRI_FOLD;
+    let mut rounds = Vec::new();
+    while degree > params.fri_min_degree {
+        rounds.push(VerifyRoundInfo::<_, H::Field>::new(iop, params, domain)?);
+        domain /= fold;
+        degree /= fold;
     }
-    // We want to minimize reallocation in verify, so make sure we
-    // didn't have to reallocate.
-    assert!(
-        rounds.len() < rounds_capacity,
-        "Did not allocate enough rounds; needed {} for degree {} but only allocated {}",
-        rounds.len(),
-        degree,
-        rounds_capacity
-    );
     // Grab the final coeffs + commit
-    let final_coeffs = iop.read_pod_slice(EXT_SIZE * degree);
+    let final_coeffs: &[<H::Field as Field>::Elem] = iop.read_pod_slice(ext_size * degree);
     let final_digest = iop.get_sha().hash_raw_pod_slice(final_coeffs);
     iop.commit(&final_digest);
     // Get the generator for the final polynomial evaluations
-    let gen = Fp::new(ROU_FWD[log2_ceil(domain)]);
+    let gen = <H::Field as Field>::Elem::ROU_FWD[log2_ceil(domain)];
     // Do queries
-    for _ in 0..QUERIES {
+    for _ in 0..params.queries {
         let rng = iop.next_u32();
         let mut pos = rng as usize % orig_domain;
         // Do the 'inner' verification for this index
@@ -130,16 +128,13 @@ where
         }
         // Do final verification
         let x = gen.pow(pos);
-        let mut fx = Fp4::ZERO;
-        let mut cur = Fp::ONE;
+        let mut fx = <H::Field as Field>::ExtElem::ZERO;
+        let mut cur = <H::Field as Field>::Elem::ONE;
         for i in 0..degree {
-            let coeff = Fp4::new(
-                final_coeffs[0 * degree + i],
-                final_coeffs[1 * degree + i],
-                final_coeffs[2 * degree + i],
-                final_coeffs[3 * degree + i],
+            let coeff = <H::Field as Field>::ExtElem::from_subelems(
+                (0..ext_size).map(|j| final_coeffs[j * degree + i]),
             );
-            fx += cur * coeff;
+            fx += coeff * cur;
             cur *= x;
         }
         if fx != goal {
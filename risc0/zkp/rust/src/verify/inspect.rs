@@ -0,0 +1,165 @@
+// Copyright 2022 Risc0, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reports on the size and security of a seal, without verifying it.
+
+use core::mem;
+
+use crate::{
+    adapter::{CircuitInfo, TapsProvider},
+    core::sha::DIGEST_WORDS,
+    field::{Elem, ExtElem, Field},
+    merkle::MerkleTreeParams,
+    taps::RegisterGroup,
+    verify::VerificationError,
+    ProofParams,
+};
+
+const WORD_BYTES: usize = mem::size_of::<u32>();
+
+/// The structure of a seal, as reported by [inspect_seal].
+///
+/// The byte sizes of each section add up to [SealInfo::total_bytes].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SealInfo {
+    /// The log base 2 of the number of cycles in the execution trace.
+    pub po2: u32,
+
+    /// The parameters the seal was proven with.
+    pub params: ProofParams,
+
+    /// The number of FRI rounds, each of which commits to a Merkle tree.
+    pub fri_rounds: usize,
+
+    /// The conjectured bits of security; see [ProofParams::security_bits].
+    pub security_bits: usize,
+
+    /// The bytes of the circuit outputs and the po2.
+    pub output_bytes: usize,
+
+    /// The bytes of the top layers of the Merkle trees of the code, data,
+    /// accum and check polynomials.
+    pub merkle_root_bytes: usize,
+
+    /// The bytes of the coefficients of the polynomials evaluated at the
+    /// out-of-domain point.
+    pub eval_bytes: usize,
+
+    /// The bytes of the top layers of the Merkle trees of each FRI round,
+    /// and of the coefficients of the final FRI polynomial.
+    pub fri_layer_bytes: usize,
+
+    /// The bytes of the Merkle branches opened by the queries, in both the
+    /// trace and FRI trees.
+    pub query_bytes: usize,
+
+    /// The bytes which record the [ProofParams].
+    pub params_bytes: usize,
+}
+
+impl SealInfo {
+    /// The size of the whole seal in bytes.
+    pub fn total_bytes(&self) -> usize {
+        self.output_bytes
+            + self.merkle_root_bytes
+            + self.eval_bytes
+            + self.fri_layer_bytes
+            + self.query_bytes
+            + self.params_bytes
+    }
+}
+
+// The words of a Merkle tree's top layer, which is committed to once, and of
+// all the branches opened by the queries.
+fn merkle_words<E: Elem>(row_size: usize, col_size: usize, queries: usize) -> (usize, usize) {
+    let params = MerkleTreeParams::new(row_size, col_size, queries);
+    let top = params.top_size * DIGEST_WORDS;
+    let branch = col_size * E::WORDS + (params.layers - params.top_layer) * DIGEST_WORDS;
+    (top, queries * branch)
+}
+
+/// Report the size and conjectured security of `seal`, which was proven for
+/// `circuit` over the field `F`.
+///
+/// Only the layout of the seal is parsed, so this is much cheaper than
+/// [super::verify], but says nothing about whether the seal is valid.
+pub fn inspect_seal<F, C>(circuit: &C, seal: &[u32]) -> Result<SealInfo, VerificationError>
+where
+    F: Field,
+    C: CircuitInfo + TapsProvider,
+{
+    let params = ProofParams::from_seal(seal).map_err(|_| VerificationError::ReceiptFormatError)?;
+    let output_words = C::OUTPUT_SIZE * F::Elem::WORDS + 1;
+    let po2 = *seal
+        .get(output_words - 1)
+        .ok_or(VerificationError::ReceiptFormatError)?;
+    if po2 as usize > params.max_po2::<F>() {
+        return Err(VerificationError::ReceiptFormatError);
+    }
+
+    let taps = circuit.get_taps();
+    let ext_size = F::ExtElem::EXT_SIZE;
+    let size = 1 << po2;
+    let domain = params.inv_rate * size;
+    let check_size = params.inv_rate * ext_size;
+
+    // The code, data, accum and check trees all cover the whole domain.
+    let mut merkle_root_words = 0;
+    let mut query_words = 0;
+    for col_size in [
+        taps.group_size(RegisterGroup::Code),
+        taps.group_size(RegisterGroup::Data),
+        taps.group_size(RegisterGroup::Accum),
+        check_size,
+    ] {
+        let (top, branches) = merkle_words::<F::Elem>(domain, col_size, params.queries);
+        merkle_root_words += top;
+        query_words += branches;
+    }
+    let eval_words = (taps.tap_size() + check_size) * F::ExtElem::WORDS;
+
+    // Each FRI round folds the domain, until the degree is small enough to
+    // send the coefficients.
+    let fold = params.fri_fold();
+    let mut degree = size;
+    let mut fri_domain = domain;
+    let mut fri_rounds = 0;
+    let mut fri_layer_words = 0;
+    while degree > params.fri_min_degree {
+        fri_domain /= fold;
+        degree /= fold;
+        let (top, branches) = merkle_words::<F::Elem>(fri_domain, fold * ext_size, params.queries);
+        fri_rounds += 1;
+        fri_layer_words += top;
+        query_words += branches;
+    }
+    fri_layer_words += ext_size * degree * F::Elem::WORDS;
+
+    let info = SealInfo {
+        po2,
+        params,
+        fri_rounds,
+        security_bits: params.security_bits(),
+        output_bytes: output_words * WORD_BYTES,
+        merkle_root_bytes: merkle_root_words * WORD_BYTES,
+        eval_bytes: eval_words * WORD_BYTES,
+        fri_layer_bytes: fri_layer_words * WORD_BYTES,
+        query_bytes: query_words * WORD_BYTES,
+        params_bytes: ProofParams::SEAL_WORDS * WORD_BYTES,
+    };
+    if info.total_bytes() != seal.len() * WORD_BYTES {
+        return Err(VerificationError::ReceiptFormatError);
+    }
+    Ok(info)
+}
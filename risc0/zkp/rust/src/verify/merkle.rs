@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use alloc::vec::Vec;
+use core::marker::PhantomData;
 
 use crate::{
     core::sha::{Digest, Sha},
@@ -38,7 +39,9 @@ pub struct MerkleTreeVerifier<'a, S: Sha> {
     top: &'a [Digest],
 
     // These are the rest of the tree.  These have the virtual indexes [1, top_size).
-    rest: Vec<S::DigestPtr>,
+    rest: Vec<Digest>,
+
+    _sha: PhantomData<S>,
 }
 
 // Translates from virtual indexes to indexes in the "top" and "rest" arrays.
@@ -80,13 +83,21 @@ impl SplitMerkleIndex for MerkleTreeParams {
 impl<'a, S: Sha> MerkleTreeVerifier<'a, S> {
     /// Constructs a new MerkleTreeVerifier by making the params, and then
     /// computing the root hashes from the top level hashes.
-    pub fn new(iop: &mut ReadIOP<'a, S>, row_size: usize, col_size: usize, queries: usize) -> Self {
+    pub fn new(
+        iop: &mut ReadIOP<'a, S>,
+        row_size: usize,
+        col_size: usize,
+        queries: usize,
+    ) -> Result<Self, VerificationError> {
         let params = MerkleTreeParams::new(row_size, col_size, queries);
 
         // Fill top vector with digests from IOP.
-        let top = iop.read_pod_slice(params.top_size);
+        let top: &[Digest] = iop.read_pod_slice(params.top_size);
+        if !top.iter().all(|digest| iop.get_sha().is_canonical(digest)) {
+            return Err(VerificationError::InvalidProof);
+        }
         // Populate hashes up to the root of the tree.
-        let mut rest = Vec::<S::DigestPtr>::with_capacity(params.top_size - 1);
+        let mut rest = Vec::<Digest>::with_capacity(params.top_size - 1);
 
         let fill_rest = rest.spare_capacity_mut();
 
@@ -114,9 +125,14 @@ impl<'a, S: Sha> MerkleTreeVerifier<'a, S> {
         };
 
         // Commit to root (index 1).
-        let verifier = MerkleTreeVerifier { params, top, rest };
+        let verifier = MerkleTreeVerifier {
+            params,
+            top,
+            rest,
+            _sha: PhantomData,
+        };
         iop.commit(verifier.root());
-        verifier
+        Ok(verifier)
     }
 
     /// Returns the root hash of the tree.
@@ -124,7 +140,7 @@ impl<'a, S: Sha> MerkleTreeVerifier<'a, S> {
         if self.rest.is_empty() {
             &self.top[self.params.idx_to_top(1)]
         } else {
-            &*self.rest[self.params.idx_to_rest(1)]
+            &self.rest[self.params.idx_to_rest(1)]
         }
     }
 
@@ -155,6 +171,9 @@ impl<'a, S: Sha> MerkleTreeVerifier<'a, S> {
                 [other] => other,
                 _ => unreachable!(),
             };
+            if !iop.get_sha().is_canonical(other) {
+                return Err(VerificationError::InvalidProof);
+            }
             // Now ascend to the parent index, and compute the hash there.
             idx /= 2;
             if low_bit == 1 {
@@ -170,7 +189,7 @@ impl<'a, S: Sha> MerkleTreeVerifier<'a, S> {
         } else {
             &self.rest[self.params.idx_to_rest(idx)]
         };
-        if *present_hash == *cur {
+        if *present_hash == cur {
             Ok(out)
         } else {
             Err(VerificationError::InvalidProof)
@@ -15,6 +15,7 @@
 pub mod adapter;
 pub mod ffpu;
 mod fri;
+mod inspect;
 pub(crate) mod merkle;
 pub mod read_iop;
 
@@ -23,22 +24,21 @@ use core::{fmt, iter::zip};
 
 #[cfg(feature = "host")]
 pub use host::CpuVerifyHal;
+pub use inspect::{inspect_seal, SealInfo};
 
 use self::adapter::VerifyAdapter;
 // use log::debug;
 use crate::{
     adapter::{CircuitInfo, TapsProvider},
     core::{
-        fp::Fp,
-        fp4::Fp4,
         log2_ceil,
-        rou::{ROU_FWD, ROU_REV},
+        ntt::bit_rev_32,
         sha::{Digest, Sha},
     },
-    field::{baby_bear::BabyBear, Elem},
+    field::{Elem, ExtElem, Field, RootsOfUnity},
     taps::RegisterGroup,
     verify::{fri::fri_verify, merkle::MerkleTreeVerifier, read_iop::ReadIOP},
-    CHECK_SIZE, INV_RATE, MAX_CYCLES_PO2, QUERIES,
+    ProofParams,
 };
 
 #[derive(Debug)]
@@ -47,6 +47,7 @@ pub enum VerificationError {
     MethodVerificationError,
     MerkleQueryOutOfRange { idx: usize, rows: usize },
     InvalidProof,
+    JournalMismatch,
 }
 
 impl fmt::Display for VerificationError {
@@ -60,27 +61,45 @@ impl fmt::Display for VerificationError {
                 idx, rows
             ),
             VerificationError::InvalidProof => write!(f, "Verification indicates proof is invalid"),
+            VerificationError::JournalMismatch => write!(f, "Journal does not match seal output"),
         }
     }
 }
 
 pub trait VerifyHal {
     type Sha: Sha;
+    type Field: Field;
 
     fn sha(&self) -> &Self::Sha;
 
     fn debug(&self, msg: &str);
 
-    fn compute_polynomial(&self, u: &[Fp4], poly_mix: Fp4, out: &[Fp], mix: &[Fp]) -> Fp4;
-
-    fn fold_eval(&self, io: &mut [Fp4], mix: Fp4, inv_wk: Fp) -> Fp4;
+    fn compute_polynomial(
+        &self,
+        u: &[<Self::Field as Field>::ExtElem],
+        poly_mix: <Self::Field as Field>::ExtElem,
+        out: &[<Self::Field as Field>::Elem],
+        mix: &[<Self::Field as Field>::Elem],
+    ) -> <Self::Field as Field>::ExtElem;
+
+    fn fold_eval(
+        &self,
+        io: &mut [<Self::Field as Field>::ExtElem],
+        mix: <Self::Field as Field>::ExtElem,
+        inv_wk: <Self::Field as Field>::Elem,
+    ) -> <Self::Field as Field>::ExtElem;
 
     /// Evaluate a polynomial whose coefficients are in the extension field at a
     /// point.
-    fn poly_eval(&self, coeffs: &[Fp4], x: Fp4, y: Fp) -> Fp4 {
-        let mut mul_fp = Fp::ONE;
-        let mut mul_fp4 = Fp4::ONE;
-        let mut tot = Fp4::ZERO;
+    fn poly_eval(
+        &self,
+        coeffs: &[<Self::Field as Field>::ExtElem],
+        x: <Self::Field as Field>::ExtElem,
+        y: <Self::Field as Field>::Elem,
+    ) -> <Self::Field as Field>::ExtElem {
+        let mut mul_fp = <Self::Field as Field>::Elem::ONE;
+        let mut mul_fp4 = <Self::Field as Field>::ExtElem::ONE;
+        let mut tot = <Self::Field as Field>::ExtElem::ZERO;
         for i in 0..coeffs.len() {
             tot += coeffs[i] * mul_fp * mul_fp4;
             mul_fp *= y;
@@ -95,7 +114,12 @@ mod host {
     use super::*;
     use crate::{
         adapter::{PolyExt, PolyExtContext},
-        core::ntt::{bit_reverse, interpolate_ntt},
+        core::{
+            fp::Fp,
+            fp4::Fp4,
+            ntt::{bit_reverse, interpolate_ntt},
+        },
+        field::baby_bear::BabyBear,
     };
 
     pub struct CpuVerifyHal<'a, S: Sha, C: PolyExt> {
@@ -111,6 +135,7 @@ mod host {
 
     impl<'a, S: Sha, C: PolyExt> VerifyHal for CpuVerifyHal<'a, S, C> {
         type Sha = S;
+        type Field = BabyBear;
 
         fn sha(&self) -> &Self::Sha {
             self.sha
@@ -121,7 +146,7 @@ mod host {
         }
 
         fn fold_eval(&self, io: &mut [Fp4], mix: Fp4, inv_wk: Fp) -> Fp4 {
-            interpolate_ntt(io);
+            interpolate_ntt::<Fp, Fp4>(io);
             bit_reverse(io);
             poly_eval(io, mix, inv_wk)
         }
@@ -147,10 +172,12 @@ mod host {
     }
 }
 
+/// Verify `seal`, which must have been proven with `params`.
 pub fn verify<'a, H, C, F>(
     hal: &'a H,
     circuit: &C,
     seal: &'a [u32],
+    params: &ProofParams,
     check_code: F,
 ) -> Result<(), VerificationError>
 where
@@ -158,15 +185,22 @@ where
     C: CircuitInfo + TapsProvider,
     F: Fn(u32, &Digest) -> bool,
 {
-    if seal.len() == 0 {
+    // Check the layout of the seal up front, so that reading it can't run
+    // off the end.
+    let info = inspect_seal::<H::Field, C>(circuit, seal)?;
+    if info.params != *params {
         return Err(VerificationError::ReceiptFormatError);
     }
+    let seal = &seal[..seal.len() - ProofParams::SEAL_WORDS];
 
-    let mut adapter = VerifyAdapter::new(circuit);
+    let mut adapter = VerifyAdapter::<H::Field, _>::new(circuit);
     let taps = adapter.taps();
 
     // Make IOP
     let mut iop = ReadIOP::new(hal.sha(), seal);
+    if params.is_committed() {
+        iop.commit(&hal.sha().hash_words(&params.to_words()));
+    }
 
     // Read any execution state
     hal.debug("> execute");
@@ -175,9 +209,12 @@ where
 
     // Get the size
     let po2 = adapter.po2();
-    assert!(po2 as usize <= MAX_CYCLES_PO2);
+    if po2 as usize > params.max_po2::<H::Field>() {
+        return Err(VerificationError::ReceiptFormatError);
+    }
     let size = 1 << po2;
-    let domain = INV_RATE * size;
+    let inv_rate = params.inv_rate;
+    let domain = inv_rate * size;
     // debug!("size = {size}, po2 = {po2}");
 
     // Get taps and compute sizes
@@ -188,10 +225,10 @@ where
 
     // Get code and data merkle roots
     hal.debug("code_merkle");
-    let code_merkle = MerkleTreeVerifier::new(&mut iop, domain, code_size, QUERIES);
+    let code_merkle = MerkleTreeVerifier::new(&mut iop, domain, code_size, params.queries)?;
     // debug!("codeRoot = {}", code_merkle.root());
     hal.debug("data_merkle");
-    let data_merkle = MerkleTreeVerifier::new(&mut iop, domain, data_size, QUERIES);
+    let data_merkle = MerkleTreeVerifier::new(&mut iop, domain, data_size, params.queries)?;
     // debug!("dataRoot = {}", data_merkle.root());
 
     // Verify code is valid
@@ -204,24 +241,26 @@ where
     adapter.accumulate(&mut iop);
 
     hal.debug("accum_merkle");
-    let accum_merkle = MerkleTreeVerifier::new(&mut iop, domain, accum_size, QUERIES);
+    let accum_merkle = MerkleTreeVerifier::new(&mut iop, domain, accum_size, params.queries)?;
     // debug!("accumRoot = {}", accum_merkle.root());
 
     // Set the poly mix value
-    let poly_mix = Fp4::random(&mut iop);
+    let poly_mix = <H::Field as Field>::ExtElem::random(&mut iop);
 
+    let ext_size = <H::Field as Field>::ExtElem::EXT_SIZE;
+    let check_size = inv_rate * ext_size;
     hal.debug("check_merkle");
-    let check_merkle = MerkleTreeVerifier::new(&mut iop, domain, CHECK_SIZE, QUERIES);
+    let check_merkle = MerkleTreeVerifier::new(&mut iop, domain, check_size, params.queries)?;
     // debug!("checkRoot = {}", check_merkle.root());
 
-    let z = Fp4::random(&mut iop);
+    let z = <H::Field as Field>::ExtElem::random(&mut iop);
     // debug!("Z = {z:?}");
-    let back_one = Fp::from(ROU_REV[po2 as usize]);
+    let back_one = <H::Field as Field>::Elem::ROU_REV[po2 as usize];
 
     // Read the U coeffs + commit their hash
     let num_taps = taps.tap_size();
-    let coeff_u = iop.read_pod_slice(num_taps + CHECK_SIZE);
-    let hash_u = *hal.sha().hash_raw_pod_slice(coeff_u);
+    let coeff_u: &[<H::Field as Field>::ExtElem] = iop.read_pod_slice(num_taps + check_size);
+    let hash_u = hal.sha().hash_raw_pod_slice(coeff_u);
     iop.commit(&hash_u);
 
     // Now, convert to evaluated values
@@ -229,8 +268,12 @@ where
     let mut eval_u = Vec::with_capacity(num_taps);
     for reg in taps.regs() {
         for i in 0..reg.size() {
-            let x = back_one.pow(reg.back(i)) * z;
-            let fx = hal.poly_eval(&coeff_u[cur_pos..(cur_pos + reg.size())], x, Fp::ONE);
+            let x = z * back_one.pow(reg.back(i));
+            let fx = hal.poly_eval(
+                &coeff_u[cur_pos..(cur_pos + reg.size())],
+                x,
+                <H::Field as Field>::Elem::ONE,
+            );
             eval_u.push(fx);
         }
         cur_pos += reg.size();
@@ -243,36 +286,43 @@ where
     hal.debug("< compute_polynomial");
     // debug!("Result = {result:?}");
 
-    // Now generate the check polynomial
-    let mut check = Fp4::ZERO;
-    let remap = [0, 2, 1, 3];
-    let fp0 = Fp::from(0 as u32);
-    let fp1 = Fp::from(1 as u32);
-    for i in 0..4 {
-        let rmi = remap[i];
-        check += coeff_u[num_taps + rmi + 0] * z.pow(i) * Fp4::new(fp1, fp0, fp0, fp0);
-        check += coeff_u[num_taps + rmi + 4] * z.pow(i) * Fp4::new(fp0, fp1, fp0, fp0);
-        check += coeff_u[num_taps + rmi + 8] * z.pow(i) * Fp4::new(fp0, fp0, fp1, fp0);
-        check += coeff_u[num_taps + rmi + 12] * z.pow(i) * Fp4::new(fp0, fp0, fp0, fp1);
+    // Now generate the check polynomial.  The check group holds inv_rate
+    // polynomials for each component of the extension field, in bit reversed
+    // order; see the split of the check polynomial in the prover.
+    let mut check = <H::Field as Field>::ExtElem::ZERO;
+    let rev_bits = log2_ceil(inv_rate);
+    for i in 0..inv_rate {
+        let rmi = (bit_rev_32(i as u32) >> (32 - rev_bits)) as usize;
+        for j in 0..ext_size {
+            let basis = <H::Field as Field>::ExtElem::from_subelems((0..ext_size).map(|k| {
+                if k == j {
+                    <H::Field as Field>::Elem::ONE
+                } else {
+                    <H::Field as Field>::Elem::ZERO
+                }
+            }));
+            check += coeff_u[num_taps + rmi + j * inv_rate] * z.pow(i) * basis;
+        }
     }
-    check *= (Fp4::from_u32(3) * z).pow(size) - Fp4::ONE;
+    let three = <H::Field as Field>::Elem::from_u64(3);
+    check *= (z * three).pow(size) - <H::Field as Field>::ExtElem::ONE;
     // debug!("Check = {check:?}");
     if check != result {
         return Err(VerificationError::InvalidProof);
     }
 
     // Set the mix mix value
-    let mix = Fp4::random(&mut iop);
+    let mix = <H::Field as Field>::ExtElem::random(&mut iop);
     // debug!("mix = {mix:?}");
 
     // Make the mixed U polynomials
-    let mut combo_u: Vec<Vec<Fp4>> = Vec::with_capacity(combo_count + 1);
+    let mut combo_u: Vec<Vec<<H::Field as Field>::ExtElem>> = Vec::with_capacity(combo_count + 1);
     combo_u.extend(
         (0..combo_count)
             .into_iter()
-            .map(|i| vec![Fp4::ZERO; taps.get_combo(i).size()]),
+            .map(|i| vec![<H::Field as Field>::ExtElem::ZERO; taps.get_combo(i).size()]),
     );
-    let mut cur_mix = Fp4::ONE;
+    let mut cur_mix = <H::Field as Field>::ExtElem::ONE;
     cur_pos = 0;
     let mut tap_mix_pows = Vec::with_capacity(taps.reg_count());
     for reg in taps.regs() {
@@ -290,14 +340,14 @@ where
     );
     // debug!("cur_mix: {cur_mix:?}, cur_pos: {cur_pos}");
     // Handle check group
-    combo_u.push(vec![Fp4::ZERO]);
+    combo_u.push(vec![<H::Field as Field>::ExtElem::ZERO]);
     assert_eq!(
         combo_u.len(),
         combo_count + 1,
         "Miscalculated capacity for combo_u"
     );
-    let mut check_mix_pows = Vec::with_capacity(CHECK_SIZE);
-    for _ in 0..CHECK_SIZE {
+    let mut check_mix_pows = Vec::with_capacity(check_size);
+    for _ in 0..check_size {
         combo_u[combo_count][0] += cur_mix * coeff_u[cur_pos];
         cur_pos += 1;
         check_mix_pows.push(cur_mix);
@@ -305,44 +355,47 @@ where
     }
     assert_eq!(
         check_mix_pows.len(),
-        CHECK_SIZE,
+        check_size,
         "Miscalculated capacity for check_mix_pows"
     );
     // debug!("cur_mix: {cur_mix:?}");
 
-    let gen = Fp::new(ROU_FWD[log2_ceil(domain)]);
+    let gen = <H::Field as Field>::Elem::ROU_FWD[log2_ceil(domain)];
     // debug!("FRI-verify, size = {size}");
     fri_verify(
         hal,
         &mut iop,
+        params,
         size,
-        |iop: &mut ReadIOP<_>, idx: usize| -> Result<Fp4, VerificationError> {
+        |iop: &mut ReadIOP<_>,
+         idx: usize|
+         -> Result<<H::Field as Field>::ExtElem, VerificationError> {
             hal.debug("fri_verify");
-            let x = Fp4::from_fp(gen.pow(idx));
+            let x = <H::Field as Field>::ExtElem::from_subfield(&gen.pow(idx));
             let rows = [
-                accum_merkle.verify::<BabyBear>(iop, idx)?,
-                code_merkle.verify::<BabyBear>(iop, idx)?,
-                data_merkle.verify::<BabyBear>(iop, idx)?,
+                accum_merkle.verify::<H::Field>(iop, idx)?,
+                code_merkle.verify::<H::Field>(iop, idx)?,
+                data_merkle.verify::<H::Field>(iop, idx)?,
             ];
-            let check_row = check_merkle.verify::<BabyBear>(iop, idx)?;
-            let mut tot = vec![Fp4::ZERO; combo_count + 1];
+            let check_row = check_merkle.verify::<H::Field>(iop, idx)?;
+            let mut tot = vec![<H::Field as Field>::ExtElem::ZERO; combo_count + 1];
             for (reg, cur) in zip(taps.regs(), tap_mix_pows.iter()) {
                 tot[reg.combo_id()] += *cur * rows[reg.group() as usize][reg.offset()];
             }
-            for (i, cur) in zip(0..CHECK_SIZE, check_mix_pows.iter()) {
+            for (i, cur) in zip(0..check_size, check_mix_pows.iter()) {
                 tot[combo_count] += *cur * check_row[i];
             }
-            let mut ret = Fp4::ZERO;
+            let mut ret = <H::Field as Field>::ExtElem::ZERO;
             for i in 0..combo_count {
-                let num = tot[i] - hal.poly_eval(&combo_u[i], x, Fp::ONE);
-                let mut divisor = Fp4::ONE;
+                let num = tot[i] - hal.poly_eval(&combo_u[i], x, <H::Field as Field>::Elem::ONE);
+                let mut divisor = <H::Field as Field>::ExtElem::ONE;
                 for back in taps.get_combo(i).slice() {
                     divisor *= x - z * back_one.pow(*back as usize);
                 }
                 ret += num * divisor.inv();
             }
             let check_num = tot[combo_count] - combo_u[combo_count][0];
-            let check_div = x - z.pow(INV_RATE);
+            let check_div = x - z.pow(inv_rate);
             ret += check_num * check_div.inv();
             Ok(ret)
         },
@@ -30,6 +30,8 @@ constexpr size_t kGPIO_SendRecvAddr = 0x01F0001C;
 constexpr size_t kGPIO_CycleCount = 0x01F00020;
 constexpr size_t kGPIO_InsecureShaCompress = 0x01F00024;
 constexpr size_t kGPIO_InsecureShaHash = 0x01F00028;
+constexpr size_t kGPIO_InputWindow = 0x01F00038;
+constexpr size_t kGPIO_OutOfMemory = 0x01F0003C;
 
 // Standard ZKVM channels; must match zkvm/sdk/rust/platform/src/io.rs.
 
@@ -39,6 +41,10 @@ constexpr uint32_t kSendRecvChannel_InitialInput = 0;
 constexpr uint32_t kSendRecvChannel_Stdout = 1;
 // Write bytes to standard error
 constexpr uint32_t kSendRecvChannel_Stderr = 2;
+// Request the state saved by the previous segment
+constexpr uint32_t kSendRecvChannel_SegmentResume = 3;
+// Save the state for the next segment
+constexpr uint32_t kSendRecvChannel_SegmentSuspend = 4;
 
 // To invoke accelerated SHA, the guest writes ShaDescriptor structs
 // in sequence to the "SHA" memory region.  Once the ShaDescriptor has
@@ -164,7 +170,10 @@ inline const uint32_t volatile* GPIO_CycleCount() {
   return reinterpret_cast<const uint32_t volatile*>(kGPIO_CycleCount);
 }
 
-// TODO(nils) Document GetKey.
+// To request a named key, write a pointer to a GetKeyDescriptor to
+// GPIO_GetKey.  The host writes the key to `addr`, which must not
+// have been touched by the guest yet.  `mode` is a KeyMode (see
+// zkvm/sdk/cpp/guest/key.h).
 struct GetKeyDescriptor {
   uint32_t name;
   uint32_t addr;
@@ -175,4 +184,27 @@ inline volatile GetKeyDescriptor* volatile* GPIO_GetKey() {
   return reinterpret_cast<volatile GetKeyDescriptor* volatile*>(kGPIO_GetKey);
 }
 
+// To move the area the host sends data to once the INPUT region runs low,
+// write a pointer to an InputWindowDescriptor to GPIO_InputWindow.  All
+// further data from the host is written from `addr`, and must fit in `size`
+// bytes.  The window must not have been touched by the guest yet.
+struct InputWindowDescriptor {
+  uint32_t size;
+  uint32_t addr;
+};
+
+inline volatile InputWindowDescriptor* volatile* GPIO_InputWindow() {
+  return reinterpret_cast<volatile InputWindowDescriptor* volatile*>(kGPIO_InputWindow);
+}
+
+// When the heap is exhausted, the guest writes a pointer to an
+// OutOfMemoryDescriptor to GPIO_OutOfMemory, which the host treats as a
+// fault.  All sizes are in bytes.
+struct OutOfMemoryDescriptor {
+  uint32_t requested;
+  uint32_t align;
+  uint32_t used;
+  uint32_t limit;
+};
+
 } // namespace risc0
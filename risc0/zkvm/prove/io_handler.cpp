@@ -50,9 +50,21 @@ void IoHandler::onFault(const std::string& msg) {
   throw std::runtime_error(msg);
 }
 
+Key IoHandler::onGetKey(const std::string& name, uint32_t mode) {
+  KeyStore& store = getKeyStore();
+  if (mode == 0 && store.count(name)) {
+    throw std::runtime_error("GetKey Mode = NEW and key exists: " + name);
+  }
+  if (mode == 1 && !store.count(name)) {
+    throw std::runtime_error("GetKey Mode = EXISTING and key does not exist: " + name);
+  }
+  return store[name];
+}
+
 MemoryHandler::MemoryHandler() : MemoryHandler(nullptr) {}
 
-MemoryHandler::MemoryHandler(IoHandler* io) : io(io), cur_host_to_guest_offset(kMemInputStart) {}
+MemoryHandler::MemoryHandler(IoHandler* io)
+    : io(io), cur_host_to_guest_offset(kMemInputStart), cur_host_to_guest_end(kMemInputEnd) {}
 
 void MemoryHandler::onInit(MemoryState& mem) {
   if (io) {
@@ -61,7 +73,7 @@ void MemoryHandler::onInit(MemoryState& mem) {
 }
 
 void MemoryHandler::sendToGuest(MemoryState& mem, const BufferU32& words) {
-  if ((cur_host_to_guest_offset + words.size()) >= kMemInputEnd) {
+  if (cur_host_to_guest_offset + words.size() * sizeof(uint32_t) > cur_host_to_guest_end) {
     throw(std::runtime_error("Read buffer overrun"));
   }
   LOG(1, "Filling " << words.size() << " words of guest input area");
@@ -123,14 +135,7 @@ void MemoryHandler::onWrite(MemoryState& mem, uint32_t cycle, uint32_t addr, uin
     LOG(1, "  addr = " << hex(desc.addr));
     LOG(1, "  key = " << str);
     LOG(1, "  mode = " << desc.mode);
-    KeyStore& store = io->getKeyStore();
-    if (desc.mode == 0 && store.count(str)) {
-      throw std::runtime_error("GetKey Mode = NEW and key exists: " + str);
-    }
-    if (desc.mode == 1 && !store.count(str)) {
-      throw std::runtime_error("GetKey Mode = EXISTING and key does not exist: " + str);
-    }
-    const Key& key = store[str];
+    Key key = io->onGetKey(str, desc.mode);
     mem.store(desc.addr, reinterpret_cast<const uint8_t*>(&key), sizeof(Key));
   } break;
   case kGPIO_SendRecvAddr: {
@@ -152,11 +157,16 @@ void MemoryHandler::onWrite(MemoryState& mem, uint32_t cycle, uint32_t addr, uin
       words.push_back(result_bytes);
       sendToGuest(mem, words);
 
-      // Send the buffer.
+      // Send the buffer, unless it doesn't fit in the rest of the window, in
+      // which case the guest makes a new window large enough to hold it.
       words.clear();
       words.resize((result.size() + sizeof(uint32_t) - 1) / sizeof(uint32_t), 0);
       memcpy(words.data(), result.data(), result_bytes);
-      sendToGuest(mem, words);
+      if (cur_host_to_guest_offset + words.size() * sizeof(uint32_t) > cur_host_to_guest_end) {
+        pending_input = std::move(words);
+      } else {
+        sendToGuest(mem, words);
+      }
     } else {
       throw std::runtime_error("SendRecv called with no IO handler set");
     }
@@ -171,6 +181,29 @@ void MemoryHandler::onWrite(MemoryState& mem, uint32_t cycle, uint32_t addr, uin
     cyclebuf.push_back(cycle);
     sendToGuest(mem, cyclebuf);
   } break;
+  case kGPIO_OutOfMemory: {
+    LOG(1, "MemoryHandler::onWrite> GPIO_OutOfMemory, descriptor at " << hex(value));
+    OutOfMemoryDescriptor desc;
+    mem.loadRegion(value, &desc, sizeof(desc));
+    throw std::runtime_error("Guest out of memory: requested " + std::to_string(desc.requested) +
+                             " bytes with " + std::to_string(desc.used) + " of " +
+                             std::to_string(desc.limit) + " bytes of heap in use");
+  } break;
+  case kGPIO_InputWindow: {
+    LOG(1, "MemoryHandler::onWrite> GPIO_InputWindow, descriptor at " << hex(value));
+    InputWindowDescriptor desc;
+    mem.loadRegion(value, &desc, sizeof(desc));
+    if (desc.addr % sizeof(uint32_t) != 0 || desc.addr + desc.size > kMemGPIOStart) {
+      throw std::runtime_error("Invalid input window");
+    }
+    cur_host_to_guest_offset = desc.addr;
+    cur_host_to_guest_end = desc.addr + desc.size;
+    if (pending_input) {
+      BufferU32 words = std::move(*pending_input);
+      pending_input.reset();
+      sendToGuest(mem, words);
+    }
+  } break;
   case kGPIO_InsecureShaCompress: {
     LOG(1, "MemoryHandler::onWrite> GPIO_InsecureSha256Compress, descriptor at " << hex(value));
     InsecureShaCompressDescriptor desc;
@@ -20,6 +20,7 @@
 #include "risc0/zkvm/platform/io.h"
 
 #include <map>
+#include <optional>
 #include <set>
 #include <vector>
 
@@ -65,6 +66,10 @@ struct IoHandler {
   virtual void onCommit(const BufferU8& data) {}
   virtual void onFault(const std::string& msg);
   virtual KeyStore& getKeyStore() = 0;
+
+  // Returns the key called `name`, where `mode` is one of the GetKey modes.
+  // By default keys come from getKeyStore().
+  virtual Key onGetKey(const std::string& name, uint32_t mode);
 };
 
 class MemoryHandler {
@@ -99,6 +104,14 @@ private:
   // Memory address of current host->guest transmission.  The host can only
   // write to each memory location once, so this advances after each write.
   uint32_t cur_host_to_guest_offset;
+
+  // End of the window the host is currently sending data to, which starts
+  // out as the INPUT region.
+  uint32_t cur_host_to_guest_end;
+
+  // A sendrecv response which didn't fit in the rest of the window, which is
+  // sent once the guest moves to a new window.
+  std::optional<BufferU32> pending_input;
 };
 
 struct StepContext {
@@ -16,6 +16,8 @@ use std::default::Default;
 use std::{fs, io::Write};
 
 use clap::Parser;
+#[cfg(feature = "pure-prove")]
+use risc0_zkvm::host::{GdbStub, Profiler, TraceCallback, TraceEvent, TraceFormat, TraceWriter};
 use risc0_zkvm::host::{MethodId, Prover, ProverOpts, Receipt, DEFAULT_METHOD_ID_LIMIT};
 
 /// Generates a MethodID for a given RISC-V ELF binary.
@@ -51,6 +53,64 @@ struct Args {
     /// Limit the number of hash table entries to compute.
     #[clap(short, long, default_value_t = DEFAULT_METHOD_ID_LIMIT)]
     limit: u32,
+
+    /// Write a trace of every instruction executed to this file.
+    #[cfg(feature = "pure-prove")]
+    #[clap(long)]
+    trace: Option<String>,
+
+    /// Format of the trace file.
+    #[cfg(feature = "pure-prove")]
+    #[clap(long, value_parser = ["json", "binary"], default_value = "json")]
+    trace_format: String,
+
+    /// Write the cycles spent in each guest call stack to this file, in
+    /// collapsed-stack format.
+    #[cfg(feature = "pure-prove")]
+    #[clap(long)]
+    profile: Option<String>,
+
+    /// Wait for GDB to connect on this port, and run the guest under its
+    /// control.
+    #[cfg(feature = "pure-prove")]
+    #[clap(long)]
+    gdb: Option<u16>,
+
+    /// Abort the guest if it runs for this many cycles without halting.
+    #[cfg(feature = "pure-prove")]
+    #[clap(long)]
+    max_cycles: Option<usize>,
+}
+
+/// Passes the trace on to the trace file and the profiler, whichever were
+/// asked for.
+#[cfg(feature = "pure-prove")]
+struct TraceSinks<'a, W: Write> {
+    trace_writer: Option<&'a mut TraceWriter<W>>,
+    profiler: Option<&'a mut Profiler>,
+}
+
+#[cfg(feature = "pure-prove")]
+impl<W: Write> TraceCallback for TraceSinks<'_, W> {
+    fn on_instruction(&mut self, event: &TraceEvent) -> anyhow::Result<()> {
+        if let Some(trace_writer) = self.trace_writer.as_mut() {
+            trace_writer.on_instruction(event)?;
+        }
+        if let Some(profiler) = self.profiler.as_mut() {
+            profiler.on_instruction(event)?;
+        }
+        Ok(())
+    }
+
+    fn on_exit(&mut self, cycle: u32) -> anyhow::Result<()> {
+        if let Some(trace_writer) = self.trace_writer.as_mut() {
+            trace_writer.on_exit(cycle)?;
+        }
+        if let Some(profiler) = self.profiler.as_mut() {
+            profiler.on_exit(cycle)?;
+        }
+        Ok(())
+    }
 }
 
 fn read_method_id(
@@ -119,6 +179,48 @@ fn main() {
 
     let opts: ProverOpts =
         ProverOpts::default().with_skip_seal(args.skip_seal || args.receipt.is_none());
+    #[cfg(feature = "pure-prove")]
+    let mut trace_writer = args.trace.as_ref().map(|trace_file| {
+        let format = match args.trace_format.as_str() {
+            "binary" => TraceFormat::Binary,
+            _ => TraceFormat::JsonLines,
+        };
+        let writer = std::io::BufWriter::new(fs::File::create(trace_file).unwrap());
+        TraceWriter::new(writer, format)
+    });
+    #[cfg(feature = "pure-prove")]
+    let mut profiler = args
+        .profile
+        .as_ref()
+        .map(|_| Profiler::new(&elf_contents).unwrap());
+    #[cfg(feature = "pure-prove")]
+    let opts = if trace_writer.is_some() || profiler.is_some() {
+        opts.with_trace_callback(TraceSinks {
+            trace_writer: trace_writer.as_mut(),
+            profiler: profiler.as_mut(),
+        })
+    } else {
+        opts
+    };
+    #[cfg(feature = "pure-prove")]
+    let opts = match args.gdb {
+        Some(port) => {
+            eprintln!("Waiting for GDB to connect on port {port}");
+            opts.with_debugger(GdbStub::listen(port).unwrap())
+        }
+        None => opts,
+    };
+    // Keep guest log messages off stdout, which carries the guest's output.
+    #[cfg(feature = "pure-prove")]
+    let opts = opts.with_log_callback(|cycle, msg: &str| -> anyhow::Result<()> {
+        eprintln!("R0VM[{cycle}]> {msg}");
+        Ok(())
+    });
+    #[cfg(feature = "pure-prove")]
+    let opts = match args.max_cycles {
+        Some(max_cycles) => opts.with_max_cycles(max_cycles),
+        None => opts,
+    };
 
     let mut prover =
         Prover::new_with_opts(&elf_contents, method_id.as_slice().unwrap(), opts).unwrap();
@@ -158,4 +260,15 @@ fn main() {
         eprintln!("Writing {} bytes of output to stdout", output.len());
     }
     std::io::stdout().write_all(output).unwrap();
+
+    // The prover holds on to the trace callback, and so to the profiler.
+    drop(prover);
+    #[cfg(feature = "pure-prove")]
+    if let (Some(profile_file), Some(profiler)) = (args.profile, profiler) {
+        let writer = std::io::BufWriter::new(fs::File::create(&profile_file).unwrap());
+        profiler.write_collapsed(writer).unwrap();
+        if args.verbose > 0 {
+            eprintln!("Wrote profile to {}", profile_file);
+        }
+    }
 }
@@ -92,3 +92,45 @@ fn stdio_outputs_in_receipt_without_seal() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+#[cfg(feature = "pure-prove")]
+fn profile_counts_last_instruction() -> Result<()> {
+    let temp = TempDir::new().unwrap();
+    let trace_file = temp.child("trace.bin");
+    let profile_file = temp.child("profile.txt");
+
+    let mut cmd = Command::cargo_bin("r0vm")?;
+    cmd.arg("--elf")
+        .arg(risc0_zkvm_methods::STANDARD_LIB_PATH)
+        .arg("--trace")
+        .arg(&*trace_file)
+        .arg("--trace-format")
+        .arg("binary")
+        .arg("--profile")
+        .arg(&*profile_file);
+    cmd.assert().stdout(EXPECTED_STDOUT).success();
+
+    // Find the cycles of the first and last instructions in the trace.
+    let trace: Vec<u32> = std::fs::read(&trace_file)?
+        .chunks_exact(4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+        .collect();
+    let mut cycles = Vec::new();
+    let mut rest = trace.as_slice();
+    while !rest.is_empty() {
+        cycles.push(rest[0]);
+        rest = &rest[6 + 3 * rest[5] as usize..];
+    }
+    let (first, last) = (cycles[0], *cycles.last().unwrap());
+
+    // The profile counts the cycles of the last instruction too, which are
+    // only known once the guest halts.
+    let total: u32 = std::fs::read_to_string(&profile_file)?
+        .lines()
+        .map(|line| line.rsplit(' ').next().unwrap().parse::<u32>().unwrap())
+        .sum();
+    assert!(total > last - first, "{total} <= {last} - {first}");
+
+    Ok(())
+}
@@ -15,6 +15,7 @@
 #include "risc0/zkvm/sdk/cpp/host/c_api.h"
 
 #include <memory>
+#include <stdexcept>
 
 #include "risc0/core/archive.h"
 #include "risc0/core/log.h"
@@ -159,6 +160,10 @@ void risc0_prover_set_sendrecv_handler(
         channel_id,
         [=](uint32_t channel_id, const std::vector<uint8_t>& buf) -> std::vector<uint8_t> {
           risc0_u8buffer* to_guest_buf = callback(channel_id, buf.data(), buf.size(), cbdata);
+          if (!to_guest_buf) {
+            // The caller keeps the handler's error and reports it instead.
+            throw std::runtime_error("sendrecv handler failed");
+          }
           risc0_u8buffer to_guest_vec = std::move(*to_guest_buf);
           delete to_guest_buf;
 
@@ -167,6 +172,23 @@ void risc0_prover_set_sendrecv_handler(
   });
 }
 
+void risc0_prover_set_getkey_handler(
+    risc0_error* err,
+    risc0_prover* ptr,
+    bool (*callback)(const char* name, size_t len, uint32_t mode, uint32_t* key, void* cbdata),
+    void* cbdata) {
+  return ffi_wrap_void(err, [&] {
+    ptr->prover->setGetKeyHandler([=](const std::string& name, uint32_t mode) -> risc0::Key {
+      risc0::Key key;
+      if (!callback(name.data(), name.size(), mode, key.data, cbdata)) {
+        // The caller keeps the provider's error and reports it instead.
+        throw std::runtime_error("getkey handler failed");
+      }
+      return key;
+    });
+  });
+}
+
 risc0_receipt* risc0_receipt_new(risc0_error* err,
                                  const uint8_t* journal,
                                  const size_t journal_len,
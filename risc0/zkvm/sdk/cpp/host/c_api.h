@@ -99,6 +99,8 @@ risc0_receipt* risc0_prover_run(risc0_error* err, risc0_prover* ptr);
 
 void risc0_prover_set_skip_seal(risc0_error* err, risc0_prover* ptr, bool skip_seal);
 
+// The callback returns the response to send to the guest, or null if the
+// request failed, which aborts risc0_prover_run.
 void risc0_prover_set_sendrecv_handler(
     risc0_error* err,
     risc0_prover* ptr,
@@ -110,6 +112,15 @@ void risc0_prover_set_sendrecv_handler(
 // Receipt
 //
 
+// The callback writes the four words of the key called `name` to `key` and
+// returns true, or returns false if it can't provide the key, which aborts
+// risc0_prover_run.  `mode` is one of the GetKey modes.
+void risc0_prover_set_getkey_handler(
+    risc0_error* err,
+    risc0_prover* ptr,
+    bool (*callback)(const char* name, size_t len, uint32_t mode, uint32_t* key, void* cbdata),
+    void* cbdata);
+
 risc0_receipt* risc0_receipt_new(risc0_error* err,
                                  const uint8_t* journal,
                                  const size_t journal_len,
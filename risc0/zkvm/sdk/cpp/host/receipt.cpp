@@ -92,7 +92,7 @@ struct Prover::Impl : public IoHandler {
   void setSendRecvHandler(
       uint32_t channelId,
       const std::function<BufferU8(uint32_t /* channelId*/, const BufferU8&)>& handler) {
-    sendRecvHandlers.insert(std::make_pair(channelId, handler));
+    sendRecvHandlers[channelId] = handler;
   }
 
   BufferU8 onSendRecv(uint32_t channel, const BufferU8& buf) override {
@@ -110,6 +110,13 @@ struct Prover::Impl : public IoHandler {
 
   KeyStore& getKeyStore() override { return keyStore; }
 
+  Key onGetKey(const std::string& name, uint32_t mode) override {
+    if (getKeyHandler) {
+      return getKeyHandler(name, mode);
+    }
+    return IoHandler::onGetKey(name, mode);
+  }
+
   std::vector<uint8_t> elfContents;
   MethodId methodId;
   KeyStore keyStore;
@@ -125,6 +132,9 @@ struct Prover::Impl : public IoHandler {
   std::map<uint32_t /* channel id */,
            std::function<BufferU8(uint32_t /* channelId*/, const BufferU8&)> /* handler */>
       sendRecvHandlers;
+
+  // Replaces keyStore as the source of keys, if set.
+  std::function<Key(const std::string& /* name */, uint32_t /* mode */)> getKeyHandler;
 };
 
 CheckedStreamReader::CheckedStreamReader(const BufferU8& buffer) : buffer(buffer), cursor(0) {}
@@ -225,6 +235,11 @@ void Prover::setSendRecvHandler(
   impl->setSendRecvHandler(channelId, handler);
 }
 
+void Prover::setGetKeyHandler(
+    const std::function<Key(const std::string& /* name */, uint32_t /* mode */)>& handler) {
+  impl->getKeyHandler = handler;
+}
+
 Receipt Prover::run() {
   // Set the memory handlers to call back to the impl
   MemoryHandler handler(impl.get());
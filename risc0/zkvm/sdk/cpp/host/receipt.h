@@ -114,6 +114,10 @@ public:
       uint32_t channelId,
       const std::function<BufferU8(uint32_t /* channelId*/, const BufferU8&)>& handler);
 
+  // Supplies the keys the guest asks for, instead of the key store.
+  void setGetKeyHandler(
+      const std::function<Key(const std::string& /* name */, uint32_t /* mode */)>& handler);
+
 private:
   Prover() = default;
 
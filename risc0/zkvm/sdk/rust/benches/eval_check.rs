@@ -0,0 +1,72 @@
+// Copyright 2022 Risc0, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `eval_check` measures how quickly the CPU prover evaluates the
+//! constraint polynomial, reported in cycles of the execution trace per
+//! second.  The registers are filled with random values, so the constraints
+//! don't hold, but the work done is the same as for a real trace.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rand::thread_rng;
+use risc0_zkp::{
+    adapter::{CircuitInfo, TapsProvider},
+    core::{
+        fp::Fp,
+        fp4::{Fp4, EXT_SIZE},
+    },
+    field::{baby_bear::BabyBear, Elem},
+    hal::{cpu::CpuHal, EvalCheck, Hal},
+    taps::RegisterGroup,
+    INV_RATE,
+};
+use risc0_zkvm::{prove::CpuEvalCheck, CIRCUIT};
+use risc0_zkvm_circuit::CircuitImpl;
+
+fn random_fps(len: usize) -> Vec<Fp> {
+    let mut rng = thread_rng();
+    (0..len).map(|_| Fp::random(&mut rng)).collect()
+}
+
+pub fn eval_check(c: &mut Criterion) {
+    let taps = CIRCUIT.get_taps();
+    let hal: CpuHal<BabyBear> = CpuHal::new();
+    let eval = CpuEvalCheck::new(&CIRCUIT);
+
+    let mut group = c.benchmark_group("eval_check");
+    group.sample_size(10);
+    for po2 in [10, 12, 14, 16] {
+        let steps = 1 << po2;
+        let domain = steps * INV_RATE;
+        let code = hal.copy_fp_from(&random_fps(taps.group_size(RegisterGroup::Code) * domain));
+        let data = hal.copy_fp_from(&random_fps(taps.group_size(RegisterGroup::Data) * domain));
+        let accum = hal.copy_fp_from(&random_fps(taps.group_size(RegisterGroup::Accum) * domain));
+        let mix = hal.copy_fp_from(&random_fps(CircuitImpl::MIX_SIZE));
+        let out = hal.copy_fp_from(&random_fps(CircuitImpl::OUTPUT_SIZE));
+        let check = hal.alloc_fp(EXT_SIZE * domain);
+        let poly_mix = Fp4::random(&mut thread_rng());
+
+        group.throughput(Throughput::Elements(steps as u64));
+        group.bench_function(BenchmarkId::from_parameter(po2), |b| {
+            b.iter(|| {
+                EvalCheck::<CpuHal<BabyBear>>::eval_check(
+                    &eval, &check, &code, &data, &accum, &mix, &out, poly_mix, po2, steps, INV_RATE,
+                )
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, eval_check);
+criterion_main!(benches);
@@ -19,37 +19,114 @@
 //! are not indicitive of performance with cryptographically secure
 //! proofs.
 
-use std::time::{Duration, Instant};
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
 
 use criterion::{
-    black_box, criterion_group, criterion_main, Bencher, BenchmarkId, Criterion, SamplingMode,
-    Throughput,
+    black_box, criterion_group, criterion_main,
+    measurement::{Measurement, ValueFormatter},
+    Bencher, BenchmarkId, Criterion, SamplingMode, Throughput,
 };
 use rand::{
     distributions::{Distribution, Standard},
     Rng,
 };
-use risc0_zkvm::host::{Prover, ProverOpts};
-use risc0_zkvm::serde::to_vec;
+use risc0_zkvm::host::{Prover, ProverOpts, Receipt};
+use risc0_zkvm::serde::{from_slice, to_vec};
 use risc0_zkvm_methods::{
     bench::{BenchmarkSpec, SpecWithIters},
-    BENCH_ID, BENCH_PATH,
+    BENCH_FREE_LIST_ID, BENCH_FREE_LIST_PATH, BENCH_ID, BENCH_PATH,
 };
 
+// The number of times each allocator benchmark allocates and frees its
+// buffers in a single run of the guest.  The bump allocator never reuses
+// memory, so this is bounded by the size of the heap.
+const ALLOC_ROUNDS: u64 = 16;
+
+// The guest cycles reported by every benchmark run so far.
+static GUEST_CYCLES: AtomicU64 = AtomicU64::new(0);
+
+// Measures the guest cycles which a benchmark reports in its journal, rather
+// than the wall-clock time taken to run it on the host.  Each run adds its
+// cycles to GUEST_CYCLES, so a measurement is how much that grew.
+struct GuestCycles;
+
+impl Measurement for GuestCycles {
+    type Intermediate = u64;
+    type Value = u64;
+
+    fn start(&self) -> u64 {
+        GUEST_CYCLES.load(Ordering::Relaxed)
+    }
+
+    fn end(&self, start: u64) -> u64 {
+        GUEST_CYCLES.load(Ordering::Relaxed) - start
+    }
+
+    fn add(&self, v1: &u64, v2: &u64) -> u64 {
+        v1 + v2
+    }
+
+    fn zero(&self) -> u64 {
+        0
+    }
+
+    fn to_f64(&self, value: &u64) -> f64 {
+        *value as f64
+    }
+
+    fn formatter(&self) -> &dyn ValueFormatter {
+        &CyclesFormatter
+    }
+}
+
+struct CyclesFormatter;
+
+impl ValueFormatter for CyclesFormatter {
+    fn scale_values(&self, _typical_value: f64, _values: &mut [f64]) -> &'static str {
+        "cycles"
+    }
+
+    fn scale_throughputs(
+        &self,
+        _typical_value: f64,
+        throughput: &Throughput,
+        values: &mut [f64],
+    ) -> &'static str {
+        let (count, unit) = match throughput {
+            Throughput::Bytes(bytes) => (*bytes, "cycles/byte"),
+            Throughput::Elements(elems) => (*elems, "cycles/elem"),
+        };
+        for value in values {
+            *value /= count as f64;
+        }
+        unit
+    }
+
+    fn scale_for_machines(&self, _values: &mut [f64]) -> &'static str {
+        "cycles"
+    }
+}
+
 fn run_guest(spec: SpecWithIters) -> Duration {
+    let start = Instant::now();
+    black_box(run_method(BENCH_PATH, BENCH_ID, spec));
+    start.elapsed()
+}
+
+fn run_method(path: &str, id: &[u8], spec: SpecWithIters) -> Receipt {
     let input_data: Vec<u32> = to_vec(&spec).unwrap();
 
     let mut prover = Prover::new_with_opts(
-        &std::fs::read(BENCH_PATH).unwrap(),
-        BENCH_ID,
+        &std::fs::read(path).unwrap(),
+        id,
         ProverOpts::default().with_skip_seal(true),
     )
     .unwrap();
     prover.add_input_u32_slice(input_data.as_slice());
-
-    let start = Instant::now();
-    black_box(prover.run().unwrap());
-    start.elapsed()
+    prover.run().unwrap()
 }
 
 fn guest_iter(b: &mut Bencher, spec: BenchmarkSpec) {
@@ -178,7 +255,36 @@ pub fn bench(c: &mut Criterion) {
     memcpy_group.finish();
 }
 
+fn bench_alloc(c: &mut Criterion<GuestCycles>) {
+    let mut alloc_group = c.benchmark_group("alloc");
+    alloc_group.sampling_mode(SamplingMode::Flat);
+    for buf_bytes in [16usize, 256, 4096, 16384] {
+        let spec = BenchmarkSpec::Alloc {
+            size: buf_bytes,
+            count: 16,
+        };
+        for (name, path, id) in [
+            ("bump", BENCH_PATH, BENCH_ID),
+            ("free-list", BENCH_FREE_LIST_PATH, BENCH_FREE_LIST_ID),
+        ] {
+            alloc_group.bench_with_input(BenchmarkId::new(name, buf_bytes), &spec, |b, spec| {
+                // Each run starts with a fresh heap, so run the guest once
+                // per iteration rather than asking it to loop.
+                b.iter(|| {
+                    let receipt = run_method(path, id, SpecWithIters(spec.clone(), ALLOC_ROUNDS));
+                    let cycles: u64 = from_slice(&receipt.get_journal_vec().unwrap()).unwrap();
+                    GUEST_CYCLES.fetch_add(cycles, Ordering::Relaxed);
+                })
+            });
+        }
+    }
+    alloc_group.finish();
+}
+
 criterion_group!(name = benches;
                  config = Criterion::default();
                  targets = bench);
-criterion_main!(benches);
+criterion_group!(name = alloc_benches;
+                 config = Criterion::default().with_measurement(GuestCycles);
+                 targets = bench_alloc);
+criterion_main!(benches, alloc_benches);
@@ -15,11 +15,15 @@
 use core::{
     alloc::{GlobalAlloc, Layout},
     cell::UnsafeCell,
+    ptr,
 };
 
-use risc0_zkvm_platform::{memory, WORD_SIZE};
+use risc0_zkvm_platform::{
+    io::{OutOfMemoryDescriptor, GPIO_OUT_OF_MEMORY},
+    memory, WORD_SIZE,
+};
 
-use crate::{_fault, align_up};
+use crate::{_fail, align_up, memory_barrier};
 
 // Bump pointer allocator for *single* core systems
 struct BumpPointerAlloc {
@@ -30,20 +34,36 @@ struct BumpPointerAlloc {
 // SAFETY: single threaded environment
 unsafe impl Sync for BumpPointerAlloc {}
 
-#[cfg(target_arch = "riscv32")]
-unsafe impl GlobalAlloc for BumpPointerAlloc {
-    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+impl BumpPointerAlloc {
+    // Returns null if the heap is exhausted.
+    unsafe fn alloc_aligned(&self, size: usize, align: usize) -> *mut u8 {
         let head = self.head.get();
 
         // move start up to the next alignment boundary
-        let alloc_start = align_up(*head, WORD_SIZE);
-        let alloc_end = alloc_start.checked_add(layout.size()).unwrap();
-        if alloc_end > self.end {
-            _fault();
-        } else {
-            *head = alloc_end;
-            alloc_start as *mut u8
+        let alloc_start = align_up(*head, align.max(WORD_SIZE));
+        match alloc_start.checked_add(size) {
+            Some(alloc_end) if alloc_end <= self.end => {
+                *head = alloc_end;
+                alloc_start as *mut u8
+            }
+            _ => ptr::null_mut(),
+        }
+    }
+
+    fn used(&self) -> usize {
+        // SAFETY: single threaded environment
+        unsafe { *self.head.get() - memory::HEAP.start() }
+    }
+}
+
+#[cfg(target_arch = "riscv32")]
+unsafe impl GlobalAlloc for BumpPointerAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.alloc_aligned(layout.size(), layout.align());
+        if ptr.is_null() {
+            out_of_memory(layout);
         }
+        ptr
     }
 
     unsafe fn dealloc(&self, _: *mut u8, _: Layout) {
@@ -51,9 +71,131 @@ unsafe impl GlobalAlloc for BumpPointerAlloc {
     }
 }
 
+// Every block is a power of two bytes, and large enough to hold the link
+// to the next free block.
+const MIN_CLASS: u32 = WORD_SIZE.trailing_zeros();
+const CLASSES: usize = usize::BITS as usize;
+
+// Size class allocator for *single* core systems.  Freed blocks are kept on
+// a free list per size class, and new blocks are carved out of the heap by
+// a bump allocator.
+struct FreeListAlloc {
+    bump: BumpPointerAlloc,
+    // The first free block of each size class, or 0.
+    free: UnsafeCell<[usize; CLASSES]>,
+}
+
+// SAFETY: single threaded environment
+unsafe impl Sync for FreeListAlloc {}
+
+impl FreeListAlloc {
+    // Returns None if the layout is larger than any block can be.
+    fn class(layout: Layout) -> Option<usize> {
+        let size = layout.size().max(layout.align()).checked_next_power_of_two()?;
+        Some(size.trailing_zeros().max(MIN_CLASS) as usize)
+    }
+}
+
 #[cfg(target_arch = "riscv32")]
+unsafe impl GlobalAlloc for FreeListAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let class = match Self::class(layout) {
+            Some(class) => class,
+            None => out_of_memory(layout),
+        };
+        let free = &mut (*self.free.get())[class];
+        // Blocks are aligned only as far as the layout they were carved out
+        // for, not to their size, so that large blocks don't waste heap on
+        // padding.  A free block which isn't aligned enough for this layout
+        // is left for another.
+        if *free != 0 && *free % layout.align() == 0 {
+            let block = *free;
+            *free = *(block as *const usize);
+            block as *mut u8
+        } else {
+            let ptr = self.bump.alloc_aligned(1 << class, layout.align());
+            if ptr.is_null() {
+                out_of_memory(layout);
+            }
+            ptr
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // Any layout which was allocated has a class.
+        let free = &mut (*self.free.get())[Self::class(layout).unwrap()];
+        *(ptr as *mut usize) = *free;
+        *free = ptr as usize;
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+        if Self::class(new_layout) == Self::class(layout) {
+            return ptr;
+        }
+        let new_ptr = self.alloc(new_layout);
+        core::ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_size));
+        self.dealloc(ptr, layout);
+        new_ptr
+    }
+}
+
+#[cfg(all(target_arch = "riscv32", not(feature = "free-list-alloc")))]
 #[global_allocator]
 static HEAP: BumpPointerAlloc = BumpPointerAlloc {
     head: UnsafeCell::new(memory::HEAP.start()),
     end: memory::HEAP.end(),
 };
+
+#[cfg(all(target_arch = "riscv32", feature = "free-list-alloc"))]
+#[global_allocator]
+static HEAP: FreeListAlloc = FreeListAlloc {
+    bump: BumpPointerAlloc {
+        head: UnsafeCell::new(memory::HEAP.start()),
+        end: memory::HEAP.end(),
+    },
+    free: UnsafeCell::new([0; CLASSES]),
+};
+
+/// Allocates memory that the guest has never touched, which the host is
+/// therefore allowed to fill in.  It must never be freed, since a freeing
+/// allocator would then hand it out again.
+pub(crate) unsafe fn alloc_untouched(layout: Layout) -> *mut u8 {
+    #[cfg(all(target_arch = "riscv32", not(feature = "free-list-alloc")))]
+    let ptr = HEAP.alloc_aligned(layout.size(), layout.align());
+    #[cfg(all(target_arch = "riscv32", feature = "free-list-alloc"))]
+    let ptr = HEAP.bump.alloc_aligned(layout.size(), layout.align());
+    #[cfg(not(target_arch = "riscv32"))]
+    let ptr = alloc_crate::alloc::alloc(layout);
+    if ptr.is_null() {
+        out_of_memory(layout);
+    }
+    ptr
+}
+
+// Returns the number of bytes of the heap handed out so far, including
+// any which have since been freed.
+fn heap_used() -> usize {
+    #[cfg(all(target_arch = "riscv32", not(feature = "free-list-alloc")))]
+    return HEAP.used();
+    #[cfg(all(target_arch = "riscv32", feature = "free-list-alloc"))]
+    return HEAP.bump.used();
+    #[cfg(not(target_arch = "riscv32"))]
+    return 0;
+}
+
+/// Reports through `GPIO_OUT_OF_MEMORY` that the heap can't satisfy
+/// `layout`, and aborts the guest.
+pub(crate) fn out_of_memory(layout: Layout) -> ! {
+    let desc = OutOfMemoryDescriptor {
+        requested: layout.size() as u32,
+        align: layout.align() as u32,
+        used: heap_used() as u32,
+        limit: memory::HEAP.len_bytes() as u32,
+    };
+    memory_barrier(&desc);
+    // A compliant host faults when it receives this descriptor.
+    unsafe { GPIO_OUT_OF_MEMORY.as_ptr().write_volatile(&desc) }
+    // As a fallback for hosts which don't know the GPIO, fault plainly.
+    unsafe { _fail("Out of memory\0") }
+}
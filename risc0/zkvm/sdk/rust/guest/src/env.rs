@@ -12,25 +12,26 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use core::{cell::UnsafeCell, mem::MaybeUninit, slice};
+use core::{alloc::Layout, cell::UnsafeCell, fmt, mem::MaybeUninit, slice};
 
 use risc0_zkp::core::sha::Digest;
-use risc0_zkvm::serde::{Deserializer, Serializer, Slice};
+use risc0_zkvm::serde::{from_slice, to_vec, Deserializer, Serializer, Slice};
 // Re-export for easy use by user programs.
 #[cfg(target_os = "zkvm")]
 pub use risc0_zkvm_platform::rt::host_io::host_sendrecv;
 use risc0_zkvm_platform::{
     io::{
-        IoDescriptor, GPIO_COMMIT, GPIO_CYCLECOUNT, GPIO_LOG, SENDRECV_CHANNEL_INITIAL_INPUT,
-        SENDRECV_CHANNEL_STDOUT,
+        GetKeyDescriptor, IoDescriptor, GETKEY_MODE_ANY, GPIO_COMMIT, GPIO_CYCLECOUNT, GPIO_GETKEY,
+        GPIO_LOG, KEY_WORDS, SENDRECV_CHANNEL_INITIAL_INPUT, SENDRECV_CHANNEL_SEGMENT_RESUME,
+        SENDRECV_CHANNEL_SEGMENT_SUSPEND, SENDRECV_CHANNEL_STDERR, SENDRECV_CHANNEL_STDOUT,
     },
     memory,
-    rt::host_io::host_recv,
+    rt::host_io::{host_recv, set_input_refill},
     WORD_SIZE,
 };
 use serde::{Deserialize, Serialize};
 
-use crate::{align_up, memory_barrier, sha};
+use crate::{align_up, alloc::alloc_untouched, memory_barrier, sha};
 
 #[cfg(not(target_os = "zkvm"))]
 // Bazel really wants to compile this file for the host too, so provide a stub.
@@ -40,11 +41,22 @@ pub fn host_sendrecv(_channel: u32, _buf: &[u8]) -> (&'static [u32], usize) {
     unimplemented!()
 }
 
+/// How many bytes of formatted text [print!](crate::print) and friends
+/// collect on the stack before sending them to the host.
+const PRINT_BUFFER_SIZE: usize = 256;
+
+/// A secret key supplied by the host.
+pub type Key = [u32; KEY_WORDS];
+
 struct Env {
     output: Serializer<Slice<'static>>,
     commit: Serializer<Slice<'static>>,
     commit_len: usize,
+    initial_input_words: Option<&'static [u32]>,
     initial_input_reader: Option<Reader>,
+    // Digests of the initial input and of the state this segment resumed
+    // from, once [resume] is called.
+    segment_start: Option<(Digest, Digest)>,
 }
 
 struct Once<T> {
@@ -87,8 +99,28 @@ impl<T> Once<T> {
 
 static ENV: Once<Env> = Once::new();
 
+// How much memory to set aside for host data each time the current input
+// window runs low.  Responses which are larger get a window of their own size.
+const INPUT_WINDOW_SIZE: usize = 256 * 1024;
+
 pub(crate) fn init() {
     ENV.init(Env::new());
+    set_input_refill(alloc_input_window);
+}
+
+// Gives the host a fresh window of at least `min_len` bytes to send data to.
+// Windows can't be reclaimed: the host can only write to memory we haven't
+// touched yet, and the data already received is handed out as `'static`
+// slices.  So everything the host sends past the INPUT region stays on the
+// heap for the rest of the run, and once the heap can't fit another window
+// the guest faults as out of memory.  Since a new window is only made once
+// less than INPUT_HEADROOM bytes of the last one are left, or for a response
+// which doesn't fit in them, at most that much of each window goes unused.
+fn alloc_input_window(min_len: usize) -> (usize, usize) {
+    let len = align_up(min_len, WORD_SIZE).max(INPUT_WINDOW_SIZE);
+    let layout = Layout::from_size_align(len, WORD_SIZE).unwrap();
+    let start = unsafe { alloc_untouched(layout) };
+    (start as usize, len)
 }
 
 pub(crate) fn finalize(result: *mut usize) {
@@ -97,12 +129,17 @@ pub(crate) fn finalize(result: *mut usize) {
 
 /// Exchanges data with the host, returning the data from the host
 /// as a slice of bytes.
+///
+/// The data stays in memory for the rest of the run.  Once the INPUT region
+/// is full, it takes up heap, and exhausting the heap faults the guest as
+/// out of memory.
 pub fn send_recv(channel: u32, buf: &[u8]) -> &'static [u8] {
     ENV.get().send_recv(channel, buf)
 }
 
 /// Exchanges data with the host, returning the data from the host as
-/// a slice of words and the length in bytes.
+/// a slice of words and the length in bytes.  Like [send_recv], the data
+/// takes up heap once the INPUT region is full.
 pub fn send_recv_as_u32(channel: u32, buf: &[u8]) -> (&'static [u32], usize) {
     ENV.get().send_recv_as_u32(channel, buf)
 }
@@ -122,12 +159,43 @@ pub fn commit<T: Serialize>(data: &T) {
     ENV.get().commit(data);
 }
 
+/// Returns the state saved by the previous segment with [suspend], or `None`
+/// in the first segment.
+///
+/// A computation too long to prove at once can be split into segments, each
+/// run and proven on its own by `risc0_zkvm::host::prove_segments`.  Each
+/// segment calls [resume] once to pick up where the last one left off, and
+/// is given the same initial input to [read].
+///
+/// Splitting is up to the guest: the executor can't stop a guest and resume
+/// it later, so guests which don't call [resume] and [suspend] always run as
+/// a single segment.
+pub fn resume<T: Deserialize<'static>>() -> Option<T> {
+    ENV.get().resume()
+}
+
+/// Ends this segment, saving `state` for the next segment to [resume] from,
+/// or ends the computation if `state` is `None`.
+///
+/// This commits the digests of the initial input, the state this segment
+/// resumed from and the state it saved, which the host uses to check that
+/// the segments chain, so nothing may be committed afterwards.
+pub fn suspend<T: Serialize>(state: Option<&T>) {
+    ENV.get().suspend(state);
+}
+
 /// Returns the number of processor cycles that have occured since the guest
 /// began.
 pub fn get_cycle_count() -> usize {
     ENV.get().get_cycle_count()
 }
 
+/// Request the secret key called `name` from the host.  The same name
+/// yields the same key for the rest of the execution.
+pub fn get_key(name: &str) -> Key {
+    ENV.get().get_key(name, GETKEY_MODE_ANY)
+}
+
 /// Print a message to the debug console.
 pub fn log(msg: &str) {
     // TODO: format! is expensive, replace with a better solution.
@@ -137,6 +205,67 @@ pub fn log(msg: &str) {
     unsafe { GPIO_LOG.as_ptr().write_volatile(ptr) };
 }
 
+// Collects formatted text in a stack buffer, sending it to the host on a
+// channel whenever the buffer fills up and when flushed.
+struct PrintBuffer {
+    channel: u32,
+    buf: [u8; PRINT_BUFFER_SIZE],
+    len: usize,
+}
+
+impl PrintBuffer {
+    fn new(channel: u32) -> Self {
+        PrintBuffer {
+            channel,
+            buf: [0; PRINT_BUFFER_SIZE],
+            len: 0,
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.len > 0 {
+            send_recv(self.channel, &self.buf[..self.len]);
+            self.len = 0;
+        }
+    }
+}
+
+impl fmt::Write for PrintBuffer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut bytes = s.as_bytes();
+        while !bytes.is_empty() {
+            if self.len == PRINT_BUFFER_SIZE {
+                self.flush();
+            }
+            let n = bytes.len().min(PRINT_BUFFER_SIZE - self.len);
+            self.buf[self.len..self.len + n].copy_from_slice(&bytes[..n]);
+            self.len += n;
+            bytes = &bytes[n..];
+        }
+        Ok(())
+    }
+}
+
+fn print_to(channel: u32, args: fmt::Arguments) {
+    let mut buf = PrintBuffer::new(channel);
+    fmt::Write::write_fmt(&mut buf, args).unwrap();
+    buf.flush();
+}
+
+/// Sends formatted text to the host's standard output.  Use
+/// [print!](crate::print) or [println!](crate::println) instead.
+#[doc(hidden)]
+pub fn _print(args: fmt::Arguments) {
+    print_to(SENDRECV_CHANNEL_STDOUT, args);
+}
+
+/// Sends formatted text to the host's standard error.  Use
+/// [eprint!](crate::eprint) or [eprintln!](crate::eprintln) instead.
+#[doc(hidden)]
+pub fn _eprint(args: fmt::Arguments) {
+    print_to(SENDRECV_CHANNEL_STDERR, args);
+}
+
 impl Env {
     fn new() -> Self {
         Env {
@@ -148,7 +277,9 @@ impl Env {
             })),
 
             commit_len: 0,
+            initial_input_words: None,
             initial_input_reader: None,
+            segment_start: None,
         }
     }
 
@@ -161,9 +292,17 @@ impl Env {
         &bytemuck::cast_slice(data)[..bytes]
     }
 
+    fn initial_input_words(&mut self) -> &'static [u32] {
+        if self.initial_input_words.is_none() {
+            let (words, _) = self.send_recv_as_u32(SENDRECV_CHANNEL_INITIAL_INPUT, &[]);
+            self.initial_input_words = Some(words);
+        }
+        self.initial_input_words.unwrap()
+    }
+
     fn initial_input(&mut self) -> &mut Reader {
         if !self.initial_input_reader.is_some() {
-            let (words, _) = self.send_recv_as_u32(SENDRECV_CHANNEL_INITIAL_INPUT, &[]);
+            let words = self.initial_input_words();
             self.initial_input_reader = Some(Reader(Deserializer::new(words)))
         }
         self.initial_input_reader.as_mut().unwrap()
@@ -187,6 +326,47 @@ impl Env {
         self.send_recv(SENDRECV_CHANNEL_STDOUT, bytemuck::cast_slice(buf));
     }
 
+    fn resume<T: Deserialize<'static>>(&mut self) -> Option<T> {
+        assert!(self.segment_start.is_none(), "resume called twice");
+        // Every segment is given the initial input again, so commit to it to
+        // keep the host from changing it between segments.
+        let input = *sha::digest_u8_slice(bytemuck::cast_slice(self.initial_input_words()));
+        // The host replies with nothing in the first segment, and otherwise
+        // with the length of the saved state in words followed by the state.
+        let (words, _) = self.send_recv_as_u32(SENDRECV_CHANNEL_SEGMENT_RESUME, &[]);
+        match words.split_first() {
+            Some((&len, state)) => {
+                assert_eq!(len as usize, state.len(), "truncated segment state");
+                let start = *sha::digest_u8_slice(bytemuck::cast_slice(state));
+                self.segment_start = Some((input, start));
+                Some(from_slice(state).unwrap())
+            }
+            None => {
+                self.segment_start = Some((input, Digest::default()));
+                None
+            }
+        }
+    }
+
+    fn suspend<T: Serialize>(&mut self, state: Option<&T>) {
+        let (input, start) = self
+            .segment_start
+            .take()
+            .expect("suspend called without resume");
+        let end = match state {
+            Some(state) => {
+                let state = to_vec(state).unwrap();
+                let state = bytemuck::cast_slice(&state);
+                self.send_recv(SENDRECV_CHANNEL_SEGMENT_SUSPEND, state);
+                *sha::digest_u8_slice(state)
+            }
+            None => Digest::default(),
+        };
+        self.commit(&input);
+        self.commit(&start);
+        self.commit(&end);
+    }
+
     fn finalize(&mut self, result: *mut usize) {
         let len_words = self.commit_len;
         let len_bytes = len_words * WORD_SIZE;
@@ -236,6 +416,25 @@ impl Env {
         sha::finalize();
     }
 
+    fn get_key(&self, name: &str, mode: u32) -> Key {
+        let name = alloc_crate::format!("{}\0", name);
+        // The host can only write to memory we haven't touched yet, so
+        // have it fill in a fresh allocation.
+        let key = unsafe { alloc_untouched(Layout::new::<Key>()) } as *mut Key;
+        unsafe {
+            let desc = GetKeyDescriptor {
+                name: name.as_ptr() as u32,
+                addr: key as u32,
+                mode,
+            };
+            memory_barrier(name.as_ptr());
+            let ptr: *const GetKeyDescriptor = &desc;
+            memory_barrier(ptr);
+            GPIO_GETKEY.as_ptr().write_volatile(&desc);
+            key.read_volatile()
+        }
+    }
+
     fn get_cycle_count(&self) -> usize {
         unsafe { GPIO_CYCLECOUNT.as_ptr().write_volatile(0) }
         match host_recv(1) {
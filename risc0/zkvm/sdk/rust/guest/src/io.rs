@@ -0,0 +1,177 @@
+// Copyright 2022 Risc0, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// A ChannelReader asks the host for data by sending the number of bytes it
+// wants as a little-endian u32, and treats an empty response as the end of
+// the stream.  A ChannelWriter sends everything written to it as requests,
+// and ignores the responses.  The host serves these with ReadHandler and
+// WriteHandler from risc0_zkvm::host.
+
+use core::fmt;
+#[cfg(feature = "std")]
+pub use std::io::{Error, Read, Result, Write};
+
+#[cfg(not(feature = "std"))]
+pub use self::no_std::{Error, Read, Result, Write};
+use crate::env;
+
+/// How many bytes a [ChannelReader] asks for at once, unless a larger read
+/// needs more.
+const READ_CHUNK: usize = 1024;
+
+// Stand-ins for the parts of `std::io` which the channels need, for guests
+// built without the standard library.  With the `std` feature, the channels
+// implement `std::io::Read` and `std::io::Write` instead, so that existing
+// parsers can consume them.
+#[cfg(not(feature = "std"))]
+mod no_std {
+    use core::fmt;
+
+    use alloc_crate::vec::Vec;
+
+    use super::READ_CHUNK;
+
+    /// Errors from reading or writing a channel.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Error {
+        /// The stream ended before the requested data was read.
+        UnexpectedEof,
+    }
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Error::UnexpectedEof => f.write_str("unexpected end of stream"),
+            }
+        }
+    }
+
+    /// A Result specialized for [Error].
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    /// A source of bytes, like `std::io::Read`.
+    pub trait Read {
+        /// Read some bytes into `buf`, returning how many were read.  Returns
+        /// 0 at the end of the stream.
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize>;
+
+        /// Fill all of `buf`, failing if the stream ends first.
+        fn read_exact(&mut self, mut buf: &mut [u8]) -> Result<()> {
+            while !buf.is_empty() {
+                match self.read(buf)? {
+                    0 => return Err(Error::UnexpectedEof),
+                    n => buf = &mut buf[n..],
+                }
+            }
+            Ok(())
+        }
+
+        /// Append the rest of the stream to `buf`, returning how many bytes
+        /// were read.
+        fn read_to_end(&mut self, buf: &mut Vec<u8>) -> Result<usize> {
+            let start = buf.len();
+            let mut chunk = [0; READ_CHUNK];
+            loop {
+                match self.read(&mut chunk)? {
+                    0 => return Ok(buf.len() - start),
+                    n => buf.extend_from_slice(&chunk[..n]),
+                }
+            }
+        }
+    }
+
+    /// A sink for bytes, like `std::io::Write`.
+    pub trait Write {
+        /// Write some of `buf`, returning how many bytes were written.
+        fn write(&mut self, buf: &[u8]) -> Result<usize>;
+
+        /// Send any buffered data on to its destination.
+        fn flush(&mut self) -> Result<()>;
+
+        /// Write all of `buf`.
+        fn write_all(&mut self, mut buf: &[u8]) -> Result<()> {
+            while !buf.is_empty() {
+                let n = self.write(buf)?;
+                buf = &buf[n..];
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Reads a stream of bytes from the host over a sendrecv channel.
+pub struct ChannelReader {
+    channel: u32,
+    // Data received from the host which hasn't been read yet.
+    pending: &'static [u8],
+    eof: bool,
+}
+
+impl ChannelReader {
+    /// Read from the given channel.
+    pub fn new(channel: u32) -> Self {
+        ChannelReader {
+            channel,
+            pending: &[],
+            eof: false,
+        }
+    }
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if self.pending.is_empty() && !self.eof && !buf.is_empty() {
+            let want = buf.len().max(READ_CHUNK) as u32;
+            self.pending = env::send_recv(self.channel, &want.to_le_bytes());
+            self.eof = self.pending.is_empty();
+        }
+        let n = buf.len().min(self.pending.len());
+        buf[..n].copy_from_slice(&self.pending[..n]);
+        self.pending = &self.pending[n..];
+        Ok(n)
+    }
+}
+
+/// Writes a stream of bytes to the host over a sendrecv channel.
+///
+/// Writes are not buffered: each one is sent to the host straight away.
+pub struct ChannelWriter {
+    channel: u32,
+}
+
+impl ChannelWriter {
+    /// Write to the given channel.
+    pub fn new(channel: u32) -> Self {
+        ChannelWriter { channel }
+    }
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if !buf.is_empty() {
+            env::send_recv(self.channel, buf);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl fmt::Write for ChannelWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_all(s.as_bytes()).map_err(|_| fmt::Error)
+    }
+}
@@ -18,6 +18,8 @@
 #![cfg_attr(not(test), no_std)]
 
 pub extern crate alloc as alloc_crate;
+#[cfg(all(feature = "std", not(test)))]
+extern crate std;
 // Allow our generated macros to reference alloc_crate.
 pub use alloc_crate as _alloc_crate;
 
@@ -26,6 +28,10 @@ mod alloc;
 /// Functions for interacting with the host environment.
 pub mod env;
 
+/// Byte streams over sendrecv channels, which implement `std::io::Read` and
+/// `std::io::Write` with the `std` feature.
+pub mod io;
+
 /// Functions for computing SHA-256 hashes.
 pub mod sha;
 
@@ -59,6 +65,13 @@ pub unsafe fn _fail(msg: &str) -> ! {
     unreachable!();
 }
 
+/// Aborts the guest, reporting that an allocation of the given layout
+/// failed along with the heap usage.
+#[doc(hidden)]
+pub fn _out_of_memory(layout: core::alloc::Layout) -> ! {
+    alloc::out_of_memory(layout)
+}
+
 /// This defines standalone (no_std) panic and alloc error handlers in
 /// a guest method, for guest methods that do not want to use the
 /// standard library.
@@ -79,12 +92,56 @@ macro_rules! standalone_handlers {
         }
 
         #[alloc_error_handler]
-        unsafe fn alloc_fault(_layout: ::core::alloc::Layout) -> ! {
-            ::risc0_zkvm_guest::_fail("Memory allocation failure")
+        unsafe fn alloc_fault(layout: ::core::alloc::Layout) -> ! {
+            ::risc0_zkvm_guest::_out_of_memory(layout)
         }
     };
 }
 
+/// Prints to the host's standard output, like `std::print!`.  The text is
+/// formatted into a buffer on the stack rather than the heap.
+///
+/// Standard output is shared with [env::write], so on the host the text ends
+/// up in the prover's output unless a different sink is installed.
+#[macro_export]
+macro_rules! print {
+    ($($arg:tt)*) => {
+        $crate::env::_print(::core::format_args!($($arg)*))
+    };
+}
+
+/// Prints to the host's standard output with a trailing newline, like
+/// `std::println!`.  See [print!].
+#[macro_export]
+macro_rules! println {
+    () => {
+        $crate::print!("\n")
+    };
+    ($($arg:tt)*) => {
+        $crate::env::_print(::core::format_args!("{}\n", ::core::format_args!($($arg)*)))
+    };
+}
+
+/// Prints to the host's standard error, like `std::eprint!`.  See [print!].
+#[macro_export]
+macro_rules! eprint {
+    ($($arg:tt)*) => {
+        $crate::env::_eprint(::core::format_args!($($arg)*))
+    };
+}
+
+/// Prints to the host's standard error with a trailing newline, like
+/// `std::eprintln!`.  See [print!].
+#[macro_export]
+macro_rules! eprintln {
+    () => {
+        $crate::eprint!("\n")
+    };
+    ($($arg:tt)*) => {
+        $crate::env::_eprint(::core::format_args!("{}\n", ::core::format_args!($($arg)*)))
+    };
+}
+
 /// Used for defining a main entrypoint.
 ///
 /// # Example
@@ -12,14 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use core::{cell::UnsafeCell, mem};
+use core::{alloc::Layout, cell::UnsafeCell, mem};
 
-use alloc_crate::{boxed::Box, vec::Vec};
-use risc0_zkp::core::{
-    fp::Fp,
-    fp4::Fp4,
-    sha::{Digest, DIGEST_WORDS},
-};
+use alloc_crate::vec::Vec;
+use risc0_zkp::core::{fp::Fp, fp4::Fp4, sha::Digest};
 use risc0_zkvm::serde::to_vec_with_capacity;
 use risc0_zkvm_platform::{
     io::{SHADescriptor, GPIO_SHA},
@@ -29,7 +25,7 @@ use risc0_zkvm_platform::{
 };
 use serde::Serialize;
 
-use crate::align_up;
+use crate::{align_up, alloc::alloc_untouched};
 
 // Current sha descriptor index.
 struct CurDesc(UnsafeCell<usize>);
@@ -63,8 +59,7 @@ pub fn raw_digest(data: &[u32]) -> &'static Digest {
     // Allocate fresh memory that's guaranteed to be uninitialized so
     // the host can write to it.
     unsafe {
-        let alloced = Box::<mem::MaybeUninit<Digest>>::new(mem::MaybeUninit::<Digest>::uninit());
-        let digest = (*Box::into_raw(alloced)).as_mut_ptr();
+        let digest = alloc_untouched(Layout::new::<Digest>()) as *mut Digest;
         raw_digest_to(data, digest);
         &*digest
     }
@@ -151,10 +146,7 @@ pub fn digest_u8_slice(data: &[u8]) -> &'static Digest {
     with_sha_trailer(data, |slice| raw_digest(slice))
 }
 
-pub(crate) fn with_sha_trailer(
-    data: &[u8],
-    f: impl Fn(&[u32]) -> &'static Digest,
-) -> &'static Digest {
+pub(crate) fn with_sha_trailer<T>(data: &[u8], f: impl Fn(&[u32]) -> T) -> T {
     let len_bytes = data.len();
     let cap = compute_capacity_needed(len_bytes);
     let mut data_u32 = Vec::<u32>::with_capacity(cap);
@@ -198,63 +190,47 @@ pub(crate) fn finalize() {
 
 /// A guest-side [Sha] implementation.
 ///
+/// The accelerator writes each digest to fresh memory, from which it is
+/// copied out; use [raw_digest] and friends directly to keep a reference to
+/// the accelerator's copy instead.
+///
 /// [Sha]: risc0_zkp::core::sha::Sha
 #[derive(Debug, Clone)]
 pub struct Impl {}
 
 impl risc0_zkp::core::sha::Sha for Impl {
-    type DigestPtr = &'static Digest;
-
-    fn hash_bytes(&self, bytes: &[u8]) -> Self::DigestPtr {
-        digest_u8_slice(bytes)
+    fn hash_bytes(&self, bytes: &[u8]) -> Digest {
+        *digest_u8_slice(bytes)
     }
 
-    fn hash_pair(&self, a: &Digest, b: &Digest) -> Self::DigestPtr {
-        raw_digest(bytemuck::cast_slice(&[*a, *b]))
+    fn hash_pair(&self, a: &Digest, b: &Digest) -> Digest {
+        *raw_digest(bytemuck::cast_slice(&[*a, *b]))
     }
 
-    fn hash_raw_words(&self, words: &[u32]) -> Self::DigestPtr {
-        raw_digest(words)
+    fn hash_raw_words(&self, words: &[u32]) -> Digest {
+        *raw_digest(words)
     }
 
-    fn hash_raw_pod_slice<T: bytemuck::Pod>(&self, pod: &[T]) -> Self::DigestPtr {
+    fn hash_raw_pod_slice<T: bytemuck::Pod>(&self, pod: &[T]) -> Digest {
         let u8s: &[u8] = bytemuck::cast_slice(pod);
 
         if u8s.len() % (CHUNK_SIZE * WORD_SIZE) == 0 {
             // Already padded; no need to copy it.
-            raw_digest(bytemuck::cast_slice(pod))
+            *raw_digest(bytemuck::cast_slice(pod))
         } else {
             let size = align_up(u8s.len(), CHUNK_SIZE * WORD_SIZE);
             let mut buf: Vec<u8> = Vec::with_capacity(size);
             buf.extend(bytemuck::cast_slice(pod));
             buf.resize(size, 0);
-            raw_digest(bytemuck::cast_slice(buf.as_slice()))
-        }
-    }
-
-    // Generate a new digest by mixing two digests together via XOR,
-    // and storing into the first digest.
-    fn mix(&self, pool: &mut Self::DigestPtr, val: &Digest) {
-        let mut digest = Box::<Digest>::new(Digest::default());
-        for i in 0..DIGEST_WORDS {
-            digest.get_mut()[i] = pool.get()[i] ^ val.get()[i];
-        }
-        unsafe {
-            let ptr: *const Digest = &*digest;
-            *pool = &*ptr
+            *raw_digest(bytemuck::cast_slice(buf.as_slice()))
         }
     }
 
-    fn compress(
-        &self,
-        _state: &Digest,
-        block_half1: &Digest,
-        block_half2: &Digest,
-    ) -> Self::DigestPtr {
+    fn compress(&self, _state: &Digest, block_half1: &Digest, block_half2: &Digest) -> Digest {
         unimplemented!()
     }
 
-    fn update(&self, _state: &Digest, _bytes: &[u8]) -> Self::DigestPtr {
+    fn update(&self, _state: &Digest, _bytes: &[u8]) -> Digest {
         unimplemented!()
     }
 }
@@ -33,15 +33,13 @@ use crate::sha::{add_trailer, compute_capacity_needed, with_sha_trailer, MemoryT
 pub struct Impl {}
 
 impl risc0_zkp::core::sha::Sha for Impl {
-    type DigestPtr = &'static Digest;
-
-    fn hash_bytes(&self, bytes: &[u8]) -> Self::DigestPtr {
+    fn hash_bytes(&self, bytes: &[u8]) -> Digest {
         with_sha_trailer(bytes, |words| {
             self.update(&SHA256_INIT, bytemuck::cast_slice(words))
         })
     }
 
-    fn update(&self, state: &Digest, bytes: &[u8]) -> Self::DigestPtr {
+    fn update(&self, state: &Digest, bytes: &[u8]) -> Digest {
         let desc = &InsecureShaHashDescriptor {
             state: state as *const Digest as u32,
             start: bytes.as_ptr() as u32,
@@ -49,16 +47,10 @@ impl risc0_zkp::core::sha::Sha for Impl {
         };
         crate::memory_barrier(desc);
         unsafe { GPIO_INSECURESHAHASH.as_ptr().write_volatile(desc) }
-        let digest: &[u32; DIGEST_WORDS] = host_recv(DIGEST_WORDS).try_into().unwrap();
-        bytemuck::cast_ref(digest)
+        Digest::from_slice(host_recv(DIGEST_WORDS))
     }
 
-    fn compress(
-        &self,
-        state: &Digest,
-        block_half1: &Digest,
-        block_half2: &Digest,
-    ) -> &'static Digest {
+    fn compress(&self, state: &Digest, block_half1: &Digest, block_half2: &Digest) -> Digest {
         let desc = &InsecureShaCompressDescriptor {
             state: state as *const Digest as u32,
             block_half1: block_half1 as *const Digest as u32,
@@ -66,7 +58,7 @@ impl risc0_zkp::core::sha::Sha for Impl {
         };
         crate::memory_barrier(desc);
         unsafe { GPIO_INSECURESHACOMPRESS.as_ptr().write_volatile(desc) }
-        let digest: &[u32; DIGEST_WORDS] = host_recv(DIGEST_WORDS).try_into().unwrap();
+        let digest = host_recv(DIGEST_WORDS);
 
         // We expect to take 72 cycles to do this operation in an
         // accelerated circuit.  With optimization, asking the host
@@ -74,21 +66,15 @@ impl risc0_zkp::core::sha::Sha for Impl {
         // extra work here.
         crate::memory_barrier(&desc.block_half1);
 
-        bytemuck::cast_ref(digest)
+        Digest::from_slice(digest)
     }
 
-    fn hash_raw_words(&self, words: &[u32]) -> Self::DigestPtr {
+    fn hash_raw_words(&self, words: &[u32]) -> Digest {
         self.update(&SHA256_INIT, bytemuck::cast_slice(words))
     }
 
-    fn hash_raw_pod_slice<T: bytemuck::Pod>(&self, pod: &[T]) -> Self::DigestPtr {
+    fn hash_raw_pod_slice<T: bytemuck::Pod>(&self, pod: &[T]) -> Digest {
         let u8s: &[u8] = bytemuck::cast_slice(pod);
         self.update(&SHA256_INIT, u8s)
     }
-
-    // Generate a new digest by mixing two digests together via XOR,
-    // and storing into the first digest.
-    fn mix(&self, pool: &mut Self::DigestPtr, val: &Digest) {
-        crate::sha::Impl {}.mix(pool, val)
-    }
 }
@@ -0,0 +1,34 @@
+// Copyright 2022 Risc0, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![no_main]
+
+// The allocator benchmarks of the `bench` method, built with the
+// free-list allocator so that the two can be compared.
+
+use risc0_zkvm_guest::env;
+use risc0_zkvm_methods::bench::{alloc_cycles, BenchmarkSpec, SpecWithIters};
+
+risc0_zkvm_guest::entry!(main);
+
+pub fn main() {
+    let SpecWithIters(spec, iters) = env::read();
+    match spec {
+        BenchmarkSpec::Alloc { size, count } => {
+            env::commit(&alloc_cycles(size, count, iters, env::get_cycle_count));
+        }
+        // Fails the run, which the host sees as a guest panic.
+        spec => panic!("The free-list guest only runs allocator benchmarks, not {spec:?}"),
+    }
+}
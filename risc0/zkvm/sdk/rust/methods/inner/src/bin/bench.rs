@@ -15,7 +15,7 @@
 #![no_main]
 
 use risc0_zkvm_guest::{env, memory_barrier, sha};
-use risc0_zkvm_methods::bench::{BenchmarkSpec, SpecWithIters};
+use risc0_zkvm_methods::bench::{alloc_cycles, BenchmarkSpec, SpecWithIters};
 
 risc0_zkvm_guest::entry!(main);
 
@@ -58,5 +58,8 @@ pub fn main() {
                 memory_barrier(&dst_slice);
             }
         }
+        BenchmarkSpec::Alloc { size, count } => {
+            env::commit(&alloc_cycles(size, count, iters, env::get_cycle_count));
+        }
     }
 }
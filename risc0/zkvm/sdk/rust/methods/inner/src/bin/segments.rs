@@ -0,0 +1,39 @@
+// Copyright 2022 Risc0, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![no_main]
+
+use risc0_zkvm_guest::{env, memory_barrier};
+
+risc0_zkvm_guest::entry!(main);
+
+// Runs `total` iterations of a hash-like loop, at most `per_segment` of them
+// in each segment, and commits the cycles each segment took.
+pub fn main() {
+    let (total, per_segment): (u32, u32) = env::read();
+    let (mut done, mut acc): (u32, u32) = env::resume().unwrap_or((0, 0));
+    let end = total.min(done.saturating_add(per_segment));
+    while done < end {
+        acc = acc.wrapping_mul(31).wrapping_add(done);
+        memory_barrier(&acc);
+        done += 1;
+    }
+    env::commit(&(env::get_cycle_count() as u32));
+    if done < total {
+        env::suspend(Some(&(done, acc)));
+    } else {
+        env::commit(&acc);
+        env::suspend::<()>(None);
+    }
+}
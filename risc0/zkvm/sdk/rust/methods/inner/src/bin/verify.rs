@@ -20,7 +20,7 @@ extern crate alloc;
 
 use risc0_zkp::{
     core::{fp::Fp, fp4::Fp4},
-    field::Elem,
+    field::{baby_bear::BabyBear, Elem},
     verify::{
         ffpu::fold_eval::{CODE as FOLD_EVAL_CODE, DATA as FOLD_EVAL_DATA},
         VerifyHal,
@@ -52,6 +52,7 @@ impl GuestVerifyHal {
 
 impl VerifyHal for GuestVerifyHal {
     type Sha = sha_insecure::Impl;
+    type Field = BabyBear;
 
     fn sha(&self) -> &Self::Sha {
         &self.sha
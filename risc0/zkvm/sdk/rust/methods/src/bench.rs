@@ -18,7 +18,32 @@ pub enum BenchmarkSpec {
     Memset {
         len: usize,
     },
+    Alloc {
+        size: usize,
+        count: usize,
+    },
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SpecWithIters(pub BenchmarkSpec, pub u64);
+
+// Allocates `count` buffers of `size` bytes and then frees them, to measure
+// the cost of the guest's allocator.
+fn alloc_churn(size: usize, count: usize) {
+    let bufs: Vec<Vec<u8>> = (0..count).map(|_| Vec::with_capacity(size)).collect();
+    for buf in bufs.iter() {
+        // Keep the allocations from being optimized out.
+        unsafe { (buf.as_ptr() as *mut u8).write_volatile(0) };
+    }
+}
+
+// Runs the `Alloc` benchmark `iters` times, returning the cycles it took as
+// counted by `cycle_count`.  Guests commit this, so that the host reports
+// the cycles spent allocating instead of the time taken to run the guest.
+pub fn alloc_cycles(size: usize, count: usize, iters: u64, cycle_count: fn() -> usize) -> u64 {
+    let start = cycle_count();
+    for _ in 0..iters {
+        alloc_churn(size, count);
+    }
+    (cycle_count() - start) as u64
+}
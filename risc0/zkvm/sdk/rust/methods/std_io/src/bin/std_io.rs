@@ -0,0 +1,37 @@
+// Copyright 2022 Risc0, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![no_main]
+
+use std::io::{BufRead, BufReader, Write};
+
+use risc0_zkvm_guest::{
+    env,
+    io::{ChannelReader, ChannelWriter},
+};
+
+risc0_zkvm_guest::entry!(main);
+
+// Reads lines of text through std::io::BufRead, and writes them back in
+// upper case through std::io::Write.
+pub fn main() {
+    let input_channel: u32 = env::read();
+    let output_channel: u32 = env::read();
+
+    let mut output = ChannelWriter::new(output_channel);
+    for line in BufReader::new(ChannelReader::new(input_channel)).lines() {
+        writeln!(output, "{}", line.unwrap().to_uppercase()).unwrap();
+    }
+    output.flush().unwrap();
+}
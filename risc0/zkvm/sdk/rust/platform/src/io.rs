@@ -65,6 +65,15 @@ pub const GPIO_COMPUTE_POLY: Gpio<*const ComputePolyDescriptor> = Gpio::new(0x01
 pub const GPIO_POLY_EVAL: Gpio<*const PolyEvalDescriptor> = Gpio::new(0x01F00030);
 pub const GPIO_FFPU: Gpio<*const FfpuDescriptor> = Gpio::new(0x01F00034);
 
+/// Directs all further data from the host to the region described, instead
+/// of the rest of the current input window.  The region must be memory the
+/// guest has not yet touched.
+pub const GPIO_INPUT_WINDOW: Gpio<*const SliceDescriptor> = Gpio::new(0x01F00038);
+
+/// Reports that the guest's heap is exhausted, which a compliant host treats
+/// as a fault.
+pub const GPIO_OUT_OF_MEMORY: Gpio<*const OutOfMemoryDescriptor> = Gpio::new(0x01F0003C);
+
 pub mod addr {
     pub const GPIO_SHA: u32 = super::GPIO_SHA.addr();
     pub const GPIO_COMMIT: u32 = super::GPIO_COMMIT.addr();
@@ -84,6 +93,9 @@ pub mod addr {
     pub const GPIO_COMPUTE_POLY: u32 = super::GPIO_COMPUTE_POLY.addr();
     pub const GPIO_POLY_EVAL: u32 = super::GPIO_POLY_EVAL.addr();
     pub const GPIO_FFPU: u32 = super::GPIO_FFPU.addr();
+
+    pub const GPIO_INPUT_WINDOW: u32 = super::GPIO_INPUT_WINDOW.addr();
+    pub const GPIO_OUT_OF_MEMORY: u32 = super::GPIO_OUT_OF_MEMORY.addr();
 }
 
 #[repr(C)]
@@ -100,6 +112,10 @@ pub struct SHADescriptor {
     pub digest: u32,
 }
 
+/// Requests the key named by the nul-terminated string at `name`.  The
+/// host writes the `KEY_WORDS` words of the key to `addr`, which must
+/// be memory the guest has not yet touched.  `mode` is one of the
+/// `GETKEY_MODE_*` values.
 #[repr(C)]
 pub struct GetKeyDescriptor {
     pub name: u32,
@@ -107,6 +123,22 @@ pub struct GetKeyDescriptor {
     pub mode: u32,
 }
 
+// Key sizes and modes; must match zkvm/sdk/cpp/guest/key.h.
+pub const KEY_WORDS: usize = 4;
+pub const GETKEY_MODE_NEW: u32 = 0;
+pub const GETKEY_MODE_EXISTING: u32 = 1;
+pub const GETKEY_MODE_ANY: u32 = 2;
+
+/// Describes an allocation the heap couldn't satisfy, written to
+/// `GPIO_OUT_OF_MEMORY`.  All sizes are in bytes.
+#[repr(C)]
+pub struct OutOfMemoryDescriptor {
+    pub requested: u32,
+    pub align: u32,
+    pub used: u32,
+    pub limit: u32,
+}
+
 #[repr(C)]
 pub struct InsecureShaCompressDescriptor {
     pub state: u32,
@@ -152,6 +184,8 @@ pub struct FfpuDescriptor {
 pub const SENDRECV_CHANNEL_INITIAL_INPUT: u32 = 0;
 pub const SENDRECV_CHANNEL_STDOUT: u32 = 1;
 pub const SENDRECV_CHANNEL_STDERR: u32 = 2;
+pub const SENDRECV_CHANNEL_SEGMENT_RESUME: u32 = 3;
+pub const SENDRECV_CHANNEL_SEGMENT_SUSPEND: u32 = 4;
 
 impl SliceDescriptor {
     pub fn new<T>(slice: &[T]) -> Self {
@@ -38,5 +38,8 @@ pub mod rt {
         pub fn host_recv(_nwords: usize) -> &'static [u32] {
             unimplemented!()
         }
+        pub fn set_input_refill(_refill: fn(usize) -> (usize, usize)) {
+            unimplemented!()
+        }
     }
 }
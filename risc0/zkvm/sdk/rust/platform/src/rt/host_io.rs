@@ -12,37 +12,83 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use core::cell::UnsafeCell;
+use core::{
+    cell::UnsafeCell,
+    sync::atomic::{compiler_fence, Ordering},
+};
 
 use crate::{
-    io::{GPIO_SENDRECV_ADDR, GPIO_SENDRECV_CHANNEL, GPIO_SENDRECV_SIZE},
+    io::{
+        SliceDescriptor, GPIO_INPUT_WINDOW, GPIO_SENDRECV_ADDR, GPIO_SENDRECV_CHANNEL,
+        GPIO_SENDRECV_SIZE,
+    },
     memory, WORD_SIZE,
 };
 
+/// Once a message from the host leaves less than this many bytes of the
+/// input window, a new window is requested from the function given to
+/// [set_input_refill].  Larger responses to [host_sendrecv] get a window of
+/// their own instead.
+pub const INPUT_HEADROOM: usize = 64 * 1024;
+
 // Current offset in number of words from the INPUT memory region that
 // we're reading,
 static mut READ_PTR: UnsafeCell<usize> = UnsafeCell::new(memory::INPUT.start());
 
-/// Reads a fixed-length message from the host, with the size
-/// specified in number of words.  Normally this would be called in
-/// response to a GPIO request of some sort.  Callers should always
-/// call this as soon as possible after the GPIO request in order to
-/// avoid desynchronization.
-pub fn host_recv(nwords: usize) -> &'static [u32] {
+// End of the window the host is currently sending data to.
+static mut READ_END: UnsafeCell<usize> = UnsafeCell::new(memory::INPUT.end());
+
+// Supplies a fresh input window of at least the given size once the current
+// one runs low.
+static mut REFILL: UnsafeCell<Option<fn(usize) -> (usize, usize)>> = UnsafeCell::new(None);
+
+fn recv(nwords: usize) -> &'static [u32] {
     // SAFETY: We're single threaded, so it's ok to borrow READ_PTR while in this
     // routine.
     let read_ptr: &mut usize = unsafe { &mut *READ_PTR.get() };
     let read_start = *read_ptr;
     let read_end = read_ptr.checked_add(nwords * WORD_SIZE).unwrap();
-    if read_end > memory::INPUT.end() {
+    if read_end > unsafe { *READ_END.get() } {
         panic!("host_recv overran input buffer with {nwords} word read");
     }
     *read_ptr = read_end;
 
-    // SAFETY: This region is in the INPUT region and we just did a bounds check.
+    // SAFETY: This region is in the input window and we just did a bounds check.
     unsafe { core::slice::from_raw_parts(read_start as *const u32, nwords) }
 }
 
+// Moves to a new input window of at least `min_len` bytes if less than
+// `min_len` bytes of the current one are left and a refill function has been
+// registered.  This must only be called between messages, or between the
+// length and the body of a response.
+fn refill_for(min_len: usize) {
+    let (read_ptr, read_end, refill) = unsafe { (*READ_PTR.get(), *READ_END.get(), *REFILL.get()) };
+    if read_end - read_ptr >= min_len {
+        return;
+    }
+    if let Some(refill) = refill {
+        let (start, len) = refill(min_len);
+        // SAFETY: the refill function promises fresh memory.
+        unsafe { set_input_window(start, len) };
+    }
+}
+
+// Moves to a new input window if the current one is running low.
+fn maybe_refill() {
+    refill_for(INPUT_HEADROOM);
+}
+
+/// Reads a fixed-length message from the host, with the size
+/// specified in number of words.  Normally this would be called in
+/// response to a GPIO request of some sort.  Callers should always
+/// call this as soon as possible after the GPIO request in order to
+/// avoid desynchronization.
+pub fn host_recv(nwords: usize) -> &'static [u32] {
+    let data = recv(nwords);
+    maybe_refill();
+    data
+}
+
 /// Interacts with the host.  'channel' specifies the ZKVM channel to
 /// use, and 'buf' provides the data to tsend to the host.
 ///
@@ -58,12 +104,49 @@ pub fn host_sendrecv(channel: u32, buf: &[u8]) -> (&'static [u32], usize) {
         GPIO_SENDRECV_ADDR.as_ptr().write_volatile(buf.as_ptr());
     }
 
-    let response_nbytes = match host_recv(1) {
+    let response_nbytes = match recv(1) {
         &[nbytes] => nbytes,
         _ => unreachable!(),
     } as usize;
 
     let response_nwords = (response_nbytes + WORD_SIZE - 1) / WORD_SIZE;
 
-    (host_recv(response_nwords), response_nbytes)
+    // The host holds back a response which doesn't fit in the rest of the
+    // window, and sends it to the start of the next one.
+    refill_for(response_nwords * WORD_SIZE);
+    let data = recv(response_nwords);
+    maybe_refill();
+    (data, response_nbytes)
+}
+
+/// Has the host send all further data to the `len` bytes at `start`, instead
+/// of the rest of the current input window.  Data already received stays
+/// where it is.
+///
+/// # Safety
+///
+/// `start` must be word aligned, and the region must be memory the guest has
+/// never touched and will not use for anything else.
+pub unsafe fn set_input_window(start: usize, len: usize) {
+    let desc = SliceDescriptor {
+        addr: start as u32,
+        size: len as u32,
+    };
+    // Make sure the descriptor is in memory before the host reads it.
+    compiler_fence(Ordering::SeqCst);
+    GPIO_INPUT_WINDOW.as_ptr().write_volatile(&desc);
+    compiler_fence(Ordering::SeqCst);
+    *READ_PTR.get() = start;
+    *READ_END.get() = start + len;
+}
+
+/// Registers `refill` to supply a new input window whenever less than
+/// [INPUT_HEADROOM] bytes of the current one are left, or a response from
+/// [host_sendrecv] doesn't fit in it, so that the guest can receive more data
+/// over its lifetime than fits in the INPUT region.  It is passed the number
+/// of bytes the window must hold at least, and returns the start and length in
+/// bytes of the new window, which must meet the requirements of
+/// [set_input_window].
+pub fn set_input_refill(refill: fn(usize) -> (usize, usize)) {
+    unsafe { *REFILL.get() = Some(refill) };
 }
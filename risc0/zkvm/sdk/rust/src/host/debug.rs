@@ -0,0 +1,63 @@
+// Copyright 2022 Risc0, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use risc0_zkvm_platform::WORD_SIZE;
+
+/// The number of general purpose registers, including the zero register.
+pub const REGISTER_COUNT: usize = 32;
+
+/// A read-only view of the guest between two instructions.
+pub struct GuestState<'a> {
+    pub(crate) pc: u32,
+    pub(crate) registers: &'a [u32; REGISTER_COUNT],
+    // Keyed by word address.
+    pub(crate) memory: &'a BTreeMap<u32, u32>,
+}
+
+impl<'a> GuestState<'a> {
+    /// The address of the next instruction to execute.
+    pub fn pc(&self) -> u32 {
+        self.pc
+    }
+
+    /// The value of register `idx`, which must be less than
+    /// [REGISTER_COUNT].
+    pub fn register(&self, idx: usize) -> u32 {
+        self.registers[idx]
+    }
+
+    /// The byte at `addr`, or `None` if neither the guest's image nor the
+    /// guest itself has put anything there.
+    pub fn load_u8(&self, addr: u32) -> Option<u8> {
+        let word = self.memory.get(&(addr / WORD_SIZE as u32))?;
+        let offset = addr % WORD_SIZE as u32;
+        Some((word >> (offset * 8)) as u8)
+    }
+}
+
+/// Controls the execution of a guest, one instruction at a time.  Install
+/// one with [ProverOpts::with_debugger](super::ProverOpts::with_debugger);
+/// see [GdbStub](super::GdbStub) for debugging with GDB.
+pub trait Debugger {
+    /// Called before each instruction executes, and returns when the guest
+    /// should resume.  Returning an error aborts execution.
+    fn on_instruction(&mut self, state: &GuestState) -> Result<()>;
+
+    /// Called once the guest has halted, or when it faults, in which case
+    /// `fault` holds the message it reported.
+    fn on_exit(&mut self, state: &GuestState, fault: Option<&str>) -> Result<()>;
+}
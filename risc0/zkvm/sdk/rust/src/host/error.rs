@@ -0,0 +1,136 @@
+// Copyright 2022 Risc0, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+
+/// The ways in which running a guest on the Rust prover can fail.
+///
+/// Errors raised by callbacks supplied through [ProverOpts](super::ProverOpts)
+/// are passed through unchanged, so callers can tell bugs in the guest apart
+/// from problems with the host's configuration by downcasting to this type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProverError {
+    /// The guest did something the circuit does not allow, such as an
+    /// unaligned memory access or writing twice to write-once memory.
+    GuestFault,
+
+    /// The guest aborted with the given message, usually from a panic.
+    GuestPanic(String),
+
+    /// The guest exhausted its heap.
+    GuestOutOfMemory {
+        /// The size in bytes of the allocation which failed.
+        requested: u32,
+
+        /// The number of bytes of the heap already in use.
+        used: u32,
+
+        /// The size of the heap in bytes.
+        limit: u32,
+    },
+
+    /// The guest sent data on a channel with no handler.
+    UnknownChannel(u32),
+
+    /// The guest asked the host to access memory outside its address space.
+    MemoryOutOfRange {
+        /// The address of the access.
+        addr: u32,
+    },
+
+    /// The guest asked the host to read a word of memory which was never
+    /// written.
+    UninitializedMemory {
+        /// The address of the word.
+        addr: u32,
+    },
+
+    /// The guest asked the host to access a word at an unaligned address.
+    UnalignedAccess {
+        /// The address of the access.
+        addr: u32,
+
+        /// The address of the instruction which made the request.
+        pc: u32,
+    },
+
+    /// The host sent the guest more input than fits in its input window.
+    InputOverflow {
+        /// The number of bytes being sent.
+        requested: usize,
+
+        /// The number of bytes left in the input window.
+        available: usize,
+    },
+
+    /// The guest ran for more cycles than the prover allows.
+    CycleLimitExceeded {
+        /// The most cycles the prover allows, either from
+        /// [ProverOpts::with_max_cycles](super::ProverOpts::with_max_cycles)
+        /// or the largest trace a seal can hold.
+        limit: usize,
+
+        /// The cycle the guest had reached when it was stopped.  This can be
+        /// under `limit` if the trace would have had to grow past it.
+        cycle: usize,
+    },
+
+    /// The host tried to change memory which the guest has already used.
+    HostMutation {
+        /// The address of the word being changed.
+        addr: u32,
+    },
+}
+
+impl fmt::Display for ProverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProverError::GuestFault => write!(f, "Guest fault"),
+            ProverError::GuestPanic(msg) => write!(f, "Guest fault: {msg}"),
+            ProverError::GuestOutOfMemory {
+                requested,
+                used,
+                limit,
+            } => write!(
+                f,
+                "Guest out of memory: requested {requested} bytes with {used} of {limit} bytes of heap in use"
+            ),
+            ProverError::UnknownChannel(channel) => write!(f, "Unknown channel: {channel}"),
+            ProverError::MemoryOutOfRange { addr } => {
+                write!(f, "Address out of range: 0x{addr:08X}")
+            }
+            ProverError::UninitializedMemory { addr } => {
+                write!(f, "Read of uninitialized memory at 0x{addr:08X}")
+            }
+            ProverError::UnalignedAccess { addr, pc } => {
+                write!(f, "Unaligned access to 0x{addr:08X} at pc 0x{pc:08X}")
+            }
+            ProverError::InputOverflow {
+                requested,
+                available,
+            } => write!(
+                f,
+                "Input of {requested} bytes overflows the guest's input window with {available} bytes left"
+            ),
+            ProverError::CycleLimitExceeded { limit, cycle } => {
+                write!(f, "Cycle limit of {limit} exceeded at cycle {cycle}")
+            }
+            ProverError::HostMutation { addr } => {
+                write!(f, "Host cannot mutate existing memory at 0x{addr:08X}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProverError {}
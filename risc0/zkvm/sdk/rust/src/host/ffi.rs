@@ -14,10 +14,13 @@
 
 use std::{ffi::CStr, mem, os::raw::c_char};
 
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use risc0_zkp::{adapter::CircuitInfo, ProofParams};
+use risc0_zkvm_circuit::CircuitImpl;
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
 
 use super::exception::Exception;
-use super::ProverOpts;
+use super::{Key, KeyMode, KeyProvider, NoKeyProvider, ProverOpts, SendRecvHandler, SendRecvReply};
+use crate::receipt::{HashFn, Receipt as ReceiptData, ReceiptHeader, CIRCUIT_ID};
 
 pub(crate) enum RawString {}
 pub(crate) enum RawProver {}
@@ -132,6 +135,19 @@ extern "C" {
         cbdata: *const u8,
     );
 
+    pub(crate) fn risc0_prover_set_getkey_handler(
+        rr: *mut RawError,
+        prover: *mut RawProver,
+        callback: unsafe extern "C" fn(
+            name: *const u8,
+            len: usize,
+            mode: u32,
+            key: *mut u32,
+            cbdata: *const u8,
+        ) -> bool,
+        cbdata: *const u8,
+    );
+
     pub(crate) fn risc0_receipt_new(
         err: *mut RawError,
         journal: *const u8,
@@ -317,18 +333,20 @@ impl Receipt {
 
 // TODO(nils): Lift "Receipt" from the pure-rust verify implementation so we
 // don't have to proxy through this structure.
-#[derive(Serialize, Deserialize)]
-struct ReceiptData {
-    journal: Vec<u8>,
-    seal: Vec<u32>,
-}
-
 impl Serialize for Receipt {
     /// Generate a serialized version of the whole receipt.
     fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
-        let data: ReceiptData = ReceiptData {
-            journal: self.get_journal().unwrap().into(),
-            seal: self.get_seal().unwrap().into(),
+        let journal = self.get_journal_vec().map_err(ser::Error::custom)?;
+        let mut seal: Vec<u32> = self.get_seal().map_err(ser::Error::custom)?.into();
+        let po2 = seal.get(CircuitImpl::OUTPUT_SIZE).copied().unwrap_or(0);
+        // The C++ prover always uses the default parameters.
+        if !seal.is_empty() {
+            seal.extend(ProofParams::default().to_words());
+        }
+        let data = ReceiptData {
+            header: ReceiptHeader::new(&journal, po2),
+            journal,
+            seal,
         };
         data.serialize(serializer)
     }
@@ -336,12 +354,38 @@ impl Serialize for Receipt {
 
 impl<'de> Deserialize<'de> for Receipt {
     /// Deserialize a receipt.
+    ///
+    /// The C++ receipt has no room for the header, so receipts from an
+    /// incompatible circuit, hash or [ProofParams] are rejected here rather
+    /// than in [Receipt::verify].
     fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
         let data = ReceiptData::deserialize(deserializer)?;
-        Ok(Receipt::new(&data.journal, &data.seal).unwrap())
+        if data.header.circuit_id != CIRCUIT_ID {
+            return Err(de::Error::custom(format!(
+                "Receipt was produced by circuit {:?}, but this verifier supports {:?}",
+                data.header.circuit_id, CIRCUIT_ID
+            )));
+        }
+        if data.header.hash != HashFn::Sha256 {
+            return Err(de::Error::custom(format!(
+                "Receipt seal uses {:?}, but this verifier only supports SHA-256",
+                data.header.hash
+            )));
+        }
+        let mut seal = data.seal;
+        if !seal.is_empty() {
+            let params = ProofParams::from_seal(&seal).map_err(de::Error::custom)?;
+            if params != ProofParams::default() {
+                return Err(de::Error::custom(format!(
+                    "Receipt seal uses {params:?}, but this verifier only supports the defaults"
+                )));
+            }
+            seal.truncate(seal.len() - ProofParams::SEAL_WORDS);
+        }
+        Receipt::new(bytemuck::cast_slice(&data.journal), &seal).map_err(de::Error::custom)
     }
 }
 
@@ -429,16 +473,74 @@ impl<'a> Prover<'a> {
         len: usize,
         cbdata: *const u8,
     ) -> *mut RawU8Buffer {
-        let cb = cbdata as *const Box<dyn Fn(u32, &[u8]) -> Vec<u8>>;
+        let ctx = &mut *(cbdata as *mut HandlerContext);
 
         let from_guest = std::slice::from_raw_parts(buf, len);
-        let to_guest = (*cb)(channel_id, from_guest);
+        let mut to_guest = Vec::new();
+        let mut sink = |data: &[u8]| {
+            to_guest.extend_from_slice(data);
+            Ok(())
+        };
+        let mut reply = SendRecvReply::new(&mut sink);
+        if let Err(err) = ctx.handler.on_sendrecv(channel_id, from_guest, &mut reply) {
+            // Returning null makes the C++ prover stop right away.
+            ctx.error = Some(err);
+            return std::ptr::null_mut();
+        }
 
         risc0_u8buffer_new(to_guest.as_ptr(), to_guest.len())
     }
 
+    unsafe extern "C" fn handle_get_key(
+        name: *const u8,
+        len: usize,
+        mode: u32,
+        key: *mut u32,
+        cbdata: *const u8,
+    ) -> bool {
+        let ctx = &mut *(cbdata as *mut KeyContext);
+
+        let name = std::slice::from_raw_parts(name, len);
+        let result = (|| -> anyhow::Result<Key> {
+            let name = std::str::from_utf8(name)?;
+            ctx.provider.get_key(name, KeyMode::from_u32(mode)?)
+        })();
+        match result {
+            Ok(words) => {
+                std::slice::from_raw_parts_mut(key, words.len()).copy_from_slice(&words);
+                true
+            }
+            Err(err) => {
+                // Returning false makes the C++ prover stop right away.
+                ctx.error = Some(err);
+                false
+            }
+        }
+    }
+
     /// Execute the ZKVM to produce a [Receipt].
     pub fn run(&mut self) -> super::Result<Receipt> {
+        if self.opts.trace_callback.is_some() {
+            return Err(Exception::new(
+                "Trace callbacks are only supported by the pure-prove prover",
+            ));
+        }
+        if self.opts.debugger.is_some() {
+            return Err(Exception::new(
+                "Debuggers are only supported by the pure-prove prover",
+            ));
+        }
+        if self.opts.hash != HashFn::Sha256 {
+            return Err(Exception::new(
+                "Hashes other than SHA-256 are only supported by the pure-prove prover",
+            ));
+        }
+        if self.opts.params != ProofParams::default() {
+            return Err(Exception::new(
+                "Proof parameters are only supported by the pure-prove prover",
+            ));
+        }
+
         let mut err = RawError::default();
 
         unsafe {
@@ -446,27 +548,80 @@ impl<'a> Prover<'a> {
         };
         check(err, || ())?;
 
-        for (channel_id, cb) in self.opts.sendrecv_callbacks.iter() {
+        let mut handlers: Vec<(u32, HandlerContext)> = self
+            .opts
+            .sendrecv_handlers
+            .iter_mut()
+            .map(|(channel_id, handler)| {
+                let ctx = HandlerContext {
+                    handler: handler.as_mut(),
+                    error: None,
+                };
+                (*channel_id, ctx)
+            })
+            .collect();
+        for (channel_id, ctx) in handlers.iter_mut() {
             let mut err = RawError::default();
             unsafe {
-                let cb: *const Box<_> = cb;
+                let ctx: *mut HandlerContext = ctx;
                 risc0_prover_set_sendrecv_handler(
                     &mut err,
                     self.ptr,
                     *channel_id,
                     Self::handle_callback,
-                    cb.cast(),
+                    ctx as *const u8,
                 );
             };
             check(err, || ())?;
         }
 
+        let mut no_keys = NoKeyProvider;
+        let mut keys = KeyContext {
+            provider: match self.opts.key_provider.as_mut() {
+                Some(provider) => provider.as_mut(),
+                None => &mut no_keys,
+            },
+            error: None,
+        };
+        let mut err = RawError::default();
+        unsafe {
+            let keys: *mut KeyContext = &mut keys;
+            risc0_prover_set_getkey_handler(
+                &mut err,
+                self.ptr,
+                Self::handle_get_key,
+                keys as *const u8,
+            );
+        };
+        check(err, || ())?;
+
         let mut err = RawError::default();
         let ptr = unsafe { risc0_prover_run(&mut err, self.ptr) };
-        check(err, || Receipt { ptr })
+        let result = check(err, || Receipt { ptr });
+        // A failed handler aborts the prover, so its error is the real cause
+        // of the prover's.
+        let errors = handlers.into_iter().map(|(_, ctx)| ctx.error);
+        if let Some(err) = errors.chain([keys.error]).flatten().next() {
+            return Err(Exception::new(&err.to_string()));
+        }
+        result
     }
 }
 
+// The state behind each sendrecv callback from the C++ prover.  Errors
+// can't be passed back through the callback, so the callback returns null to
+// abort the prover and the error is kept here to be reported instead.
+struct HandlerContext<'b> {
+    handler: &'b mut dyn SendRecvHandler,
+    error: Option<anyhow::Error>,
+}
+
+// Likewise for the key provider.
+struct KeyContext<'b> {
+    provider: &'b mut dyn KeyProvider,
+    error: Option<anyhow::Error>,
+}
+
 impl Drop for Receipt {
     fn drop(&mut self) {
         let mut err = RawError::default();
@@ -0,0 +1,360 @@
+// Copyright 2022 Risc0, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    collections::BTreeSet,
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+};
+
+use anyhow::{bail, Result};
+use log::debug;
+
+use super::{Debugger, GuestState, REGISTER_COUNT};
+
+// GDB numbers the pc right after the general purpose registers.
+const PC_REGNUM: usize = REGISTER_COUNT;
+
+// Stop replies, as signal numbers.
+const STOP_TRAP: &str = "S05";
+const STOP_ABORT: &str = "S06";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    Step,
+    Continue,
+    Detached,
+}
+
+/// A [Debugger] which serves the GDB remote serial protocol, so that the
+/// guest can be debugged with `target remote` from a RISC-V GDB.
+///
+/// The guest stops before its first instruction.  Breakpoints,
+/// single-stepping and reading registers and memory are supported.  Writes
+/// are refused, since the host cannot change the guest's state without
+/// invalidating the proof.
+pub struct GdbStub {
+    stream: TcpStream,
+    breakpoints: BTreeSet<u32>,
+    mode: Mode,
+    // Whether GDB is waiting for a stop reply.
+    resumed: bool,
+}
+
+impl GdbStub {
+    /// Wait for GDB to connect to the given port on the loopback interface.
+    pub fn listen(port: u16) -> Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        let (stream, addr) = listener.accept()?;
+        debug!("GDB connected from {addr}");
+        Ok(Self::new(stream))
+    }
+
+    /// Serve GDB over an established connection.
+    pub fn new(stream: TcpStream) -> Self {
+        // Packets are small and every one of them waits for a reply.
+        stream.set_nodelay(true).ok();
+        GdbStub {
+            stream,
+            breakpoints: BTreeSet::new(),
+            mode: Mode::Step,
+            resumed: false,
+        }
+    }
+
+    // Returns the next packet, or None if GDB has gone away.
+    fn read_packet(&mut self) -> Result<Option<String>> {
+        loop {
+            // Skip acks and interrupts until the start of a packet.
+            loop {
+                match self.read_byte()? {
+                    Some(b'$') => break,
+                    Some(_) => continue,
+                    None => return Ok(None),
+                }
+            }
+            let mut data = Vec::new();
+            loop {
+                match self.read_byte()? {
+                    Some(b'#') => break,
+                    Some(byte) => data.push(byte),
+                    None => return Ok(None),
+                }
+            }
+            let mut checksum = [0; 2];
+            self.stream.read_exact(&mut checksum)?;
+            if u8::from_str_radix(std::str::from_utf8(&checksum)?, 16) == Ok(checksum_of(&data)) {
+                self.stream.write_all(b"+")?;
+                return Ok(Some(String::from_utf8(data)?));
+            }
+            self.stream.write_all(b"-")?;
+        }
+    }
+
+    fn read_byte(&mut self) -> Result<Option<u8>> {
+        let mut byte = [0];
+        match self.stream.read(&mut byte)? {
+            0 => Ok(None),
+            _ => Ok(Some(byte[0])),
+        }
+    }
+
+    fn send(&mut self, data: &str) -> Result<()> {
+        let packet = format!("${data}#{:02x}", checksum_of(data.as_bytes()));
+        loop {
+            self.stream.write_all(packet.as_bytes())?;
+            match self.read_byte()? {
+                Some(b'-') => continue,
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn stop(&mut self, state: &GuestState, reply: &str) -> Result<()> {
+        if self.resumed {
+            self.resumed = false;
+            self.send(reply)?;
+        }
+        loop {
+            let packet = match self.read_packet()? {
+                Some(packet) => packet,
+                None => {
+                    debug!("GDB disconnected");
+                    self.mode = Mode::Detached;
+                    return Ok(());
+                }
+            };
+            debug!("GDB> {packet}");
+            let (cmd, args) = packet.split_at(packet.len().min(1));
+            let response = match cmd {
+                "?" => reply.to_string(),
+                "c" | "s" => {
+                    self.mode = if cmd == "c" {
+                        Mode::Continue
+                    } else {
+                        Mode::Step
+                    };
+                    self.resumed = true;
+                    return Ok(());
+                }
+                "D" => {
+                    self.send("OK")?;
+                    self.mode = Mode::Detached;
+                    return Ok(());
+                }
+                "k" => bail!("Killed by debugger"),
+                "g" => (0..REGISTER_COUNT)
+                    .map(|idx| state.register(idx))
+                    .chain([state.pc()])
+                    .map(hex_u32)
+                    .collect(),
+                "p" => match usize::from_str_radix(args, 16) {
+                    Ok(idx) if idx < REGISTER_COUNT => hex_u32(state.register(idx)),
+                    Ok(PC_REGNUM) => hex_u32(state.pc()),
+                    _ => "E01".into(),
+                },
+                "m" => read_memory(state, args).unwrap_or_else(|| "E01".into()),
+                "Z" | "z" => match parse_breakpoint(args) {
+                    Some(addr) => {
+                        if cmd == "Z" {
+                            self.breakpoints.insert(addr);
+                        } else {
+                            self.breakpoints.remove(&addr);
+                        }
+                        "OK".into()
+                    }
+                    None => String::new(),
+                },
+                "G" | "P" | "M" | "X" => "E01".into(),
+                "H" => "OK".into(),
+                "q" => match args {
+                    "Attached" => "1".into(),
+                    "C" => "QC1".into(),
+                    "fThreadInfo" => "m1".into(),
+                    "sThreadInfo" => "l".into(),
+                    _ if args.starts_with("Supported") => "PacketSize=1000".into(),
+                    _ => String::new(),
+                },
+                _ => String::new(),
+            };
+            self.send(&response)?;
+        }
+    }
+}
+
+impl Debugger for GdbStub {
+    fn on_instruction(&mut self, state: &GuestState) -> Result<()> {
+        match self.mode {
+            Mode::Detached => Ok(()),
+            Mode::Continue if !self.breakpoints.contains(&state.pc()) => Ok(()),
+            _ => self.stop(state, STOP_TRAP),
+        }
+    }
+
+    fn on_exit(&mut self, state: &GuestState, fault: Option<&str>) -> Result<()> {
+        if self.mode == Mode::Detached {
+            return Ok(());
+        }
+        match fault {
+            Some(_) => {
+                // Give the user a chance to look around before the guest is
+                // torn down.
+                self.stop(state, STOP_ABORT)?;
+                if self.mode == Mode::Detached {
+                    return Ok(());
+                }
+                self.send("X06")
+            }
+            None => self.send("W00"),
+        }
+    }
+}
+
+fn checksum_of(data: &[u8]) -> u8 {
+    data.iter().fold(0, |sum, byte| sum.wrapping_add(*byte))
+}
+
+// GDB expects registers in target byte order.
+fn hex_u32(value: u32) -> String {
+    format!("{:08x}", value.swap_bytes())
+}
+
+// Parses "addr,len" and reads as much of it as is available.
+fn read_memory(state: &GuestState, args: &str) -> Option<String> {
+    let (addr, len) = args.split_once(',')?;
+    let addr = u32::from_str_radix(addr, 16).ok()?;
+    let len = u32::from_str_radix(len, 16).ok()?;
+    let bytes: String = (addr..addr.saturating_add(len))
+        .map_while(|addr| state.load_u8(addr))
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    if bytes.is_empty() && len != 0 {
+        None
+    } else {
+        Some(bytes)
+    }
+}
+
+// Parses "type,addr,kind" for software and hardware breakpoints.
+fn parse_breakpoint(args: &str) -> Option<u32> {
+    let mut fields = args.split(',');
+    match fields.next()? {
+        "0" | "1" => u32::from_str_radix(fields.next()?, 16).ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::BTreeMap,
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+        thread,
+    };
+
+    use super::{checksum_of, GdbStub};
+    use crate::host::{Debugger, GuestState, REGISTER_COUNT};
+
+    fn request(stream: &mut TcpStream, data: &str) -> String {
+        write!(stream, "${data}#{:02x}", checksum_of(data.as_bytes())).unwrap();
+        let mut byte = [0];
+        stream.read_exact(&mut byte).unwrap();
+        assert_eq!(&byte, b"+");
+        let mut response = Vec::new();
+        loop {
+            stream.read_exact(&mut byte).unwrap();
+            match byte[0] {
+                b'$' => continue,
+                b'#' => break,
+                byte => response.push(byte),
+            }
+        }
+        let mut checksum = [0; 2];
+        stream.read_exact(&mut checksum).unwrap();
+        stream.write_all(b"+").unwrap();
+        String::from_utf8(response).unwrap()
+    }
+
+    #[test]
+    fn loopback() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let client = thread::spawn(move || {
+            let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+            assert_eq!(request(&mut stream, "?"), "S05");
+            assert_eq!(request(&mut stream, "p20"), "00100000");
+            assert_eq!(request(&mut stream, "p2"), "78563412");
+            assert_eq!(request(&mut stream, "m1000,6"), "130000006f00");
+            assert_eq!(request(&mut stream, "m2000,4"), "E01");
+            assert_eq!(request(&mut stream, "M1000,4:00000000"), "E01");
+            assert_eq!(request(&mut stream, "Z0,1008,4"), "OK");
+            assert_eq!(request(&mut stream, "c"), "S05");
+            assert_eq!(request(&mut stream, "p20"), "08100000");
+            assert_eq!(request(&mut stream, "s"), "S05");
+            assert_eq!(request(&mut stream, "p20"), "0c100000");
+            assert_eq!(request(&mut stream, "c"), "W00");
+        });
+
+        let mut stub = GdbStub::new(listener.accept().unwrap().0);
+        let mut registers = [0; REGISTER_COUNT];
+        registers[2] = 0x12345678;
+        let memory = BTreeMap::from([(0x400, 0x13), (0x401, 0x6f)]);
+        let state = |pc| GuestState {
+            pc,
+            registers: &registers,
+            memory: &memory,
+        };
+        for pc in [0x1000, 0x1004, 0x1008, 0x100c, 0x1010] {
+            stub.on_instruction(&state(pc)).unwrap();
+        }
+        stub.on_exit(&state(0x1010), None).unwrap();
+        client.join().unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "pure-prove")]
+    fn guest_fault() {
+        use risc0_zkvm_methods::{FAIL_ID, FAIL_PATH};
+        use risc0_zkvm_platform::memory::MEM_SIZE;
+
+        use super::hex_u32;
+        use crate::{
+            elf::Program,
+            host::{Prover, ProverOpts},
+        };
+
+        let elf = std::fs::read(FAIL_PATH).unwrap();
+        let entry = Program::load_elf(&elf, MEM_SIZE as u32).unwrap().entry;
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let client = thread::spawn(move || {
+            let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+            assert_eq!(request(&mut stream, "?"), "S05");
+            assert_eq!(request(&mut stream, "p20"), hex_u32(entry));
+            assert_eq!(request(&mut stream, "s"), "S05");
+            assert_ne!(request(&mut stream, "p20"), hex_u32(entry));
+            assert_eq!(request(&mut stream, "c"), "S06");
+            assert_eq!(request(&mut stream, "c"), "X06");
+        });
+
+        let stub = GdbStub::new(listener.accept().unwrap().0);
+        let opts = ProverOpts::default()
+            .with_skip_seal(true)
+            .with_debugger(stub);
+        let mut prover = Prover::new_with_opts(&elf, FAIL_ID, opts).unwrap();
+        assert!(prover.run().is_err());
+        client.join().unwrap();
+    }
+}
@@ -0,0 +1,158 @@
+// Copyright 2022 Risc0, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use rand::{thread_rng, Rng};
+use risc0_zkvm_platform::io::{GETKEY_MODE_ANY, GETKEY_MODE_EXISTING, GETKEY_MODE_NEW, KEY_WORDS};
+use sha2::{Digest, Sha256};
+
+/// A secret key handed to the guest when it calls `env::get_key`.
+pub type Key = [u32; KEY_WORDS];
+
+/// How the guest expects a requested key to relate to keys already known to
+/// the host.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyMode {
+    /// The key must not exist yet.
+    New,
+
+    /// The key must already exist.
+    Existing,
+
+    /// Return the existing key, or create it if it doesn't exist yet.
+    Any,
+}
+
+impl KeyMode {
+    /// Decode a mode as written by the guest in a `GetKeyDescriptor`.
+    pub fn from_u32(mode: u32) -> Result<Self> {
+        match mode {
+            GETKEY_MODE_NEW => Ok(KeyMode::New),
+            GETKEY_MODE_EXISTING => Ok(KeyMode::Existing),
+            GETKEY_MODE_ANY => Ok(KeyMode::Any),
+            _ => bail!("Invalid key mode: {mode}"),
+        }
+    }
+}
+
+/// Supplies named keys to the guest.  Install one with
+/// [ProverOpts::with_key_provider](super::ProverOpts::with_key_provider).
+pub trait KeyProvider {
+    /// Return the key called `name`, or an error if it can't be provided in
+    /// the requested `mode`.
+    fn get_key(&mut self, name: &str, mode: KeyMode) -> Result<Key>;
+}
+
+// The provider used when none is configured, so that a guest never silently
+// gets keys the host doesn't know about.
+pub(crate) struct NoKeyProvider;
+
+impl KeyProvider for NoKeyProvider {
+    fn get_key(&mut self, name: &str, _mode: KeyMode) -> Result<Key> {
+        bail!("Guest requested key {name}, but no key provider is configured")
+    }
+}
+
+/// A [KeyProvider] which remembers every key it hands out, generating keys
+/// for names it hasn't seen before.
+///
+/// By default new keys are random.  [KeyStore::deterministic] instead derives
+/// them from a seed, which is useful for tests.
+pub struct KeyStore {
+    keys: HashMap<String, Key>,
+    seed: Option<Vec<u8>>,
+}
+
+impl KeyStore {
+    /// Construct a store which generates random keys.
+    pub fn new() -> Self {
+        KeyStore {
+            keys: HashMap::new(),
+            seed: None,
+        }
+    }
+
+    /// Construct a store which derives each new key from `seed` and the key's
+    /// name, so the same name always yields the same key.
+    pub fn deterministic(seed: &[u8]) -> Self {
+        KeyStore {
+            keys: HashMap::new(),
+            seed: Some(seed.to_vec()),
+        }
+    }
+
+    /// Add a key to the store, replacing any existing key with that name.
+    pub fn insert(&mut self, name: &str, key: Key) {
+        self.keys.insert(name.to_string(), key);
+    }
+
+    fn generate(&self, name: &str) -> Key {
+        let mut key = Key::default();
+        match &self.seed {
+            Some(seed) => {
+                let digest = Sha256::new()
+                    .chain_update(seed)
+                    .chain_update(name)
+                    .finalize();
+                for (word, bytes) in key.iter_mut().zip(digest.chunks_exact(4)) {
+                    *word = u32::from_le_bytes(bytes.try_into().unwrap());
+                }
+            }
+            None => thread_rng().fill(&mut key[..]),
+        }
+        key
+    }
+}
+
+impl Default for KeyStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KeyProvider for KeyStore {
+    fn get_key(&mut self, name: &str, mode: KeyMode) -> Result<Key> {
+        match (mode, self.keys.get(name)) {
+            (KeyMode::New, Some(_)) => bail!("GetKey mode is New and key exists: {name}"),
+            (KeyMode::Existing, None) => {
+                bail!("GetKey mode is Existing and key does not exist: {name}")
+            }
+            (_, Some(key)) => Ok(*key),
+            (_, None) => {
+                let key = self.generate(name);
+                self.insert(name, key);
+                Ok(key)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{KeyMode, KeyProvider, KeyStore};
+
+    #[test]
+    fn deterministic() {
+        let mut a = KeyStore::deterministic(b"seed");
+        let mut b = KeyStore::deterministic(b"seed");
+        let key = a.get_key("x", KeyMode::New).unwrap();
+        assert_eq!(b.get_key("x", KeyMode::Any).unwrap(), key);
+        assert_eq!(a.get_key("x", KeyMode::Existing).unwrap(), key);
+        assert_ne!(a.get_key("y", KeyMode::Any).unwrap(), key);
+        assert!(a.get_key("x", KeyMode::New).is_err());
+        assert!(a.get_key("z", KeyMode::Existing).is_err());
+    }
+}
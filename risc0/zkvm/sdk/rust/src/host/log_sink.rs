@@ -0,0 +1,31 @@
+// Copyright 2022 Risc0, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+
+/// Receives the messages a guest logs with `risc0_zkvm_guest::env::log`.
+/// Install one with
+/// [ProverOpts::with_log_callback](super::ProverOpts::with_log_callback).
+/// Without one, messages go to the `log` crate at the info level.
+pub trait LogCallback {
+    /// Called with each message and the cycle it was logged at.  Returning
+    /// an error aborts execution.
+    fn on_log(&mut self, cycle: u32, msg: &str) -> Result<()>;
+}
+
+impl<F: FnMut(u32, &str) -> Result<()>> LogCallback for F {
+    fn on_log(&mut self, cycle: u32, msg: &str) -> Result<()> {
+        self(cycle, msg)
+    }
+}
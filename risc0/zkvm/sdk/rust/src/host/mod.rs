@@ -17,18 +17,46 @@
 
 #[cxx::bridge]
 mod bridge {}
+mod debug;
+mod error;
 mod exception;
 #[cfg(not(feature = "pure-prove"))]
 mod ffi;
+mod gdb;
+mod key;
+mod log_sink;
+mod profile;
 #[cfg(feature = "pure-prove")]
 mod prove;
+#[cfg(all(feature = "prove", feature = "verify"))]
+mod segment;
+mod sendrecv;
+mod trace;
 
-use std::collections::HashMap;
+use std::{collections::HashMap, io::Write};
 
+pub use debug::{Debugger, GuestState, REGISTER_COUNT};
+pub use error::ProverError;
 pub use exception::Exception;
 #[cfg(not(feature = "pure-prove"))]
 use ffi as prove;
+pub use gdb::GdbStub;
+pub(crate) use key::NoKeyProvider;
+pub use key::{Key, KeyMode, KeyProvider, KeyStore};
+pub use log_sink::LogCallback;
+pub use profile::Profiler;
+#[cfg(feature = "pure-prove")]
+pub use prove::ExecutionSession;
 pub use prove::{MethodId, Prover, Receipt};
+pub use risc0_zkp::ProofParams;
+use risc0_zkvm_platform::io::{SENDRECV_CHANNEL_STDERR, SENDRECV_CHANNEL_STDOUT};
+#[cfg(all(feature = "prove", feature = "verify"))]
+pub use segment::{prove_segments, SegmentedReceipt};
+use sendrecv::SendRecvCallback;
+pub use sendrecv::{ReadHandler, SendRecvHandler, SendRecvReply, WriteHandler};
+pub use trace::{MemoryAccess, RegisterWrite, TraceCallback, TraceEvent, TraceFormat, TraceWriter};
+
+pub use crate::receipt::HashFn;
 
 /// The default digest count when generating a MethodId.
 pub const DEFAULT_METHOD_ID_LIMIT: u32 = 16;
@@ -40,7 +68,23 @@ pub type Result<T> = std::result::Result<T, Exception>;
 pub struct ProverOpts<'a> {
     pub(crate) skip_seal: bool,
 
-    pub(crate) sendrecv_callbacks: HashMap<u32, Box<dyn Fn(u32, &[u8]) -> Vec<u8> + 'a + Sync>>,
+    pub(crate) sendrecv_handlers: HashMap<u32, Box<dyn SendRecvHandler + 'a>>,
+
+    pub(crate) key_provider: Option<Box<dyn KeyProvider + 'a>>,
+
+    pub(crate) trace_callback: Option<Box<dyn TraceCallback + 'a>>,
+
+    pub(crate) debugger: Option<Box<dyn Debugger + 'a>>,
+
+    pub(crate) log_callback: Option<Box<dyn LogCallback + 'a>>,
+
+    pub(crate) max_cycles: Option<usize>,
+
+    pub(crate) rng_seed: Option<u64>,
+
+    pub(crate) hash: HashFn,
+
+    pub(crate) params: ProofParams,
 }
 
 impl<'a> ProverOpts<'a> {
@@ -56,21 +100,141 @@ impl<'a> ProverOpts<'a> {
     /// numbers.  The guest can call these callbacks by invoking
     /// risc0_zkvm_guest::host_sendrecv.
     pub fn with_sendrecv_callback(
-        mut self,
+        self,
         channel_id: u32,
         callback: impl Fn(u32, &[u8]) -> Vec<u8> + 'a + Sync,
     ) -> Self {
-        self.sendrecv_callbacks
-            .insert(channel_id, Box::new(callback));
+        self.with_sendrecv_handler(channel_id, SendRecvCallback(callback))
+    }
+
+    /// Answer the guest's sendrecv requests on the given channel with a
+    /// [SendRecvHandler].  Unlike [ProverOpts::with_sendrecv_callback], the
+    /// handler may keep mutable state, fail, and stream its responses.
+    pub fn with_sendrecv_handler(
+        mut self,
+        channel_id: u32,
+        handler: impl SendRecvHandler + 'a,
+    ) -> Self {
+        self.sendrecv_handlers.insert(channel_id, Box::new(handler));
         self
     }
+
+    /// Send everything the guest writes to standard output, such as with
+    /// risc0_zkvm_guest::println, to `stdout`.  This includes data from
+    /// risc0_zkvm_guest::env::write, which is then no longer available from
+    /// the prover's get_output.
+    pub fn with_stdout(self, stdout: impl Write + 'a) -> Self {
+        self.with_sendrecv_handler(SENDRECV_CHANNEL_STDOUT, WriteHandler::new(stdout))
+    }
+
+    /// Send everything the guest writes to standard error, such as with
+    /// risc0_zkvm_guest::eprintln, to `stderr` instead of the host's
+    /// standard error.
+    pub fn with_stderr(self, stderr: impl Write + 'a) -> Self {
+        self.with_sendrecv_handler(SENDRECV_CHANNEL_STDERR, WriteHandler::new(stderr))
+    }
+
+    /// Supply the keys requested by the guest through
+    /// risc0_zkvm_guest::env::get_key.  If not set, requesting a key fails;
+    /// use a [KeyStore] for keys which are random but remembered.
+    pub fn with_key_provider(self, key_provider: impl KeyProvider + 'a) -> Self {
+        Self {
+            key_provider: Some(Box::new(key_provider)),
+            ..self
+        }
+    }
+
+    /// Add a callback which is invoked for every instruction the guest
+    /// executes.  See [TraceWriter] for saving the trace to a file.
+    pub fn with_trace_callback(self, trace_callback: impl TraceCallback + 'a) -> Self {
+        Self {
+            trace_callback: Some(Box::new(trace_callback)),
+            ..self
+        }
+    }
+
+    /// Run the guest under a [Debugger], which decides when each
+    /// instruction executes.  See [GdbStub] for debugging with GDB.
+    pub fn with_debugger(self, debugger: impl Debugger + 'a) -> Self {
+        Self {
+            debugger: Some(Box::new(debugger)),
+            ..self
+        }
+    }
+
+    /// Send the messages the guest logs with risc0_zkvm_guest::env::log
+    /// to a [LogCallback] instead of the `log` crate.
+    #[cfg(feature = "pure-prove")]
+    pub fn with_log_callback(self, log_callback: impl LogCallback + 'a) -> Self {
+        Self {
+            log_callback: Some(Box::new(log_callback)),
+            ..self
+        }
+    }
+
+    /// Stop the guest with [ProverError::CycleLimitExceeded] if it runs
+    /// for `max_cycles` cycles without halting, or if its trace, which
+    /// doubles in size as it fills up, would grow past `max_cycles` cycles.
+    /// This bounds the time and memory spent on guests which may never
+    /// finish.
+    #[cfg(feature = "pure-prove")]
+    pub fn with_max_cycles(self, max_cycles: usize) -> Self {
+        Self {
+            max_cycles: Some(max_cycles),
+            ..self
+        }
+    }
+
+    /// Draw the random padding which makes the seal zero-knowledge from an
+    /// RNG seeded with `seed`, so that proving the same execution twice
+    /// gives byte-identical seals.
+    ///
+    /// **This is not zero-knowledge**: anyone who knows the seed can strip
+    /// the padding and learn about the guest's private data.  Only use it
+    /// for tests, such as comparing against golden files, and never in
+    /// production.
+    #[cfg(feature = "pure-prove")]
+    pub fn with_rng_seed(self, seed: u64) -> Self {
+        Self {
+            rng_seed: Some(seed),
+            ..self
+        }
+    }
+
+    /// Build the seal's Merkle trees and Fiat-Shamir with `hash` instead of
+    /// SHA-256.  The code Merkle root depends on the hash, so the method ID
+    /// given to the prover, and used to verify the receipt, must be computed
+    /// with [crate::method_id::MethodId::compute_with_limit_and_hash] for the
+    /// same hash.
+    pub fn with_hash(self, hash: HashFn) -> Self {
+        Self { hash, ..self }
+    }
+
+    /// Prove with `params` instead of the defaults, e.g. to make a smaller
+    /// seal by raising [ProofParams::inv_rate] and lowering
+    /// [ProofParams::queries], at the cost of a slower prover.  Receipts are
+    /// only verified if [ProofParams::security_bits] is at least that of the
+    /// defaults.  The method ID depends on [ProofParams::inv_rate], so it
+    /// must be computed with [crate::method_id::MethodId::compute_with_params]
+    /// for the same parameters.
+    pub fn with_proof_params(self, params: ProofParams) -> Self {
+        Self { params, ..self }
+    }
 }
 
 impl<'a> Default for ProverOpts<'a> {
     fn default() -> ProverOpts<'a> {
         ProverOpts {
             skip_seal: false,
-            sendrecv_callbacks: HashMap::new(),
+            sendrecv_handlers: HashMap::new(),
+            key_provider: None,
+            trace_callback: None,
+            debugger: None,
+            log_callback: None,
+            max_cycles: None,
+            rng_seed: None,
+            hash: HashFn::Sha256,
+            params: ProofParams::default(),
         }
     }
 }
@@ -80,15 +244,24 @@ mod test {
     use std::sync::Mutex;
 
     use anyhow::Result;
-    use risc0_zkp::core::sha::Digest;
+    use risc0_zkp::{
+        core::{
+            sha::{Digest, Sha},
+            sha_cpu,
+        },
+        MAX_CYCLES_PO2,
+    };
     use risc0_zkvm_methods::{
-        FAIL_ID, FAIL_PATH, IO_ID, IO_PATH, SENDRECV_ID, SENDRECV_PATH, SHA_ACCEL_ID,
-        SHA_ACCEL_PATH, SHA_ID, SHA_PATH,
+        FAIL_ID, FAIL_PATH, IO_ID, IO_PATH, SEGMENTS_ID, SEGMENTS_PATH, SENDRECV_ID, SENDRECV_PATH,
+        SHA_ACCEL_ID, SHA_ACCEL_PATH, SHA_ID, SHA_PATH,
     };
     use risc0_zkvm_platform::memory::{COMMIT, HEAP};
     use test_log::test;
 
-    use super::{MethodId, Prover, ProverOpts, Receipt};
+    use super::{
+        prove_segments, HashFn, MethodId, ProofParams, Prover, ProverOpts, Receipt,
+        SegmentedReceipt,
+    };
     use crate::serde::{from_slice, to_vec};
 
     #[test]
@@ -178,6 +351,17 @@ mod test {
         Ok(receipt)
     }
 
+    #[test]
+    #[cfg(feature = "pure-prove")]
+    fn rng_seed() {
+        let prove = |opts| run_memio_with_opts(&[(HEAP.start(), 0)], opts).unwrap();
+        let first = prove(ProverOpts::default().with_rng_seed(1));
+        let second = prove(ProverOpts::default().with_rng_seed(1));
+        assert_eq!(first.get_seal().unwrap(), second.get_seal().unwrap());
+        let other = prove(ProverOpts::default().with_rng_seed(2));
+        assert_ne!(first.get_seal().unwrap(), other.get_seal().unwrap());
+    }
+
     #[test]
     fn receipt_serde() {
         let receipt = run_memio_with_opts(&[(HEAP.start(), 0)], ProverOpts::default()).unwrap();
@@ -202,6 +386,98 @@ mod test {
         assert!(de.verify(IO_ID).is_err());
     }
 
+    #[test]
+    #[cfg(feature = "pure-prove")]
+    fn receipt_journal_mismatch() {
+        use risc0_zkp::verify::VerificationError;
+
+        let mut prover = Prover::new(&std::fs::read(SHA_PATH).unwrap(), SHA_ID).unwrap();
+        prover.add_input_u32_slice(&to_vec(&"abc").unwrap());
+        let mut receipt = prover.run().unwrap();
+        receipt.journal[0] ^= 1;
+        let err = receipt.verify(SHA_ID).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<VerificationError>(),
+            Some(VerificationError::JournalMismatch)
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "pure-prove")]
+    fn proof_params() {
+        let elf = std::fs::read(SHA_PATH).unwrap();
+        let prove = |params| {
+            let method_id = MethodId::compute_with_params(&elf, 8, HashFn::Sha256, &params)
+                .unwrap()
+                .as_slice()
+                .unwrap()
+                .to_vec();
+            let opts = ProverOpts::default().with_proof_params(params);
+            let mut prover = Prover::new_with_opts(&elf, &method_id, opts).unwrap();
+            prover.add_input_u32_slice(&to_vec(&"abc").unwrap());
+            prover.run()
+        };
+
+        // Trade queries for a higher rate, keeping the same security.
+        let params = ProofParams {
+            queries: 25,
+            inv_rate: 16,
+            ..ProofParams::default()
+        };
+        let receipt = prove(params).unwrap();
+        assert_eq!(ProofParams::from_seal(&receipt.seal).unwrap(), params);
+
+        // Seals with less security than the defaults are rejected.
+        let weak = ProofParams {
+            queries: 10,
+            ..ProofParams::default()
+        };
+        assert!(prove(weak).is_err());
+    }
+
+    #[test]
+    fn receipt_envelope() {
+        use crate::receipt::{RECEIPT_MAGIC, RECEIPT_VERSION};
+
+        let receipt = run_memio(&[(HEAP.start(), 0)]).unwrap();
+        let ser: Vec<u32> = crate::serde::to_vec(&receipt).unwrap();
+        assert_eq!(ser[0], RECEIPT_MAGIC);
+        assert_eq!(ser[1], RECEIPT_VERSION);
+
+        // Fields appended to the header by later versions are skipped.
+        let header_len = ser[2] as usize;
+        let mut extended = ser.clone();
+        extended[2] += 1;
+        extended.insert(3 + header_len, 0xdeadbeef);
+        let de: Receipt = crate::serde::from_slice(&extended).unwrap();
+        assert_eq!(de.get_journal().unwrap(), receipt.get_journal().unwrap());
+
+        // Version 1 headers had no hash, and always used SHA-256.  Seals
+        // before version 3 had no parameters, and always used the defaults.
+        let current: crate::receipt::Receipt = crate::serde::from_slice(&ser).unwrap();
+        assert_eq!(current.header.hash, HashFn::Sha256);
+        let mut v1 = ser.clone();
+        v1[1] = 1;
+        v1[2] -= 1;
+        v1.remove(2 + header_len);
+        let seal_len = current.seal.len() - ProofParams::SEAL_WORDS;
+        v1.truncate(v1.len() - ProofParams::SEAL_WORDS);
+        let seal_len_index = v1.len() - seal_len - 1;
+        v1[seal_len_index] = seal_len as u32;
+        let de: crate::receipt::Receipt = crate::serde::from_slice(&v1).unwrap();
+        assert_eq!(de.header.hash, HashFn::Sha256);
+        assert_eq!(de.journal, receipt.get_journal_vec().unwrap());
+        assert_eq!(de.seal, current.seal);
+
+        let mut bad_magic = ser.clone();
+        bad_magic[0] = 0;
+        assert!(crate::serde::from_slice::<Receipt>(&bad_magic).is_err());
+
+        let mut newer = ser.clone();
+        newer[1] = RECEIPT_VERSION + 1;
+        assert!(crate::serde::from_slice::<Receipt>(&newer).is_err());
+    }
+
     #[test]
     fn fail() {
         // Check that a compliant host will fault.
@@ -260,6 +536,562 @@ mod test {
         prover.run().unwrap();
     }
 
+    #[test]
+    fn sendrecv_handler() {
+        use super::{SendRecvHandler, SendRecvReply};
+
+        // Replies with a growing prefix of "Hello", a byte at a time.
+        struct Echo<'a> {
+            requests: &'a mut Vec<Vec<u8>>,
+        }
+
+        impl<'a> SendRecvHandler for Echo<'a> {
+            fn on_sendrecv(
+                &mut self,
+                channel: u32,
+                request: &[u8],
+                reply: &mut SendRecvReply,
+            ) -> Result<()> {
+                assert_eq!(channel, 5);
+                self.requests.push(request.into());
+                for byte in &b"Hello"[..self.requests.len()] {
+                    reply.write(&[*byte])?;
+                }
+                Ok(())
+            }
+        }
+
+        let mut requests = Vec::new();
+        let opts = ProverOpts::default()
+            .with_skip_seal(true)
+            .with_sendrecv_handler(
+                5,
+                Echo {
+                    requests: &mut requests,
+                },
+            );
+        let mut prover =
+            Prover::new_with_opts(&std::fs::read(SENDRECV_PATH).unwrap(), SENDRECV_ID, opts)
+                .unwrap();
+        prover.add_input_u32_slice(&[5, 5]);
+        prover.run().unwrap();
+        drop(prover);
+        let expected: Vec<Vec<u8>> = vec![
+            "".into(),
+            "H".into(),
+            "He".into(),
+            "Hel".into(),
+            "Hell".into(),
+        ];
+        assert_eq!(requests, expected);
+
+        // Errors from the handler abort execution.
+        let opts = ProverOpts::default()
+            .with_skip_seal(true)
+            .with_sendrecv_handler(5, |_channel_id, _buf: &[u8]| -> Result<Vec<u8>> {
+                anyhow::bail!("No more data")
+            });
+        let mut prover =
+            Prover::new_with_opts(&std::fs::read(SENDRECV_PATH).unwrap(), SENDRECV_ID, opts)
+                .unwrap();
+        prover.add_input_u32_slice(&[5, 1]);
+        assert!(prover.run().is_err());
+    }
+
+    #[test]
+    fn print() {
+        use risc0_zkvm_methods::{PRINT_ID, PRINT_PATH};
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let opts = ProverOpts::default()
+            .with_skip_seal(true)
+            .with_stdout(&mut stdout)
+            .with_stderr(&mut stderr);
+        let mut prover =
+            Prover::new_with_opts(&std::fs::read(PRINT_PATH).unwrap(), PRINT_ID, opts).unwrap();
+        prover.run().unwrap();
+        drop(prover);
+        let expected = format!("Hello, world!\n12\n{:=<1000}\n", "");
+        assert_eq!(String::from_utf8(stdout).unwrap(), expected);
+        assert_eq!(String::from_utf8(stderr).unwrap(), "error: deadbeef\n");
+    }
+
+    #[test]
+    #[cfg(feature = "pure-prove")]
+    fn log_callback() {
+        use risc0_zkvm_methods::{PRINT_ID, PRINT_PATH};
+
+        let mut logs = Vec::new();
+        let opts = ProverOpts::default()
+            .with_skip_seal(true)
+            .with_stdout(std::io::sink())
+            .with_stderr(std::io::sink())
+            .with_log_callback(|cycle, msg: &str| -> Result<()> {
+                logs.push((cycle, msg.to_string()));
+                Ok(())
+            });
+        let mut prover =
+            Prover::new_with_opts(&std::fs::read(PRINT_PATH).unwrap(), PRINT_ID, opts).unwrap();
+        prover.run().unwrap();
+        drop(prover);
+        assert_eq!(logs.len(), 1);
+        assert!(logs[0].0 > 0);
+        assert_eq!(logs[0].1, "Hello from the log");
+    }
+
+    #[test]
+    fn sendrecv_past_input_region() {
+        // Send the guest several times more data than fits in INPUT, which
+        // it has to receive in windows allocated from its heap.
+        const REPLY_LEN: usize = 32 * 1024;
+        const COUNT: usize = 100;
+        let mut requests = 0;
+        let opts = ProverOpts::default()
+            .with_skip_seal(true)
+            .with_sendrecv_handler(5, |_channel_id, buf: &[u8]| -> Result<Vec<u8>> {
+                // The guest echoes back the previous reply.
+                let expected_len = if requests == 0 { 0 } else { REPLY_LEN };
+                assert_eq!(buf.len(), expected_len);
+                assert!(buf
+                    .iter()
+                    .all(|&byte| byte == (requests as u8).wrapping_sub(1)));
+                requests += 1;
+                Ok(vec![requests as u8 - 1; REPLY_LEN])
+            });
+        let mut prover =
+            Prover::new_with_opts(&std::fs::read(SENDRECV_PATH).unwrap(), SENDRECV_ID, opts)
+                .unwrap();
+        prover.add_input_u32_slice(&[5, COUNT as u32]);
+        prover.run().unwrap();
+        drop(prover);
+        assert_eq!(requests, COUNT);
+    }
+
+    #[test]
+    fn sendrecv_large_response() {
+        // Fill up INPUT so that the guest moves to a heap window, then send
+        // responses larger than INPUT_HEADROOM, the rest of the window, and
+        // a whole window, which each need a window of their own.
+        let mut sizes = vec![32 * 1024; 40];
+        sizes.extend([100 * 1024, 300 * 1024, 1024 * 1024, 16]);
+        let mut requests = 0;
+        let opts = ProverOpts::default()
+            .with_skip_seal(true)
+            .with_sendrecv_handler(5, |_channel_id, buf: &[u8]| -> Result<Vec<u8>> {
+                // The guest echoes back the previous reply.
+                let expected_len = if requests == 0 {
+                    0
+                } else {
+                    sizes[requests - 1]
+                };
+                assert_eq!(buf.len(), expected_len);
+                assert!(buf
+                    .iter()
+                    .all(|&byte| byte == (requests as u8).wrapping_sub(1)));
+                requests += 1;
+                Ok(vec![requests as u8 - 1; sizes[requests - 1]])
+            });
+        let mut prover =
+            Prover::new_with_opts(&std::fs::read(SENDRECV_PATH).unwrap(), SENDRECV_ID, opts)
+                .unwrap();
+        prover.add_input_u32_slice(&[5, sizes.len() as u32]);
+        prover.run().unwrap();
+        drop(prover);
+        assert_eq!(requests, sizes.len());
+    }
+
+    #[test]
+    #[cfg(feature = "pure-prove")]
+    fn sendrecv_past_heap() {
+        use super::ProverError;
+
+        // Each response gets a window of its own on the heap, which is never
+        // freed, so the heap runs out long before the guest stops asking.
+        const REPLY_LEN: usize = 1024 * 1024;
+        let opts = ProverOpts::default()
+            .with_skip_seal(true)
+            .with_sendrecv_handler(5, |_channel_id, _buf: &[u8]| -> Result<Vec<u8>> {
+                Ok(vec![0; REPLY_LEN])
+            });
+        let mut prover =
+            Prover::new_with_opts(&std::fs::read(SENDRECV_PATH).unwrap(), SENDRECV_ID, opts)
+                .unwrap();
+        let count = (HEAP.len_bytes() / REPLY_LEN) as u32 + 2;
+        prover.add_input_u32_slice(&[5, count]);
+        let err = prover.run().err().unwrap();
+        match err.downcast().unwrap() {
+            ProverError::GuestOutOfMemory {
+                requested,
+                used,
+                limit,
+            } => {
+                assert_eq!(requested, REPLY_LEN as u32);
+                assert!(used + requested > limit);
+                assert_eq!(limit, HEAP.len_bytes() as u32);
+            }
+            err => panic!("Unexpected error: {err}"),
+        }
+    }
+
+    fn run_segments(total: u32, per_segment: u32) -> SegmentedReceipt {
+        prove_segments(
+            &std::fs::read(SEGMENTS_PATH).unwrap(),
+            SEGMENTS_ID,
+            &to_vec(&(total, per_segment)).unwrap(),
+            ProverOpts::default,
+        )
+        .unwrap()
+    }
+
+    // The result of the segments guest's loop.
+    fn segments_result(total: u32) -> u32 {
+        (0..total).fold(0u32, |acc, i| acc.wrapping_mul(31).wrapping_add(i))
+    }
+
+    #[test]
+    fn segments() {
+        let mut receipt = run_segments(1000, 400);
+        assert_eq!(receipt.segments.len(), 3);
+        receipt.verify(SEGMENTS_ID).unwrap();
+        let input = to_vec(&(1000u32, 400u32)).unwrap();
+        assert_eq!(
+            receipt.get_input_digest().unwrap(),
+            sha_cpu::Impl {}.hash_words(&input)
+        );
+
+        // Each segment commits its cycle count, and the last the result.
+        let journal = receipt.get_journal_vec().unwrap();
+        assert_eq!(journal.len(), 4);
+        assert_eq!(journal[3], segments_result(1000));
+
+        // A segment given other input is caught, even though it resumes
+        // from the same state.
+        let mut other = run_segments(1200, 400);
+        std::mem::swap(&mut receipt.segments[1], &mut other.segments[1]);
+        assert!(receipt.verify(SEGMENTS_ID).is_err());
+        std::mem::swap(&mut receipt.segments[1], &mut other.segments[1]);
+
+        // Dropping or reordering segments breaks the chain.
+        let last = receipt.segments.pop().unwrap();
+        assert!(receipt.verify(SEGMENTS_ID).is_err());
+        receipt.segments.insert(0, last);
+        assert!(receipt.verify(SEGMENTS_ID).is_err());
+    }
+
+    #[test]
+    #[ignore = "proves more cycles than fit in a single seal, which is slow"]
+    fn segments_past_max_cycles() {
+        const TOTAL: u32 = 1 << 23;
+        let receipt = run_segments(TOTAL, 1 << 19);
+        receipt.verify(SEGMENTS_ID).unwrap();
+
+        let journal = receipt.get_journal_vec().unwrap();
+        let (cycles, result) = journal.split_at(journal.len() - 1);
+        let cycles: u64 = cycles.iter().map(|&cycles| cycles as u64).sum();
+        assert!(cycles > 1 << MAX_CYCLES_PO2);
+        assert_eq!(result, &[segments_result(TOTAL)]);
+    }
+
+    #[test]
+    fn channel_io() {
+        use risc0_zkvm_methods::{CHANNEL_IO_ID, CHANNEL_IO_PATH};
+
+        use super::{ReadHandler, WriteHandler};
+
+        let input: Vec<u8> = (0..10000).map(|i| (i % 251) as u8).collect();
+        let mut output = Vec::new();
+        let opts = ProverOpts::default()
+            .with_skip_seal(true)
+            .with_sendrecv_handler(6, ReadHandler::new(input.as_slice()))
+            .with_sendrecv_handler(7, WriteHandler::new(&mut output));
+        let mut prover = Prover::new_with_opts(
+            &std::fs::read(CHANNEL_IO_PATH).unwrap(),
+            CHANNEL_IO_ID,
+            opts,
+        )
+        .unwrap();
+        prover.add_input_u32_slice(&[6, 7]);
+        prover.run().unwrap();
+        drop(prover);
+        assert!(output.iter().eq(input.iter().rev()));
+    }
+
+    #[test]
+    fn std_io() {
+        use risc0_zkvm_methods::{STD_IO_ID, STD_IO_PATH};
+
+        use super::{ReadHandler, WriteHandler};
+
+        let input = b"hello\nworld\n";
+        let mut output = Vec::new();
+        let opts = ProverOpts::default()
+            .with_skip_seal(true)
+            .with_sendrecv_handler(6, ReadHandler::new(&input[..]))
+            .with_sendrecv_handler(7, WriteHandler::new(&mut output));
+        let mut prover =
+            Prover::new_with_opts(&std::fs::read(STD_IO_PATH).unwrap(), STD_IO_ID, opts).unwrap();
+        prover.add_input_u32_slice(&[6, 7]);
+        prover.run().unwrap();
+        drop(prover);
+        assert_eq!(output, b"HELLO\nWORLD\n");
+    }
+
+    #[test]
+    #[cfg(feature = "pure-prove")]
+    fn out_of_memory() {
+        use risc0_zkvm_methods::{OOM_ID, OOM_PATH};
+
+        use super::ProverError;
+
+        let opts = ProverOpts::default().with_skip_seal(true);
+        let mut prover =
+            Prover::new_with_opts(&std::fs::read(OOM_PATH).unwrap(), OOM_ID, opts).unwrap();
+        let len = HEAP.len_bytes() as u32 + 1;
+        prover.add_input_u32_slice(&to_vec(&len).unwrap());
+        let err = prover.run().err().unwrap();
+        match err.downcast().unwrap() {
+            ProverError::GuestOutOfMemory {
+                requested,
+                used,
+                limit,
+            } => {
+                assert_eq!(requested, len);
+                assert!(used < limit);
+                assert_eq!(limit, HEAP.len_bytes() as u32);
+            }
+            err => panic!("Unexpected error: {err}"),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "pure-prove")]
+    fn prover_error() {
+        use super::ProverError;
+
+        let error_of =
+            |result: Result<Receipt>| -> ProverError { result.err().unwrap().downcast().unwrap() };
+
+        let mut prover = Prover::new(&std::fs::read(FAIL_PATH).unwrap(), FAIL_ID).unwrap();
+        match error_of(prover.run()) {
+            ProverError::GuestPanic(msg) => assert!(msg.contains("Failure"), "{msg}"),
+            err => panic!("Unexpected error: {err}"),
+        }
+
+        assert_eq!(
+            error_of(run_memio(&[(HEAP.start() + 1, 1)])),
+            ProverError::GuestFault
+        );
+        assert_eq!(
+            error_of(run_memio(&[(COMMIT.start(), 1), (COMMIT.start(), 2)])),
+            ProverError::GuestFault
+        );
+
+        let opts = ProverOpts::default().with_skip_seal(true);
+        let mut prover =
+            Prover::new_with_opts(&std::fs::read(SENDRECV_PATH).unwrap(), SENDRECV_ID, opts)
+                .unwrap();
+        prover.add_input_u32_slice(&[7, 1]);
+        assert_eq!(error_of(prover.run()), ProverError::UnknownChannel(7));
+    }
+
+    #[test]
+    #[cfg(feature = "pure-prove")]
+    fn max_cycles() {
+        use super::ProverError;
+
+        let run = |max_cycles| {
+            let opts = ProverOpts::default()
+                .with_skip_seal(true)
+                .with_max_cycles(max_cycles);
+            let mut prover =
+                Prover::new_with_opts(&std::fs::read(SHA_PATH).unwrap(), SHA_ID, opts).unwrap();
+            // Hash enough data that the trace has to grow while running.
+            prover.add_input_u32_slice(&to_vec(&"abc".repeat(4096)).unwrap());
+            prover.execute()
+        };
+
+        let exceeded = |max_cycles| {
+            let err = run(max_cycles).err().unwrap();
+            match err.downcast().unwrap() {
+                ProverError::CycleLimitExceeded { limit, cycle } => {
+                    assert_eq!(limit, max_cycles);
+                    assert!(cycle <= limit);
+                }
+                err => panic!("Unexpected error: {err}"),
+            }
+        };
+
+        let session = run(usize::MAX).unwrap();
+        assert!(run(1 << session.po2).is_ok());
+        // The guest halts within the limit, but its trace would not fit.
+        exceeded((1 << session.po2) - 1);
+        exceeded(session.cycles / 2);
+    }
+
+    #[test]
+    fn get_key() {
+        use risc0_zkvm_methods::{GET_KEY_ID, GET_KEY_PATH};
+
+        use super::{KeyMode, KeyProvider, KeyStore};
+
+        let opts = ProverOpts::default()
+            .with_skip_seal(true)
+            .with_key_provider(KeyStore::deterministic(b"get_key"));
+        let mut prover =
+            Prover::new_with_opts(&std::fs::read(GET_KEY_PATH).unwrap(), GET_KEY_ID, opts).unwrap();
+        let receipt = prover.run().unwrap();
+        let journal = receipt.get_journal_vec().unwrap();
+
+        let mut expected = KeyStore::deterministic(b"get_key");
+        let a = expected.get_key("a", KeyMode::New).unwrap();
+        let b = expected.get_key("b", KeyMode::New).unwrap();
+        assert_ne!(a, b);
+        assert_eq!(journal, [a, b, a].concat());
+
+        // Without a key provider, asking for a key fails.
+        let opts = ProverOpts::default().with_skip_seal(true);
+        let mut prover =
+            Prover::new_with_opts(&std::fs::read(GET_KEY_PATH).unwrap(), GET_KEY_ID, opts).unwrap();
+        let err = prover.run().err().unwrap();
+        assert!(
+            err.to_string().contains("no key provider is configured"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "pure-prove")]
+    fn trace() {
+        use super::{MemoryAccess, TraceEvent};
+
+        let mut events: Vec<TraceEvent> = Vec::new();
+        {
+            let opts = ProverOpts::default()
+                .with_skip_seal(true)
+                .with_trace_callback(|event: &TraceEvent| {
+                    events.push(event.clone());
+                    Ok(())
+                });
+            run_memio_with_opts(&[(HEAP.start(), 1)], opts).unwrap();
+        }
+        let store = MemoryAccess {
+            addr: HEAP.start() as u32,
+            value: 1,
+            is_write: true,
+        };
+        assert!(events
+            .iter()
+            .any(|event| event.mnemonic == "SW" && event.memory.contains(&store)));
+        assert!(events.iter().all(|event| event.pc % 4 == 0));
+    }
+
+    #[test]
+    #[cfg(feature = "pure-prove")]
+    fn trace_load_then_alu() {
+        use super::{RegisterWrite, TraceEvent};
+
+        let mut events: Vec<TraceEvent> = Vec::new();
+        {
+            let opts = ProverOpts::default()
+                .with_skip_seal(true)
+                .with_trace_callback(|event: &TraceEvent| {
+                    events.push(event.clone());
+                    Ok(())
+                });
+            run_memio_with_opts(&[(HEAP.start(), 7), (HEAP.start(), 0)], opts).unwrap();
+        }
+        let rd = |insn: u32| (insn >> 7) & 0x1f;
+        let rs1 = |insn: u32| (insn >> 15) & 0x1f;
+        // Find a load whose result is used straight away by an ALU
+        // instruction.
+        let (load, alu) = events
+            .windows(2)
+            .map(|pair| (&pair[0], &pair[1]))
+            .find(|(load, alu)| {
+                load.mnemonic == "LW"
+                    && rd(load.insn) != 0
+                    && matches!(
+                        alu.mnemonic.as_str(),
+                        "ADD" | "ADDI" | "AND" | "ANDI" | "OR"
+                    )
+                    && rs1(alu.insn) == rd(load.insn)
+                    && rd(alu.insn) != 0
+            })
+            .unwrap();
+        // The load writes the word it read to its register.
+        let read = load.memory.iter().find(|access| !access.is_write).unwrap();
+        assert_eq!(
+            load.reg_write,
+            Some(RegisterWrite {
+                reg: rd(load.insn),
+                value: read.value,
+            })
+        );
+        // The ALU instruction touches no memory, and writes its own result.
+        assert!(alu.memory.is_empty());
+        assert_eq!(alu.reg_write.unwrap().reg, rd(alu.insn));
+        if alu.mnemonic == "ADDI" {
+            let imm = (alu.insn as i32 >> 20) as u32;
+            assert_eq!(alu.reg_write.unwrap().value, read.value.wrapping_add(imm));
+        }
+        assert!(load.cycle < alu.cycle);
+    }
+
+    #[test]
+    #[cfg(feature = "pure-prove")]
+    fn profile() {
+        use super::{Profiler, TraceCallback, TraceEvent};
+
+        // Pass everything on to the profiler, noting when the guest starts.
+        struct Recorder<'a> {
+            profiler: &'a mut Profiler,
+            start: &'a mut Option<u32>,
+        }
+
+        impl TraceCallback for Recorder<'_> {
+            fn on_instruction(&mut self, event: &TraceEvent) -> Result<()> {
+                self.start.get_or_insert(event.cycle);
+                self.profiler.on_instruction(event)
+            }
+
+            fn on_exit(&mut self, cycle: u32) -> Result<()> {
+                self.profiler.on_exit(cycle)
+            }
+        }
+
+        let elf = std::fs::read(SHA_PATH).unwrap();
+        let mut profiler = Profiler::new(&elf).unwrap();
+        let mut start = None;
+        let session = {
+            let recorder = Recorder {
+                profiler: &mut profiler,
+                start: &mut start,
+            };
+            let opts = ProverOpts::default()
+                .with_skip_seal(true)
+                .with_trace_callback(recorder);
+            let mut prover = Prover::new_with_opts(&elf, SHA_ID, opts).unwrap();
+            prover.add_input_u32_slice(&to_vec(&"abc").unwrap());
+            prover.execute().unwrap()
+        };
+        let start = start.unwrap() as usize;
+        let mut out = Vec::new();
+        profiler.write_collapsed(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        // Every cycle from the first instruction until the guest halts is
+        // counted, including those of the last instruction.
+        let total: usize = out
+            .lines()
+            .map(|line| line.rsplit(' ').next().unwrap().parse::<usize>().unwrap())
+            .sum();
+        assert_eq!(total, session.cycles - start);
+        // `_start` is assembly without a symbol size, so it shows up as unknown.
+        assert!(out
+            .lines()
+            .any(|line| line.starts_with("[unknown];__start")));
+    }
+
     #[test]
     fn sha_accel() {
         let opts = ProverOpts::default().with_skip_seal(true);
@@ -301,6 +1133,30 @@ mod test {
         prover.run().unwrap();
     }
 
+    #[test]
+    #[cfg(feature = "pure-prove")]
+    fn execute_then_prove() {
+        use risc0_zkp::hal::cpu::CpuHal;
+
+        use super::ExecutionSession;
+        use crate::{prove::CpuEvalCheck, CIRCUIT};
+
+        let mut prover = Prover::new(&std::fs::read(IO_PATH).unwrap(), IO_ID).unwrap();
+        prover.add_input_u32_slice(&[1, HEAP.start() as u32, 1]);
+        let session = prover.execute().unwrap();
+        assert!(session.cycles > 0);
+        assert!(session.cycles < 1 << session.po2);
+
+        // Sessions can be saved and proven later.
+        let ser: Vec<u32> = to_vec(&session).unwrap();
+        let session: ExecutionSession = from_slice(&ser).unwrap();
+
+        let hal = CpuHal::new();
+        let eval = CpuEvalCheck::new(&CIRCUIT);
+        let receipt = session.prove(&hal, &eval).unwrap();
+        receipt.verify(IO_ID).unwrap();
+    }
+
     #[test]
     #[cfg(feature = "pure-prove")]
     fn recursion() {
@@ -0,0 +1,200 @@
+// Copyright 2022 Risc0, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::BTreeMap, io::Write};
+
+use anyhow::{anyhow, Result};
+use xmas_elf::{
+    sections::SectionData,
+    symbol_table::{Entry, Type},
+    ElfFile,
+};
+
+use super::{TraceCallback, TraceEvent};
+
+// The return address register, ra.
+const REG_RA: u32 = 1;
+
+struct Symbol {
+    start: u32,
+    end: u32,
+    name: String,
+}
+
+/// Attributes the cycles spent by a guest to the functions it was executing,
+/// using the guest's symbol table.
+///
+/// Feed it every instruction by installing it as a [TraceCallback], then
+/// write out the result with [Profiler::write_collapsed], which produces the
+/// "collapsed stack" format understood by flamegraph tools and pprof.
+pub struct Profiler {
+    symbols: Vec<Symbol>,
+    // The functions currently on the guest's call stack, outermost first.
+    stack: Vec<usize>,
+    // The previous instruction, whose cycles are counted once we know
+    // when the next one starts, or when the guest halts.
+    prev: Option<TraceEvent>,
+    samples: BTreeMap<Vec<usize>, u64>,
+}
+
+impl Profiler {
+    /// Construct a profiler for the guest in the given ELF file.
+    pub fn new(elf: &[u8]) -> Result<Self> {
+        let elf = ElfFile::new(elf).map_err(|err| anyhow!(err))?;
+        let symtab = elf
+            .find_section_by_name(".symtab")
+            .ok_or_else(|| anyhow!("ELF has no symbol table"))?;
+        let entries = match symtab.get_data(&elf).map_err(|err| anyhow!(err))? {
+            SectionData::SymbolTable32(entries) => entries,
+            _ => return Err(anyhow!("Not a 32-bit ELF")),
+        };
+        let mut symbols = Vec::new();
+        for entry in entries {
+            if entry.get_type() != Ok(Type::Func) || entry.size() == 0 {
+                continue;
+            }
+            let name = entry.get_name(&elf).map_err(|err| anyhow!(err))?;
+            symbols.push(Symbol {
+                start: entry.value() as u32,
+                end: (entry.value() + entry.size()) as u32,
+                name: format!("{:#}", rustc_demangle::demangle(name)),
+            });
+        }
+        Ok(Self::from_symbols(symbols))
+    }
+
+    fn from_symbols(mut symbols: Vec<Symbol>) -> Self {
+        symbols.sort_by_key(|symbol| symbol.start);
+        Profiler {
+            symbols,
+            stack: Vec::new(),
+            prev: None,
+            samples: BTreeMap::new(),
+        }
+    }
+
+    // Returns the index of the function containing `pc`, or `symbols.len()`
+    // if there isn't one.
+    fn lookup(&self, pc: u32) -> usize {
+        let idx = self.symbols.partition_point(|symbol| symbol.start <= pc);
+        match idx.checked_sub(1) {
+            Some(idx) if pc < self.symbols[idx].end => idx,
+            _ => self.symbols.len(),
+        }
+    }
+
+    fn name(&self, idx: usize) -> &str {
+        match self.symbols.get(idx) {
+            Some(symbol) => &symbol.name,
+            None => "[unknown]",
+        }
+    }
+
+    /// Write the number of cycles spent in each call stack, one stack per
+    /// line, with function names separated by semicolons.
+    pub fn write_collapsed<W: Write>(&self, mut writer: W) -> Result<()> {
+        for (stack, cycles) in self.samples.iter() {
+            let names: Vec<&str> = stack.iter().map(|idx| self.name(*idx)).collect();
+            writeln!(writer, "{} {}", names.join(";"), cycles)?;
+        }
+        Ok(())
+    }
+}
+
+impl TraceCallback for Profiler {
+    fn on_instruction(&mut self, event: &TraceEvent) -> Result<()> {
+        let func = self.lookup(event.pc);
+        if let Some(prev) = self.prev.take() {
+            *self.samples.entry(self.stack.clone()).or_default() +=
+                (event.cycle - prev.cycle) as u64;
+
+            let rd = (prev.insn >> 7) & 0x1f;
+            let rs1 = (prev.insn >> 15) & 0x1f;
+            let is_jump = prev.mnemonic == "JAL" || prev.mnemonic == "JALR";
+            if is_jump && rd == REG_RA {
+                self.stack.push(func);
+            } else if prev.mnemonic == "JALR" && rd == 0 && rs1 == REG_RA {
+                self.stack.pop();
+            }
+        }
+        // Anything else that leaves a function, like a tail call, replaces
+        // the current frame.
+        match self.stack.last_mut() {
+            Some(top) => *top = func,
+            None => self.stack.push(func),
+        }
+        self.prev = Some(event.clone());
+        Ok(())
+    }
+
+    fn on_exit(&mut self, cycle: u32) -> Result<()> {
+        if let Some(prev) = self.prev.take() {
+            *self.samples.entry(self.stack.clone()).or_default() += (cycle - prev.cycle) as u64;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Profiler, Symbol};
+    use crate::host::{TraceCallback, TraceEvent};
+
+    // jal ra, <offset>
+    const CALL: u32 = 0x000000ef;
+    // jalr zero, 0(ra)
+    const RET: u32 = 0x00008067;
+    // addi zero, zero, 0
+    const NOP: u32 = 0x00000013;
+
+    fn event(cycle: u32, pc: u32, insn: u32, mnemonic: &str) -> TraceEvent {
+        TraceEvent {
+            cycle,
+            pc,
+            insn,
+            mnemonic: mnemonic.into(),
+            reg_write: None,
+            memory: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn collapsed() {
+        let mut profiler = Profiler::from_symbols(vec![
+            Symbol {
+                start: 0x100,
+                end: 0x200,
+                name: "main".into(),
+            },
+            Symbol {
+                start: 0x200,
+                end: 0x300,
+                name: "foo".into(),
+            },
+        ]);
+        for event in [
+            event(0, 0x100, NOP, "ADDI"),
+            event(3, 0x104, CALL, "JAL"),
+            event(6, 0x200, NOP, "ADDI"),
+            event(10, 0x204, RET, "JALR"),
+            event(13, 0x108, NOP, "ADDI"),
+        ] {
+            profiler.on_instruction(&event).unwrap();
+        }
+        profiler.on_exit(15).unwrap();
+        let mut out = Vec::new();
+        profiler.write_collapsed(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "main 8\nmain;foo 7\n");
+    }
+}
@@ -14,5 +14,5 @@
 
 pub use crate::host::ProverOpts;
 pub use crate::method_id::MethodId;
-pub use crate::prove::Prover;
+pub use crate::prove::{ExecutionSession, Prover};
 pub use crate::receipt::Receipt;
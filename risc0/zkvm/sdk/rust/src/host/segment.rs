@@ -0,0 +1,220 @@
+// Copyright 2022 Risc0, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{cell::RefCell, rc::Rc};
+
+use anyhow::{anyhow, bail, Result};
+use risc0_zkp::core::sha::{Digest, DIGEST_WORDS};
+use risc0_zkvm_platform::io::{SENDRECV_CHANNEL_SEGMENT_RESUME, SENDRECV_CHANNEL_SEGMENT_SUSPEND};
+
+use super::{Prover, ProverOpts, Receipt};
+
+/// The receipts of a computation which the guest split into segments with
+/// `risc0_zkvm_guest::env::suspend`, each proven on its own.
+///
+/// Each segment's journal ends with the digests of its initial input, the
+/// state it resumed from and the state it saved.  The first segment resumes
+/// from, and the last saves, the default digest.
+pub struct SegmentedReceipt {
+    /// The receipt of each segment, in the order they ran.
+    pub segments: Vec<Receipt>,
+}
+
+impl SegmentedReceipt {
+    /// Verify each segment against `method_id`, and that they chain: they
+    /// all have the same initial input, the first starts afresh, each
+    /// resumes from the state saved by the one before it, and only the last
+    /// one finishes.
+    pub fn verify(&self, method_id: &[u8]) -> Result<()> {
+        let mut journals = Vec::with_capacity(self.segments.len());
+        for segment in &self.segments {
+            segment.verify(method_id)?;
+            journals.push(segment.get_journal_vec()?);
+        }
+        check_chain(&journals)
+    }
+
+    /// Returns the digest of the initial input given to every segment, which
+    /// is the SHA-256 of its words.  [SegmentedReceipt::verify] checks that
+    /// all the segments agree on it.
+    pub fn get_input_digest(&self) -> Result<Digest> {
+        let segment = self
+            .segments
+            .first()
+            .ok_or_else(|| anyhow!("No segments"))?;
+        let journal = segment.get_journal_vec()?;
+        let offset = state_offset(&journal)?;
+        Ok(Digest::from_slice(&journal[offset..offset + DIGEST_WORDS]))
+    }
+
+    /// Returns the journals of the segments concatenated in order, without
+    /// their state digests.
+    pub fn get_journal_vec(&self) -> Result<Vec<u32>> {
+        let mut journal = Vec::new();
+        for segment in &self.segments {
+            let segment = segment.get_journal_vec()?;
+            journal.extend_from_slice(&segment[..state_offset(&segment)?]);
+        }
+        Ok(journal)
+    }
+}
+
+/// Run the guest in `elf` a segment at a time, proving each segment with a
+/// fresh [Prover] made with options from `opts` and given `input`, until the
+/// guest finishes.
+///
+/// The guest decides where each segment ends, by calling
+/// `risc0_zkvm_guest::env::suspend` with the state to resume from; the
+/// executor does not split executions at a cycle budget.  A guest which
+/// doesn't use `resume` and `suspend` fails to verify as a
+/// [SegmentedReceipt], since its journal holds no state digests.
+pub fn prove_segments<'a>(
+    elf: &[u8],
+    method_id: &[u8],
+    input: &[u32],
+    mut opts: impl FnMut() -> ProverOpts<'a>,
+) -> Result<SegmentedReceipt> {
+    let mut segments = Vec::new();
+    let mut state: Option<Vec<u32>> = None;
+    loop {
+        let mut resume = Vec::new();
+        if let Some(state) = &state {
+            resume.push(state.len() as u32);
+            resume.extend_from_slice(state);
+        }
+        let resume: Vec<u8> = bytemuck::cast_slice(&resume).to_vec();
+        let saved = Rc::new(RefCell::new(None));
+        let suspend = {
+            let saved = saved.clone();
+            move |_, request: &[u8]| -> Result<Vec<u8>> {
+                let words = request
+                    .chunks_exact(4)
+                    .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+                    .collect();
+                *saved.borrow_mut() = Some(words);
+                Ok(Vec::new())
+            }
+        };
+        let opts = opts()
+            .with_sendrecv_handler(SENDRECV_CHANNEL_SEGMENT_RESUME, move |_, _: &[u8]| {
+                Ok(resume.clone())
+            })
+            .with_sendrecv_handler(SENDRECV_CHANNEL_SEGMENT_SUSPEND, suspend);
+        let mut prover = Prover::new_with_opts(elf, method_id, opts)?;
+        prover.add_input_u32_slice(input);
+        segments.push(prover.run()?);
+        drop(prover);
+
+        state = saved.borrow_mut().take();
+        if state.is_none() {
+            return Ok(SegmentedReceipt { segments });
+        }
+    }
+}
+
+// Returns where the input and state digests start in a segment's journal.
+fn state_offset(journal: &[u32]) -> Result<usize> {
+    journal
+        .len()
+        .checked_sub(3 * DIGEST_WORDS)
+        .ok_or_else(|| anyhow!("Segment journal is too short to hold its state digests"))
+}
+
+fn check_chain(journals: &[Vec<u32>]) -> Result<()> {
+    if journals.is_empty() {
+        bail!("No segments");
+    }
+    let mut prev_end = Digest::default();
+    let mut first_input = None;
+    for (i, journal) in journals.iter().enumerate() {
+        let offset = state_offset(journal)?;
+        let digests: Vec<Digest> = journal[offset..]
+            .chunks_exact(DIGEST_WORDS)
+            .map(Digest::from_slice)
+            .collect();
+        let (input, start, end) = (digests[0], digests[1], digests[2]);
+        if *first_input.get_or_insert(input) != input {
+            bail!("Segment {i} was given different input from the first segment");
+        }
+        if start != prev_end {
+            bail!("Segment {i} does not resume from the state saved by the segment before it");
+        }
+        let last = i + 1 == journals.len();
+        if last && end != Digest::default() {
+            bail!("Segment {i} is the last, but saved state for another segment");
+        }
+        if !last && end == Digest::default() {
+            bail!("Segment {i} finished the computation, but is not the last");
+        }
+        prev_end = end;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use risc0_zkp::core::sha::{Digest, DIGEST_WORDS};
+
+    use super::check_chain;
+
+    fn journal(data: &[u32], start: u32, end: u32) -> Vec<u32> {
+        journal_with_input(data, 9, start, end)
+    }
+
+    fn journal_with_input(data: &[u32], input: u32, start: u32, end: u32) -> Vec<u32> {
+        let digest = |n| {
+            if n == 0 {
+                Digest::default()
+            } else {
+                Digest::new([n; DIGEST_WORDS])
+            }
+        };
+        let mut journal = data.to_vec();
+        journal.extend_from_slice(digest(input).as_slice());
+        journal.extend_from_slice(digest(start).as_slice());
+        journal.extend_from_slice(digest(end).as_slice());
+        journal
+    }
+
+    #[test]
+    fn chain() {
+        assert!(check_chain(&[journal(&[], 0, 0)]).is_ok());
+        assert!(
+            check_chain(&[journal(&[1], 0, 1), journal(&[], 1, 2), journal(&[2], 2, 0)]).is_ok()
+        );
+
+        // No segments at all.
+        assert!(check_chain(&[]).is_err());
+        // The first segment resumes from saved state.
+        assert!(check_chain(&[journal(&[], 1, 0)]).is_err());
+        // The last segment saves state, so a segment is missing at the end.
+        assert!(check_chain(&[journal(&[], 0, 1)]).is_err());
+        // A segment in the middle is missing.
+        assert!(check_chain(&[journal(&[], 0, 1), journal(&[], 2, 0)]).is_err());
+        // The segments are out of order.
+        assert!(
+            check_chain(&[journal(&[], 1, 2), journal(&[], 0, 1), journal(&[], 2, 0)]).is_err()
+        );
+        // A segment finishes early.
+        assert!(check_chain(&[journal(&[], 0, 0), journal(&[], 0, 0)]).is_err());
+        // A segment is given different input.
+        assert!(check_chain(&[
+            journal_with_input(&[], 9, 0, 1),
+            journal_with_input(&[], 8, 1, 0)
+        ])
+        .is_err());
+        // A journal too short to hold the digests.
+        assert!(check_chain(&[vec![0; 2 * DIGEST_WORDS]]).is_err());
+    }
+}
@@ -0,0 +1,142 @@
+// Copyright 2022 Risc0, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::{Read, Write};
+
+use anyhow::{bail, Result};
+
+// How many bytes a ReadHandler reads from its source at once.
+const READ_CHUNK: usize = 4096;
+
+/// Answers the requests a guest sends on a channel with
+/// `risc0_zkvm_guest::env::host_sendrecv`.  Install one with
+/// [ProverOpts::with_sendrecv_handler](super::ProverOpts::with_sendrecv_handler).
+pub trait SendRecvHandler {
+    /// Called with each `request` the guest sends on `channel`.  The
+    /// response is written to `reply`, and is empty if nothing is written.
+    /// Returning an error aborts execution.
+    fn on_sendrecv(
+        &mut self,
+        channel: u32,
+        request: &[u8],
+        reply: &mut SendRecvReply,
+    ) -> Result<()>;
+}
+
+impl<F: FnMut(u32, &[u8]) -> Result<Vec<u8>>> SendRecvHandler for F {
+    fn on_sendrecv(
+        &mut self,
+        channel: u32,
+        request: &[u8],
+        reply: &mut SendRecvReply,
+    ) -> Result<()> {
+        reply.write(&self(channel, request)?)
+    }
+}
+
+/// The response to a sendrecv request.
+///
+/// A response can be written a piece at a time, and each piece is copied
+/// into the guest's input window as it is written.
+pub struct SendRecvReply<'a> {
+    sink: &'a mut dyn FnMut(&[u8]) -> Result<()>,
+}
+
+impl<'a> SendRecvReply<'a> {
+    pub(crate) fn new(sink: &'a mut dyn FnMut(&[u8]) -> Result<()>) -> Self {
+        SendRecvReply { sink }
+    }
+
+    /// Append `data` to the response.  This fails if the response grows
+    /// larger than the guest's heap, from which its input windows are
+    /// allocated.
+    pub fn write(&mut self, data: &[u8]) -> Result<()> {
+        (self.sink)(data)
+    }
+}
+
+/// A [SendRecvHandler] which serves a `risc0_zkvm_guest::io::ChannelReader`
+/// from a [Read] source, such as a file.
+///
+/// Each request holds the number of bytes the guest wants as a little-endian
+/// `u32`.  The response holds as many of them as the source has left, which
+/// the guest takes as the end of the stream once it is empty.
+pub struct ReadHandler<R: Read> {
+    source: R,
+}
+
+impl<R: Read> ReadHandler<R> {
+    /// Serve data from `source`.
+    pub fn new(source: R) -> Self {
+        ReadHandler { source }
+    }
+}
+
+impl<R: Read> SendRecvHandler for ReadHandler<R> {
+    fn on_sendrecv(
+        &mut self,
+        _channel: u32,
+        request: &[u8],
+        reply: &mut SendRecvReply,
+    ) -> Result<()> {
+        let mut want = match request.try_into() {
+            Ok(bytes) => u32::from_le_bytes(bytes) as usize,
+            Err(_) => bail!("Malformed read request of {} bytes", request.len()),
+        };
+        let mut chunk = [0; READ_CHUNK];
+        while want > 0 {
+            let n = self.source.read(&mut chunk[..want.min(READ_CHUNK)])?;
+            if n == 0 {
+                break;
+            }
+            reply.write(&chunk[..n])?;
+            want -= n;
+        }
+        Ok(())
+    }
+}
+
+/// A [SendRecvHandler] which passes everything a guest writes to a
+/// `risc0_zkvm_guest::io::ChannelWriter` on to a [Write] sink.
+pub struct WriteHandler<W: Write> {
+    sink: W,
+}
+
+impl<W: Write> WriteHandler<W> {
+    /// Write the guest's data to `sink`.
+    pub fn new(sink: W) -> Self {
+        WriteHandler { sink }
+    }
+}
+
+impl<W: Write> SendRecvHandler for WriteHandler<W> {
+    fn on_sendrecv(&mut self, _channel: u32, request: &[u8], _: &mut SendRecvReply) -> Result<()> {
+        Ok(self.sink.write_all(request)?)
+    }
+}
+
+// Adapts the closures given to
+// [ProverOpts::with_sendrecv_callback](super::ProverOpts::with_sendrecv_callback).
+pub(crate) struct SendRecvCallback<F>(pub F);
+
+impl<F: Fn(u32, &[u8]) -> Vec<u8>> SendRecvHandler for SendRecvCallback<F> {
+    fn on_sendrecv(
+        &mut self,
+        channel: u32,
+        request: &[u8],
+        reply: &mut SendRecvReply,
+    ) -> Result<()> {
+        reply.write(&(self.0)(channel, request))
+    }
+}
@@ -0,0 +1,226 @@
+// Copyright 2022 Risc0, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Write;
+
+use anyhow::Result;
+
+/// A register written by an instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegisterWrite {
+    /// The index of the register, from 1 to 31.
+    pub reg: u32,
+
+    /// The value written to the register.
+    pub value: u32,
+}
+
+/// A memory access made by an instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryAccess {
+    /// The byte address of the word accessed.
+    pub addr: u32,
+
+    /// The word read or written.
+    pub value: u32,
+
+    /// True if this access was a write.
+    pub is_write: bool,
+}
+
+/// Everything the guest did while executing a single instruction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceEvent {
+    /// The cycle in which the instruction was decoded.
+    pub cycle: u32,
+
+    /// The address of the instruction.
+    pub pc: u32,
+
+    /// The encoded instruction.
+    pub insn: u32,
+
+    /// The mnemonic of the instruction, as decoded by the circuit.
+    pub mnemonic: String,
+
+    /// The register written by this instruction, if any.
+    pub reg_write: Option<RegisterWrite>,
+
+    /// The memory accessed by this instruction, not including the fetch of
+    /// the instruction itself.
+    pub memory: Vec<MemoryAccess>,
+}
+
+/// Receives a [TraceEvent] for every instruction the guest executes.
+/// Install one with
+/// [ProverOpts::with_trace_callback](super::ProverOpts::with_trace_callback).
+pub trait TraceCallback {
+    /// Called after each instruction has executed.  Returning an error
+    /// aborts execution.
+    fn on_instruction(&mut self, event: &TraceEvent) -> Result<()>;
+
+    /// Called once the guest has halted at `cycle`, after the last
+    /// instruction has been passed to [TraceCallback::on_instruction].
+    fn on_exit(&mut self, _cycle: u32) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<F: FnMut(&TraceEvent) -> Result<()>> TraceCallback for F {
+    fn on_instruction(&mut self, event: &TraceEvent) -> Result<()> {
+        self(event)
+    }
+}
+
+/// The output formats supported by [TraceWriter].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceFormat {
+    /// Little-endian words per instruction: `cycle`, `pc`, `insn`, the
+    /// written register (0 if none), the value written to it, and the
+    /// number of memory accesses, followed by `addr`, `value` and
+    /// `is_write` for each access.
+    Binary,
+
+    /// One JSON object per line per instruction.
+    JsonLines,
+}
+
+/// A [TraceCallback] which streams the trace to a writer, so that
+/// executions can be saved and compared.
+pub struct TraceWriter<W: Write> {
+    writer: W,
+    format: TraceFormat,
+}
+
+impl<W: Write> TraceWriter<W> {
+    /// Construct a [TraceWriter] writing to `writer` in the given format.
+    pub fn new(writer: W, format: TraceFormat) -> Self {
+        TraceWriter { writer, format }
+    }
+
+    fn write_binary(&mut self, event: &TraceEvent) -> Result<()> {
+        let reg_write = event
+            .reg_write
+            .unwrap_or(RegisterWrite { reg: 0, value: 0 });
+        let mut words = vec![
+            event.cycle,
+            event.pc,
+            event.insn,
+            reg_write.reg,
+            reg_write.value,
+            event.memory.len() as u32,
+        ];
+        for access in event.memory.iter() {
+            words.extend([access.addr, access.value, access.is_write as u32]);
+        }
+        for word in words {
+            self.writer.write_all(&word.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn write_json(&mut self, event: &TraceEvent) -> Result<()> {
+        let reg_write = match event.reg_write {
+            Some(RegisterWrite { reg, value }) => format!("{{\"reg\":{reg},\"value\":{value}}}"),
+            None => "null".into(),
+        };
+        let memory: Vec<String> = event
+            .memory
+            .iter()
+            .map(|access| {
+                format!(
+                    "{{\"addr\":{},\"value\":{},\"is_write\":{}}}",
+                    access.addr, access.value, access.is_write
+                )
+            })
+            .collect();
+        writeln!(
+            self.writer,
+            "{{\"cycle\":{},\"pc\":{},\"insn\":{},\"mnemonic\":\"{}\",\"reg_write\":{},\"memory\":[{}]}}",
+            event.cycle,
+            event.pc,
+            event.insn,
+            event.mnemonic,
+            reg_write,
+            memory.join(",")
+        )?;
+        Ok(())
+    }
+}
+
+impl<W: Write> TraceCallback for TraceWriter<W> {
+    fn on_instruction(&mut self, event: &TraceEvent) -> Result<()> {
+        match self.format {
+            TraceFormat::Binary => self.write_binary(event),
+            TraceFormat::JsonLines => self.write_json(event),
+        }
+    }
+
+    fn on_exit(&mut self, _cycle: u32) -> Result<()> {
+        Ok(self.writer.flush()?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MemoryAccess, RegisterWrite, TraceCallback, TraceEvent, TraceFormat, TraceWriter};
+
+    fn event() -> TraceEvent {
+        TraceEvent {
+            cycle: 7,
+            pc: 0x1000,
+            insn: 0x00112023,
+            mnemonic: "SW".into(),
+            reg_write: None,
+            memory: vec![MemoryAccess {
+                addr: 0x20,
+                value: 5,
+                is_write: true,
+            }],
+        }
+    }
+
+    #[test]
+    fn json_lines() {
+        let mut out = Vec::new();
+        let mut writer = TraceWriter::new(&mut out, TraceFormat::JsonLines);
+        writer.on_instruction(&event()).unwrap();
+        let mut event = event();
+        event.reg_write = Some(RegisterWrite { reg: 1, value: 2 });
+        event.memory.clear();
+        writer.on_instruction(&event).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            concat!(
+                "{\"cycle\":7,\"pc\":4096,\"insn\":1122339,\"mnemonic\":\"SW\",\"reg_write\":null,",
+                "\"memory\":[{\"addr\":32,\"value\":5,\"is_write\":true}]}\n",
+                "{\"cycle\":7,\"pc\":4096,\"insn\":1122339,\"mnemonic\":\"SW\",",
+                "\"reg_write\":{\"reg\":1,\"value\":2},\"memory\":[]}\n",
+            )
+        );
+    }
+
+    #[test]
+    fn binary() {
+        let mut out = Vec::new();
+        TraceWriter::new(&mut out, TraceFormat::Binary)
+            .on_instruction(&event())
+            .unwrap();
+        let words: Vec<u32> = out
+            .chunks(4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+            .collect();
+        assert_eq!(words, [7, 0x1000, 0x00112023, 0, 0, 1, 0x20, 5, 1]);
+    }
+}
@@ -15,6 +15,12 @@
 use alloc::vec::Vec;
 
 use anyhow::Result;
+#[cfg(feature = "prove")]
+use risc0_zkp::{
+    core::{blake3, poseidon},
+    hal::cpu::CpuHal,
+    ProofParams,
+};
 use risc0_zkp::{
     core::{
         log2_ceil,
@@ -23,6 +29,9 @@ use risc0_zkp::{
     MAX_CYCLES, MIN_CYCLES,
 };
 
+#[cfg(feature = "prove")]
+use crate::receipt::HashFn;
+
 /// The default digest count when generating a MethodId.
 pub const DEFAULT_METHOD_ID_LIMIT: u32 = 16;
 
@@ -79,7 +88,46 @@ impl MethodId {
 
     #[cfg(feature = "prove")]
     pub fn compute_with_limit(elf_contents: &[u8], limit: u32) -> Result<Self> {
-        prove::compute_with_limit(elf_contents, limit)
+        MethodId::compute_with_limit_and_hash(elf_contents, limit, HashFn::Sha256)
+    }
+
+    /// Compute the method ID for proofs whose seals use the given hash, as
+    /// selected by [ProverOpts::with_hash](crate::host::ProverOpts::with_hash).
+    #[cfg(feature = "prove")]
+    pub fn compute_with_limit_and_hash(
+        elf_contents: &[u8],
+        limit: u32,
+        hash: HashFn,
+    ) -> Result<Self> {
+        Self::compute_with_params(elf_contents, limit, hash, &ProofParams::default())
+    }
+
+    /// Like [MethodId::compute_with_limit_and_hash], but for seals made with
+    /// `params`, whose [ProofParams::inv_rate] sets the size of the code
+    /// Merkle tree.
+    #[cfg(feature = "prove")]
+    pub fn compute_with_params(
+        elf_contents: &[u8],
+        limit: u32,
+        hash: HashFn,
+        params: &ProofParams,
+    ) -> Result<Self> {
+        params.validate()?;
+        match hash {
+            HashFn::Sha256 => prove::compute_with_hal(&CpuHal::new(), elf_contents, limit, params),
+            HashFn::Poseidon => prove::compute_with_hal(
+                &CpuHal::new_with_sha(poseidon::Impl {}),
+                elf_contents,
+                limit,
+                params,
+            ),
+            HashFn::Blake3 => prove::compute_with_hal(
+                &CpuHal::new_with_sha(blake3::Impl {}),
+                elf_contents,
+                limit,
+                params,
+            ),
+        }
     }
 }
 
@@ -90,18 +138,22 @@ mod prove {
         adapter::TapsProvider,
         core::{fp::Fp, sha::Digest},
         field::baby_bear::BabyBear,
-        hal::{cpu::CpuHal, Hal},
+        hal::Hal,
         prove::poly_group::PolyGroup,
-        MIN_CYCLES, ZK_CYCLES,
+        ProofParams, MIN_CYCLES, ZK_CYCLES,
     };
     use risc0_zkvm_platform::memory::MEM_SIZE;
 
     use super::{MethodId, MAX_CODE_DIGEST_COUNT};
     use crate::{elf::Program, prove::exec, CIRCUIT};
 
-    pub fn compute_with_limit(elf_contents: &[u8], limit: u32) -> Result<MethodId> {
+    pub fn compute_with_hal<H: Hal<Field = BabyBear>>(
+        hal: &H,
+        elf_contents: &[u8],
+        limit: u32,
+        params: &ProofParams,
+    ) -> Result<MethodId> {
         let code_size = CIRCUIT.code_size();
-        let hal: CpuHal<BabyBear> = CpuHal::new();
         let program = Program::load_elf(elf_contents, MEM_SIZE as u32)?;
 
         // Start with an empty table
@@ -111,8 +163,11 @@ mod prove {
         let count = std::cmp::min(limit, MAX_CODE_DIGEST_COUNT);
         for i in 0..count {
             let cycles = MIN_CYCLES * (1 << i);
-            if cycles < program.image.len() + 3 + ZK_CYCLES {
-                // Can't even fit the program in this cycle size, just set to zero
+            if cycles < program.image.len() + 3 + ZK_CYCLES
+                || cycles > 1 << params.max_po2::<BabyBear>()
+            {
+                // Can't even fit the program in this cycle size, or can't
+                // prove this many cycles with these params, just set to zero
                 table.push(Digest::default());
                 continue;
             }
@@ -127,7 +182,7 @@ mod prove {
             hal.batch_interpolate_ntt(&coeffs, code_size);
             hal.zk_shift(&coeffs, code_size);
             // Make the poly-group & extract the root
-            let code_group = PolyGroup::new(&hal, &coeffs, code_size, cycles);
+            let code_group = PolyGroup::new(hal, &coeffs, code_size, cycles, params);
             table.push(code_group.merkle.root().clone());
         }
 
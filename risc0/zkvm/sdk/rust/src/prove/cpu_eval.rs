@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use rayon::prelude::*;
 use risc0_zkp::{
     adapter::{PolyFp, PolyFpContext},
     core::{
@@ -19,15 +20,20 @@ use risc0_zkp::{
         fp4::{Fp4, EXT_SIZE},
         log2_ceil,
         rou::ROU_FWD,
+        sha::Sha,
     },
     field::{baby_bear::BabyBear, Elem},
     hal::{
         cpu::{CpuBuffer, CpuHal},
         EvalCheck,
     },
-    INV_RATE,
 };
 
+// How many cycles of the domain are evaluated in parallel at a time.  The
+// results of each block are collected before being scattered into the
+// check polynomial, which is laid out one extension element column at a time.
+const BLOCK_SIZE: usize = 1 << 16;
+
 pub struct CpuEvalCheck<'a, C: PolyFp> {
     circuit: &'a C,
 }
@@ -38,7 +44,7 @@ impl<'a, C: PolyFp> CpuEvalCheck<'a, C> {
     }
 }
 
-impl<'a, C: PolyFp> EvalCheck<CpuHal<BabyBear>> for CpuEvalCheck<'a, C> {
+impl<'a, C: PolyFp + Sync, S: Sha + Sync> EvalCheck<CpuHal<BabyBear, S>> for CpuEvalCheck<'a, C> {
     fn eval_check(
         &self,
         check: &CpuBuffer<Fp>,
@@ -50,33 +56,40 @@ impl<'a, C: PolyFp> EvalCheck<CpuHal<BabyBear>> for CpuEvalCheck<'a, C> {
         poly_mix: Fp4,
         po2: usize,
         steps: usize,
+        inv_rate: usize,
     ) {
-        const EXP_PO2: usize = log2_ceil(INV_RATE);
-
-        let domain = steps * INV_RATE;
+        let exp_po2 = log2_ceil(inv_rate);
+        let domain = steps * inv_rate;
         let code = code.as_slice();
         let data = data.as_slice();
         let accum = accum.as_slice();
         let mix = mix.as_slice();
         let out = out.as_slice();
+        let args: &[&[Fp]] = &[&code, &out, &data, &mix, &accum];
         let mut check = check.as_slice_mut();
-        // TODO: parallelize
-        for cycle in 0..domain {
-            let args: &[&[Fp]] = &[&code, &out, &data, &mix, &accum];
-            let cond = self.circuit.poly_fp(
-                &PolyFpContext {
-                    size: domain,
-                    cycle,
-                    mix: poly_mix,
-                },
-                args,
-            );
-            let x = Fp::new(ROU_FWD[po2 + EXP_PO2]).pow(cycle);
-            // TODO: what is this magic number 3?
-            let y = (Fp::new(3) * x).pow(1 << po2);
-            let ret = cond.tot * (y - Fp::new(1)).inv();
-            for i in 0..EXT_SIZE {
-                check[i * domain + cycle] = ret.elems()[i];
+        let mut block = vec![Fp4::default(); BLOCK_SIZE.min(domain)];
+        for start in (0..domain).step_by(BLOCK_SIZE) {
+            let block = &mut block[..BLOCK_SIZE.min(domain - start)];
+            block.par_iter_mut().enumerate().for_each(|(i, ret)| {
+                let cycle = start + i;
+                let cond = self.circuit.poly_fp(
+                    &PolyFpContext {
+                        size: domain,
+                        cycle,
+                        mix: poly_mix,
+                        inv_rate,
+                    },
+                    args,
+                );
+                let x = Fp::new(ROU_FWD[po2 + exp_po2]).pow(cycle);
+                // TODO: what is this magic number 3?
+                let y = (Fp::new(3) * x).pow(1 << po2);
+                *ret = cond.tot * (y - Fp::new(1)).inv();
+            });
+            for (i, ret) in block.iter().enumerate() {
+                for j in 0..EXT_SIZE {
+                    check[j * domain + start + i] = ret.elems()[j];
+                }
             }
         }
     }
@@ -18,10 +18,11 @@ use core::{
 };
 use std::collections::{btree_map::Entry, BTreeMap, BTreeSet};
 
-use anyhow::{bail, Result};
+use anyhow::{anyhow, Result};
 use bytemuck::Pod;
 use lazy_regex::{regex, Captures};
 use log::{debug, trace};
+use rand::{thread_rng, Rng};
 use risc0_zkp::{
     adapter::{CircuitDef, CustomStep, PolyExt, PolyExtContext, TapsProvider},
     core::{
@@ -39,24 +40,129 @@ use risc0_zkvm_platform::{
     io::{
         addr::{
             GPIO_COMMIT, GPIO_COMPUTE_POLY, GPIO_CYCLECOUNT, GPIO_FAULT, GPIO_FFPU, GPIO_GETKEY,
-            GPIO_INSECURESHACOMPRESS, GPIO_INSECURESHAHASH, GPIO_LOG, GPIO_POLY_EVAL,
-            GPIO_SENDRECV_ADDR, GPIO_SENDRECV_CHANNEL, GPIO_SENDRECV_SIZE, GPIO_SHA,
+            GPIO_INPUT_WINDOW, GPIO_INSECURESHACOMPRESS, GPIO_INSECURESHAHASH, GPIO_LOG,
+            GPIO_OUT_OF_MEMORY, GPIO_POLY_EVAL, GPIO_SENDRECV_ADDR, GPIO_SENDRECV_CHANNEL,
+            GPIO_SENDRECV_SIZE, GPIO_SHA,
         },
-        ComputePolyDescriptor, FfpuDescriptor, InsecureShaCompressDescriptor,
-        InsecureShaHashDescriptor, IoDescriptor, PolyEvalDescriptor, SHADescriptor,
-        SliceDescriptor,
+        ComputePolyDescriptor, FfpuDescriptor, GetKeyDescriptor, InsecureShaCompressDescriptor,
+        InsecureShaHashDescriptor, IoDescriptor, OutOfMemoryDescriptor, PolyEvalDescriptor,
+        SHADescriptor, SliceDescriptor,
     },
-    memory::{INPUT, MEM_BITS},
+    memory::{GPIO, HEAP, INPUT, MEM_BITS, MEM_SIZE},
     WORD_SIZE,
 };
 
 use super::ffpu::ffpu_execute;
-use crate::{elf::Program, CIRCUIT};
+use crate::{
+    elf::Program,
+    host::{
+        GuestState, Key, KeyMode, MemoryAccess, ProverError, RegisterWrite, SendRecvReply,
+        TraceEvent, REGISTER_COUNT,
+    },
+    CIRCUIT,
+};
+
+// Where the circuit keeps the state of each cycle in its data columns; see
+// risc0/zkvm/circuit/data_regs.h and final_cycle.h.  After a final cycle,
+// these hold the two-bit digits of the next PC, one-hot selectors for the
+// low three and high two bits of the register written, and the low and high
+// half-words of each register.
+const DATA_PC: usize = 0;
+const DATA_RD_LOW: usize = 16;
+const DATA_RD_HIGH: usize = 24;
+const DATA_REGISTERS: usize = 32;
+// A one-hot selector for the type of every cycle, indexed by DataCycleType
+// in risc0/zkvm/circuit/constants.h.
+const DATA_CYCLE_TYPE: usize = 132;
+const CYCLE_TYPE_DECODE: usize = 0;
+const CYCLE_TYPE_FINAL: usize = 7;
+
+// The instructions decoded by the circuit, from
+// risc0/zkvm/circuit/riscv32im.inl: the opcode without its low two bits,
+// funct3 and funct7 where they matter, and the mnemonic.
+const MNEMONICS: &[(u32, Option<u32>, Option<u32>, &str)] = &[
+    (0b01100, Some(0), Some(0), "ADD"),
+    (0b01100, Some(0), Some(32), "SUB"),
+    (0b01100, Some(4), Some(0), "XOR"),
+    (0b01100, Some(6), Some(0), "OR"),
+    (0b01100, Some(7), Some(0), "AND"),
+    (0b01100, Some(2), Some(0), "SLT"),
+    (0b01100, Some(3), Some(0), "SLTU"),
+    (0b00100, Some(0), None, "ADDI"),
+    (0b00100, Some(4), None, "XORI"),
+    (0b00100, Some(6), None, "ORI"),
+    (0b00100, Some(7), None, "ANDI"),
+    (0b00100, Some(2), None, "SLTI"),
+    (0b00100, Some(3), None, "SLTIU"),
+    (0b00000, Some(0), None, "LB"),
+    (0b00000, Some(1), None, "LH"),
+    (0b00000, Some(2), None, "LW"),
+    (0b00000, Some(4), None, "LBU"),
+    (0b00000, Some(5), None, "LHU"),
+    (0b01000, Some(0), None, "SB"),
+    (0b01000, Some(1), None, "SH"),
+    (0b01000, Some(2), None, "SW"),
+    (0b11000, Some(0), None, "BEQ"),
+    (0b11000, Some(1), None, "BNE"),
+    (0b11000, Some(4), None, "BLT"),
+    (0b11000, Some(5), None, "BGE"),
+    (0b11000, Some(6), None, "BLTU"),
+    (0b11000, Some(7), None, "BGEU"),
+    (0b11011, None, None, "JAL"),
+    (0b11001, Some(0), None, "JALR"),
+    (0b01101, None, None, "LUI"),
+    (0b00101, None, None, "AUIPC"),
+    (0b01100, Some(0), Some(1), "MUL"),
+    (0b01100, Some(1), Some(1), "MULH"),
+    (0b01100, Some(2), Some(1), "MULSU"),
+    (0b01100, Some(3), Some(1), "MULU"),
+    (0b01100, Some(1), Some(0), "SLL"),
+    (0b00100, Some(1), Some(0), "SLLI"),
+    (0b01100, Some(4), Some(1), "DIV"),
+    (0b01100, Some(5), Some(1), "DIVU"),
+    (0b01100, Some(6), Some(1), "REM"),
+    (0b01100, Some(7), Some(1), "REMU"),
+    (0b01100, Some(5), Some(0), "SRL"),
+    (0b01100, Some(5), Some(32), "SRA"),
+    (0b00100, Some(5), Some(0), "SRLI"),
+    (0b00100, Some(5), Some(32), "SRAI"),
+    (0b11100, Some(0), Some(0), "HALT"),
+];
+
+// The mnemonic of `insn`, as the circuit would decode it.
+fn mnemonic(insn: u32) -> &'static str {
+    let opcode = (insn >> 2) & 0x1f;
+    let func3 = (insn >> 12) & 0x7;
+    let func7 = insn >> 25;
+    MNEMONICS
+        .iter()
+        .find(|(oc, f3, f7, _)| {
+            *oc == opcode && f3.map_or(true, |f3| f3 == func3) && f7.map_or(true, |f7| f7 == func7)
+        })
+        .map_or("UNKNOWN", |(_, _, _, mnemonic)| mnemonic)
+}
 
 pub trait IoHandler {
     fn on_commit(&mut self, buf: &[u32]) -> Result<()>;
     fn on_fault(&mut self, msg: &str) -> Result<()>;
-    fn on_txrx(&mut self, channel: u32, buf: &[u8]) -> Result<Vec<u8>>;
+    fn on_txrx(&mut self, channel: u32, buf: &[u8], reply: &mut SendRecvReply) -> Result<()>;
+    fn on_get_key(&mut self, name: &str, mode: KeyMode) -> Result<Key>;
+    fn is_trace_enabled(&self) -> bool;
+    fn on_trace(&mut self, event: &TraceEvent) -> Result<()>;
+    fn on_trace_exit(&mut self, cycle: u32) -> Result<()>;
+    fn is_debug_enabled(&self) -> bool;
+    fn on_debug_step(&mut self, state: &GuestState) -> Result<()>;
+    fn on_debug_exit(&mut self, state: &GuestState, fault: Option<&str>) -> Result<()>;
+
+    fn on_log(&mut self, cycle: u32, msg: &str) -> Result<()> {
+        log_message(cycle, msg)
+    }
+}
+
+// Where messages from GPIO_LOG go when nothing else handles them.
+pub fn log_message(cycle: u32, msg: &str) -> Result<()> {
+    log::info!("R0VM[{cycle}]> {msg}");
+    Ok(())
 }
 
 #[derive(Clone, PartialEq, Eq)]
@@ -81,6 +187,16 @@ impl std::fmt::Debug for MemoryEvent {
 struct MemoryState {
     pub memory: BTreeMap<u32, u32>,
     pub history: BTreeSet<MemoryEvent>,
+    // The address of the instruction being executed, for error reports.
+    pub pc: u32,
+}
+
+// The end of the region of `size` bytes at `start`, which must not wrap
+// around the address space.
+fn region_end(start: u32, size: u32) -> Result<u32> {
+    start
+        .checked_add(size)
+        .ok_or_else(|| ProverError::MemoryOutOfRange { addr: start }.into())
 }
 
 /// Align the given address `addr` upwards to alignment `align`.
@@ -91,66 +207,58 @@ const fn align_up(addr: usize, align: usize) -> usize {
 }
 
 impl MemoryState {
-    #[track_caller]
-    fn load_u8(&self, addr: u32) -> u8 {
+    fn load_u8(&self, addr: u32) -> Result<u8> {
         // debug!("load_u8: 0x{addr:08X}");
         // align to the nearest word
         let aligned = addr & !(WORD_SIZE as u32 - 1);
         let offset = addr % WORD_SIZE as u32;
-        let word = self.load_u32(aligned);
-        ((word >> (offset * 8)) & 0xff) as u8
+        let word = self.load_u32(aligned)?;
+        Ok(((word >> (offset * 8)) & 0xff) as u8)
     }
 
-    #[track_caller]
-    fn load_u32(&self, addr: u32) -> u32 {
+    fn load_u32(&self, addr: u32) -> Result<u32> {
         // debug!("load_u32: 0x{addr:08X}");
-        assert_eq!(addr % WORD_SIZE as u32, 0, "unaligned load");
+        self.check_aligned(addr)?;
         let key = addr / 4;
         match self.memory.get(&key) {
-            Some(word) => *word,
-            None => panic!("addr out of range: 0x{addr:08X}"),
+            Some(word) => Ok(*word),
+            None if addr as usize >= MEM_SIZE => Err(ProverError::MemoryOutOfRange { addr }.into()),
+            None => Err(ProverError::UninitializedMemory { addr }.into()),
         }
     }
 
-    #[track_caller]
-    fn load_fp4(&self, addr: u32) -> Fp4 {
+    fn load_fp4(&self, addr: u32) -> Result<Fp4> {
         let words = &[
-            self.load_u32(addr + 0 * WORD_SIZE as u32),
-            self.load_u32(addr + 1 * WORD_SIZE as u32),
-            self.load_u32(addr + 2 * WORD_SIZE as u32),
-            self.load_u32(addr + 3 * WORD_SIZE as u32),
+            self.load_u32(addr + 0 * WORD_SIZE as u32)?,
+            self.load_u32(addr + 1 * WORD_SIZE as u32)?,
+            self.load_u32(addr + 2 * WORD_SIZE as u32)?,
+            self.load_u32(addr + 3 * WORD_SIZE as u32)?,
         ];
-        Fp4::from_u32_words(words)
+        Ok(Fp4::from_u32_words(words))
     }
 
-    #[track_caller]
-    fn load_region_u32(&self, start: u32, size: u32) -> Vec<u32> {
-        (start..start + size)
+    fn load_region_u32(&self, start: u32, size: u32) -> Result<Vec<u32>> {
+        (start..region_end(start, size)?)
             .step_by(WORD_SIZE)
             .map(|addr| self.load_u32(addr))
             .collect()
     }
 
-    #[track_caller]
-    fn load_region_fp4(&self, start: u32, size: u32) -> Vec<Fp4> {
+    fn load_region_fp4(&self, start: u32, size: u32) -> Result<Vec<Fp4>> {
         debug!("load_region_fp4: 0x{start:08X}:{size}");
-        (start..start + size)
+        (start..region_end(start, size)?)
             .step_by(WORD_SIZE * Fp4::WORDS)
             .map(|addr| self.load_fp4(addr))
             .collect()
     }
 
-    #[track_caller]
-    fn load_region(&self, addr: u32, size: u32) -> Vec<u8> {
-        let mut region = Vec::new();
-        for addr in addr..addr + size {
-            region.push(self.load_u8(addr));
-        }
-        region
+    fn load_region(&self, addr: u32, size: u32) -> Result<Vec<u8>> {
+        (addr..region_end(addr, size)?)
+            .map(|addr| self.load_u8(addr))
+            .collect()
     }
 
-    #[track_caller]
-    fn store_u8(&mut self, addr: u32, value: u8) {
+    fn store_u8(&mut self, addr: u32, value: u8) -> Result<()> {
         // debug!("store_u8: 0x{addr:08X} <= 0x{value:08X}");
         // align to the nearest word
         let aligned = addr & !(WORD_SIZE as u32 - 1);
@@ -158,13 +266,12 @@ impl MemoryState {
         let key = aligned / 4;
         let mut word = self.memory.get(&key).unwrap_or(&0) & !(0xff << (offset * 8));
         word |= (value as u32) << (offset * 8);
-        self.store_u32(aligned, word);
+        self.store_u32(aligned, word)
     }
 
-    #[track_caller]
-    fn store_u32(&mut self, addr: u32, value: u32) {
+    fn store_u32(&mut self, addr: u32, value: u32) -> Result<()> {
         // debug!("store_u32: 0x{addr:08X} <= 0x{value:08X}");
-        assert_eq!(addr % WORD_SIZE as u32, 0, "unaligned store");
+        self.check_aligned(addr)?;
         let key = addr / 4;
         match self.memory.entry(key) {
             Entry::Occupied(mut entry) => {
@@ -186,7 +293,7 @@ impl MemoryState {
                         debug!("addr: 0x{key:08X}, value: 0x{value:08X}, txn: {txn:?}");
                         // The guest has actually touched this memory, and we are not writing the
                         // same value
-                        panic!("Host cannot mutate existing memory");
+                        return Err(ProverError::HostMutation { addr }.into());
                     }
                 }
                 entry.insert(value);
@@ -195,78 +302,85 @@ impl MemoryState {
                 entry.insert(value);
             }
         }
+        Ok(())
     }
 
-    #[track_caller]
-    fn store_region(&mut self, addr: u32, slice: &[u8]) {
+    fn store_region(&mut self, addr: u32, slice: &[u8]) -> Result<()> {
         // debug!("store_region: 0x{addr:08X} <= {} bytes", slice.len());
         for i in 0..slice.len() {
-            self.store_u8(addr + i as u32, slice[i]);
+            self.store_u8(addr + i as u32, slice[i])?;
         }
+        Ok(())
     }
 
-    #[track_caller]
-    fn store_region_u32(&mut self, addr: u32, slice: &[u32]) {
-        assert!(addr % WORD_SIZE as u32 == 0);
+    fn store_region_u32(&mut self, addr: u32, slice: &[u32]) -> Result<()> {
         for (offset, word) in slice.iter().enumerate() {
-            self.store_u32(addr + WORD_SIZE as u32 * offset as u32, *word);
+            self.store_u32(addr + WORD_SIZE as u32 * offset as u32, *word)?;
         }
+        Ok(())
+    }
+
+    fn check_aligned(&self, addr: u32) -> Result<()> {
+        if addr % WORD_SIZE as u32 != 0 {
+            return Err(ProverError::UnalignedAccess { addr, pc: self.pc }.into());
+        }
+        Ok(())
     }
 
     // Reads a C structure from a guest's memory and transmutes it
     // into the given structure, which should be repr(C) and plain old
     // data.
-    unsafe fn read_descriptor<T: Send>(&self, addr: u32) -> T {
-        let size = core::mem::size_of::<T>();
-        assert_eq!(size % WORD_SIZE, 0, "Descriptors should be word aligned");
-        let buf = self.load_region_u32(addr, size as u32);
-        assert_eq!(buf.len() * WORD_SIZE, size);
-        core::ptr::read_unaligned(buf.as_ptr() as *const T)
+    unsafe fn read_descriptor<T: Send>(&self, addr: u32) -> Result<T> {
+        self.check_aligned(addr)?;
+        let buf = self.load_region(addr, core::mem::size_of::<T>() as u32)?;
+        Ok(core::ptr::read_unaligned(buf.as_ptr() as *const T))
     }
 
     // Reads words from guest's memory and transmutes it into the given structure,
     // which should be repr(C) and plain old data.
-    fn read_slice<T: Pod>(&self, desc: &SliceDescriptor) -> Vec<T> {
+    fn read_slice<T: Pod>(&self, desc: &SliceDescriptor) -> Result<Vec<T>> {
         let elt_size = core::mem::size_of::<T>();
         assert_eq!(elt_size % WORD_SIZE, 0, "T should be word aligned");
-        let bytes = self.load_region(desc.addr, desc.size);
-        bytes
+        let bytes = self.load_region(desc.addr, desc.size)?;
+        Ok(bytes
             .chunks_exact(elt_size)
             .map(|chunk| *bytemuck::from_bytes(chunk))
-            .collect()
+            .collect())
     }
 
-    fn read_slices(&self, desc: &SliceDescriptor) -> Vec<SliceDescriptor> {
+    fn read_slices(&self, desc: &SliceDescriptor) -> Result<Vec<SliceDescriptor>> {
         let elt_size = core::mem::size_of::<SliceDescriptor>();
         assert_eq!(elt_size % WORD_SIZE, 0, "T should be word aligned");
-        let bytes = self.load_region(desc.addr, desc.size);
-        bytes
+        let bytes = self.load_region(desc.addr, desc.size)?;
+        Ok(bytes
             .chunks_exact(elt_size)
             .map(|chunk| {
                 let size: u32 = *bytemuck::from_bytes(&chunk[..4]);
                 let addr: u32 = *bytemuck::from_bytes(&chunk[4..]);
                 SliceDescriptor { size, addr }
             })
-            .collect()
+            .collect())
     }
 
     // Reads words from guest's memory and transmutes it into the given value,
     // which should be repr(C) and plain old data.
-    fn read_value<T: Pod>(&self, addr: u32) -> T {
+    fn read_value<T: Pod>(&self, addr: u32) -> Result<T> {
         let size = core::mem::size_of::<T>();
         assert_eq!(size % WORD_SIZE, 0, "T should be word aligned");
-        let bytes = self.load_region(addr, size as u32);
-        *bytemuck::from_bytes(&bytes)
+        let bytes = self.load_region(addr, size as u32)?;
+        Ok(*bytemuck::from_bytes(&bytes))
     }
 
-    fn strlen(&self, addr: u32) -> usize {
-        let mut addr = addr;
-        let mut len = 0;
-        while self.load_u8(addr) != 0 {
-            addr += 1;
-            len += 1;
+    // Reads a nul-terminated string from the guest's memory.
+    fn read_str(&self, addr: u32) -> Result<String> {
+        let mut bytes = Vec::new();
+        for addr in addr.. {
+            match self.load_u8(addr)? {
+                0 => break,
+                byte => bytes.push(byte),
+            }
         }
-        len
+        Ok(String::from_utf8(bytes)?)
     }
 
     fn pop_history(&mut self) -> MemoryEvent {
@@ -285,6 +399,24 @@ pub struct MachineContext<'a, H: IoHandler> {
     memory: MemoryState,
     io: &'a mut H,
     cur_host_to_guest_offset: usize,
+    // End of the window the guest is receiving host data in, which starts
+    // out as the INPUT region.
+    input_end: usize,
+    // A response which outgrew the rest of the input window, which is sent
+    // once the guest moves to a new window.
+    pending_input: Option<Vec<u8>>,
+    trace_enabled: bool,
+    // The instruction currently being traced.
+    trace: Option<TraceEvent>,
+    // The memory accessed by the cycle being executed, which belongs to
+    // the instruction being traced unless the cycle decodes a new one.
+    step_memory: Vec<MemoryAccess>,
+    debug_enabled: bool,
+    // The guest's registers, as of the last final cycle.
+    registers: [u32; REGISTER_COUNT],
+    // Whether an error came from one of our callbacks rather than from the
+    // circuit itself.
+    callback_failed: bool,
 }
 
 impl PartialOrd for MemoryEvent {
@@ -307,6 +439,14 @@ impl<'a, H: IoHandler> CircuitDef<MachineContext<'a, H>> for CircuitImpl {}
 
 impl<'a, H: IoHandler> CustomStep for MachineContext<'a, H> {
     fn call(&mut self, name: &str, extra: &str, args: &[Fp], outs: &mut [Fp]) -> Result<()> {
+        let result = self.dispatch(name, extra, args, outs);
+        self.callback_failed |= result.is_err();
+        result
+    }
+}
+
+impl<'a, H: IoHandler> MachineContext<'a, H> {
+    fn dispatch(&mut self, name: &str, extra: &str, args: &[Fp], outs: &mut [Fp]) -> Result<()> {
         match name {
             "divide32" => {
                 let ((x0, x1), (x2, x3)) = self.divide32((args[0], args[1]), (args[2], args[3]));
@@ -349,6 +489,7 @@ impl MemoryState {
         Self {
             memory: BTreeMap::new(),
             history: BTreeSet::new(),
+            pc: 0,
         }
     }
 }
@@ -365,11 +506,111 @@ fn merge_word((low, high): (Fp, Fp)) -> u32 {
 
 impl<'a, H: IoHandler> MachineContext<'a, H> {
     pub fn new(io: &'a mut H) -> Self {
+        let trace_enabled = io.is_trace_enabled();
+        let debug_enabled = io.is_debug_enabled();
         MachineContext {
             memory: MemoryState::new(),
             io,
             cur_host_to_guest_offset: INPUT.start(),
+            input_end: INPUT.end(),
+            pending_input: None,
+            trace_enabled,
+            trace: None,
+            step_memory: Vec::new(),
+            debug_enabled,
+            registers: [0; REGISTER_COUNT],
+            callback_failed: false,
+        }
+    }
+
+    // Follow the guest through the state the circuit left in the data
+    // columns after executing `cycle`.  Each instruction starts with a
+    // decode cycle, which fetches it, and ends with a final cycle, which
+    // writes its result and the next PC.
+    fn on_step(&mut self, data: &[Fp], steps: usize, cycle: usize) -> Result<()> {
+        let col = |idx: usize| -> u32 { data[idx * steps + cycle].into() };
+        let memory = std::mem::take(&mut self.step_memory);
+        if col(DATA_CYCLE_TYPE + CYCLE_TYPE_DECODE) == 1 {
+            self.flush_trace()?;
+            let pc = self.memory.pc;
+            if self.debug_enabled {
+                self.io.on_debug_step(&GuestState {
+                    pc,
+                    registers: &self.registers,
+                    memory: &self.memory.memory,
+                })?;
+            }
+            if self.trace_enabled {
+                let insn = self.memory.load_u32(pc)?;
+                self.trace = Some(TraceEvent {
+                    cycle: cycle as u32,
+                    pc,
+                    insn,
+                    mnemonic: mnemonic(insn).to_string(),
+                    reg_write: None,
+                    memory: Vec::new(),
+                });
+            }
+        } else if col(DATA_CYCLE_TYPE + CYCLE_TYPE_FINAL) == 1 {
+            let register = |reg: usize| {
+                col(DATA_REGISTERS + 2 * reg) | col(DATA_REGISTERS + 2 * reg + 1) << 16
+            };
+            if let Some(event) = self.trace.as_mut() {
+                event.memory.extend(memory);
+                let one_hot =
+                    |start: usize, len: usize| (0..len).find(|i| col(start + i) == 1).unwrap_or(0);
+                let reg = one_hot(DATA_RD_LOW, 8) + 8 * one_hot(DATA_RD_HIGH, 4);
+                if reg != 0 {
+                    event.reg_write = Some(RegisterWrite {
+                        reg: reg as u32,
+                        value: register(reg),
+                    });
+                }
+            }
+            if self.debug_enabled {
+                for (reg, value) in self.registers.iter_mut().enumerate() {
+                    *value = register(reg);
+                }
+            }
+            self.memory.pc = (0..16).map(|i| col(DATA_PC + i) << (2 * i)).sum();
+            self.flush_trace()?;
+        } else if let Some(event) = self.trace.as_mut() {
+            event.memory.extend(memory);
         }
+        Ok(())
+    }
+
+    fn trace_access(&mut self, addr: u32, value: u32, is_write: bool) {
+        // Cycles without a memory operation read from address 0.
+        if !is_write && addr == 0 {
+            return;
+        }
+        if self.trace_enabled {
+            self.step_memory.push(MemoryAccess {
+                addr: addr * WORD_SIZE as u32,
+                value,
+                is_write,
+            });
+        }
+    }
+
+    fn flush_trace(&mut self) -> Result<()> {
+        match self.trace.take() {
+            Some(event) => self.io.on_trace(&event),
+            None => Ok(()),
+        }
+    }
+
+    fn debug_exit(&mut self, fault: Option<&str>) -> Result<()> {
+        if !self.debug_enabled {
+            return Ok(());
+        }
+        let state = GuestState {
+            pc: self.memory.pc,
+            registers: &self.registers,
+            memory: &self.memory.memory,
+        };
+        self.io.on_debug_exit(&state, fault)
     }
 
     fn divide32(&self, numer: (Fp, Fp), denom: (Fp, Fp)) -> ((Fp, Fp), (Fp, Fp)) {
@@ -434,6 +675,7 @@ impl<'a, H: IoHandler> MachineContext<'a, H> {
         // debug!("[{}] R: 0x{:08X}", cycle, addr);
         let data = *self.memory.memory.entry(addr).or_insert(0);
         // debug!("data: 0x{data:08X}");
+        self.trace_access(addr, data, false);
         self.memory.history.insert(MemoryEvent {
             cycle,
             addr,
@@ -449,6 +691,7 @@ impl<'a, H: IoHandler> MachineContext<'a, H> {
         let data = merge_word(value);
         let is_write = addr < (1 << (MEM_BITS - 1));
         // debug!("[{}] W: 0x{:08X} <= 0x{:08X}", cycle, addr, data);
+        self.trace_access(addr, data, true);
         self.memory.history.insert(MemoryEvent {
             cycle,
             addr,
@@ -458,12 +701,13 @@ impl<'a, H: IoHandler> MachineContext<'a, H> {
         match self.memory.memory.entry(addr) {
             Entry::Occupied(mut entry) => {
                 if *entry.get() != data && !is_write {
-                    bail!(
+                    return Err(anyhow!(
                         "Double wrote write-once memory at 0x{:08X}. old: 0x{:08X}, new: 0x{:08X}",
                         addr * 4,
                         *entry.get(),
                         data
-                    );
+                    )
+                    .context(ProverError::GuestFault));
                 }
                 *entry.get_mut() = data;
             }
@@ -475,17 +719,23 @@ impl<'a, H: IoHandler> MachineContext<'a, H> {
         Ok(())
     }
 
-    // Writes to the guest's INPUT region.  Zero pads up to the next
+    // Writes to the guest's input window.  Zero pads up to the next
     // word, and advances the host to guest offset.
-    fn send_to_guest(&mut self, bytes: &[u8]) {
+    fn send_to_guest(&mut self, bytes: &[u8]) -> Result<()> {
         let nwords = align_up(bytes.len(), WORD_SIZE);
-        assert!(
-            self.cur_host_to_guest_offset + nwords < INPUT.end(),
-            "Read buffer overrun"
-        );
+        let end = self.cur_host_to_guest_offset + nwords;
+        if end > self.input_end {
+            // The guest has read more than fits in its input region.
+            return Err(ProverError::InputOverflow {
+                requested: nwords,
+                available: self.input_end.saturating_sub(self.cur_host_to_guest_offset),
+            }
+            .into());
+        }
         self.memory
-            .store_region(self.cur_host_to_guest_offset as u32, bytes);
-        self.cur_host_to_guest_offset += nwords
+            .store_region(self.cur_host_to_guest_offset as u32, bytes)?;
+        self.cur_host_to_guest_offset += nwords;
+        Ok(())
     }
 
     fn on_write(&mut self, cycle: u32, addr: u32, value: u32) -> Result<()> {
@@ -495,118 +745,203 @@ impl<'a, H: IoHandler> MachineContext<'a, H> {
                 debug!("on_write> GPIO_COMMIT, ptr = {value:08X}");
                 // SAFETY: IoDescriptor is a plain-old-data type with
                 // repr(C) and no pointers so it's safe to fill it from bytes.
-                let desc: IoDescriptor = unsafe { self.memory.read_descriptor(value) };
+                let desc: IoDescriptor = unsafe { self.memory.read_descriptor(value)? };
                 debug!(
                     "on_write> GPIO_COMMIT, commit region starts at {} and is {} bytes long",
                     desc.addr, desc.size
                 );
 
-                let buf = self.memory.load_region_u32(desc.addr, desc.size);
+                let buf = self.memory.load_region_u32(desc.addr, desc.size)?;
                 debug!("Data: {:08X?}", &buf);
                 self.io.on_commit(buf.as_slice())?;
             }
             GPIO_FAULT => {
                 debug!("on_write> GPIO_FAULT");
-                let len = self.memory.strlen(value);
-                let buf = self.memory.load_region(value, len as u32);
-                let str = String::from_utf8(buf).unwrap();
+                let str = self.memory.read_str(value)?;
+                self.debug_exit(Some(&str))?;
                 self.io.on_fault(&str)?;
             }
+            GPIO_OUT_OF_MEMORY => {
+                debug!("on_write> GPIO_OUT_OF_MEMORY");
+                // SAFETY: OutOfMemoryDescriptor is a plain old repr(C)
+                // structure and has no pointers.
+                let desc: OutOfMemoryDescriptor = unsafe { self.memory.read_descriptor(value)? };
+                let err = ProverError::GuestOutOfMemory {
+                    requested: desc.requested,
+                    used: desc.used,
+                    limit: desc.limit,
+                };
+                self.debug_exit(Some(&err.to_string()))?;
+                return Err(err.into());
+            }
             GPIO_GETKEY => {
                 debug!("on_write> GPIO_GETKEY");
-                todo!()
+                // SAFETY: GetKeyDescriptor is a plain old repr(C)
+                // structure and has no pointers.
+                let desc: GetKeyDescriptor = unsafe { self.memory.read_descriptor(value)? };
+                let name = self.memory.read_str(desc.name)?;
+                let mode = KeyMode::from_u32(desc.mode)?;
+                debug!(
+                    "  addr = 0x{:08X}, name = {name}, mode = {mode:?}",
+                    desc.addr
+                );
+                let key = self.io.on_get_key(&name, mode)?;
+                self.memory.store_region_u32(desc.addr, &key)?;
             }
             GPIO_LOG => {
                 debug!("on_write> GPIO_LOG");
-                let len = self.memory.strlen(value);
-                let buf = self.memory.load_region(value, len as u32);
-                let str = String::from_utf8(buf).unwrap();
-                println!("R0VM[{cycle}]> {}", str);
+                let str = self.memory.read_str(value)?;
+                self.io.on_log(cycle, &str)?;
             }
             GPIO_SENDRECV_ADDR => {
                 debug!("on_write> GPIO_SENDRECV_ADDR");
-                let channel = self.memory.load_u32(GPIO_SENDRECV_CHANNEL);
-                let size = self.memory.load_u32(GPIO_SENDRECV_SIZE);
-                let region = self.memory.load_region(value, size);
-                let result = self.io.on_txrx(channel, &region)?;
-                self.send_to_guest(bytemuck::cast_slice(&[result.len() as u32]));
-                self.send_to_guest(result.as_slice())
+                let channel = self.memory.load_u32(GPIO_SENDRECV_CHANNEL)?;
+                let size = self.memory.load_u32(GPIO_SENDRECV_SIZE)?;
+                let region = self.memory.load_region(value, size)?;
+                // The response is preceded by its length, which is filled in
+                // once the handler returns.  Its pieces go straight into the
+                // input window after the length.  If it outgrows the rest of
+                // the window, the guest makes a new window which is large
+                // enough, and the response is sent there.
+                let len_addr = self.cur_host_to_guest_offset as u32;
+                self.send_to_guest(&[0; WORD_SIZE])?;
+                let start = self.cur_host_to_guest_offset;
+                let window_end = self.input_end;
+                let memory = &mut self.memory;
+                let mut len = 0;
+                let mut pending: Option<Vec<u8>> = None;
+                let mut sink = |data: &[u8]| -> Result<()> {
+                    // No input window can be larger than the heap.
+                    if len + data.len() > HEAP.len_bytes() {
+                        return Err(ProverError::InputOverflow {
+                            requested: len + data.len(),
+                            available: HEAP.len_bytes(),
+                        }
+                        .into());
+                    }
+                    if pending.is_none()
+                        && start + align_up(len + data.len(), WORD_SIZE) > window_end
+                    {
+                        pending = Some(memory.load_region(start as u32, len as u32)?);
+                    }
+                    match &mut pending {
+                        Some(pending) => pending.extend_from_slice(data),
+                        None => memory.store_region((start + len) as u32, data)?,
+                    }
+                    len += data.len();
+                    Ok(())
+                };
+                self.io
+                    .on_txrx(channel, &region, &mut SendRecvReply::new(&mut sink))?;
+                self.memory.store_u32(len_addr, len as u32)?;
+                match pending {
+                    Some(pending) => self.pending_input = Some(pending),
+                    None => self.cur_host_to_guest_offset += align_up(len, WORD_SIZE),
+                }
+            }
+            GPIO_INPUT_WINDOW => {
+                // SAFETY: SliceDescriptor is a plain old repr(C)
+                // structure and has no pointers.
+                let desc: SliceDescriptor = unsafe { self.memory.read_descriptor(value)? };
+                debug!(
+                    "on_write> GPIO_INPUT_WINDOW, 0x{:08X}:{}",
+                    desc.addr, desc.size
+                );
+                self.memory.check_aligned(desc.addr)?;
+                let start = desc.addr as usize;
+                let end = start + desc.size as usize;
+                // The window must be in writable memory, below the GPIOs.
+                if end > GPIO.start() {
+                    return Err(ProverError::MemoryOutOfRange { addr: end as u32 }.into());
+                }
+                self.cur_host_to_guest_offset = start;
+                self.input_end = end;
+                if let Some(response) = self.pending_input.take() {
+                    self.send_to_guest(&response)?;
+                }
             }
             GPIO_SHA => {
                 debug!("on_write> GPIO_SHA, descriptor ptr = {value:08X}");
                 // SAFETY: ShaDescriptor is a plain old repr(C)
                 // structure and has no pointers.
-                let desc: SHADescriptor = unsafe { self.memory.read_descriptor(value) };
-                self.process_sha(&desc);
+                let desc: SHADescriptor = unsafe { self.memory.read_descriptor(value)? };
+                self.process_sha(&desc)?;
             }
             GPIO_CYCLECOUNT => {
                 debug!("onWrite> GPIO_CycleCount, cycle = {cycle:08X}");
 
-                assert_eq!(
-                    value, 0,
-                    "CycleCount request should only be written as zero"
-                );
-                self.send_to_guest(bytemuck::cast_slice(&[cycle as u32]));
+                if value != 0 {
+                    return Err(anyhow!(
+                        "CycleCount request should only be written as zero, got 0x{value:08X}"
+                    )
+                    .context(ProverError::GuestFault));
+                }
+                self.send_to_guest(bytemuck::cast_slice(&[cycle as u32]))?;
             }
             GPIO_INSECURESHACOMPRESS => {
                 debug!("onWrite> GPIO_InsecureShaCompress");
                 // SAFETY: InsecureShaCompressDescriptor is a plain
                 // old repr(C) structure and has no pointers.
                 let desc: InsecureShaCompressDescriptor =
-                    unsafe { self.memory.read_descriptor(value) };
+                    unsafe { self.memory.read_descriptor(value)? };
 
                 let sha = risc0_zkp::core::sha::default_implementation();
 
                 const DIGEST_BYTES: u32 = (WORD_SIZE * DIGEST_WORDS) as u32;
-                let state = self.memory.load_region_u32(desc.state, DIGEST_BYTES);
-                let block_half1 = self.memory.load_region_u32(desc.block_half1, DIGEST_BYTES);
-                let block_half2 = self.memory.load_region_u32(desc.block_half2, DIGEST_BYTES);
+                let state = self.memory.load_region_u32(desc.state, DIGEST_BYTES)?;
+                let block_half1 = self
+                    .memory
+                    .load_region_u32(desc.block_half1, DIGEST_BYTES)?;
+                let block_half2 = self
+                    .memory
+                    .load_region_u32(desc.block_half2, DIGEST_BYTES)?;
 
                 let digest = sha.compress(
                     &Digest::from_slice(state.as_slice()),
                     &Digest::from_slice(block_half1.as_slice()),
                     &Digest::from_slice(block_half2.as_slice()),
                 );
-                self.send_to_guest(bytemuck::cast_slice(digest.as_slice()));
+                self.send_to_guest(bytemuck::cast_slice(digest.as_slice()))?;
             }
             GPIO_INSECURESHAHASH => {
                 debug!("onWrite> GPIO_InsecureShaHash");
                 // SAFETY: InsecureShaHashDescriptor is a plain old
                 // repr(C) structure and has no pointers.
-                let desc: InsecureShaHashDescriptor = unsafe { self.memory.read_descriptor(value) };
+                let desc: InsecureShaHashDescriptor =
+                    unsafe { self.memory.read_descriptor(value)? };
 
                 let sha = risc0_zkp::core::sha::default_implementation();
 
                 let orig_state = self
                     .memory
-                    .load_region_u32(desc.state, (WORD_SIZE * DIGEST_WORDS) as u32);
+                    .load_region_u32(desc.state, (WORD_SIZE * DIGEST_WORDS) as u32)?;
                 let state: Digest = Digest::from_slice(orig_state.as_slice());
-                let bytes = self.memory.load_region(desc.start, desc.len);
+                let bytes = self.memory.load_region(desc.start, desc.len)?;
 
                 let digest = sha.update(&state, bytes.as_slice());
-                self.send_to_guest(bytemuck::cast_slice(digest.as_slice()));
+                self.send_to_guest(bytemuck::cast_slice(digest.as_slice()))?;
             }
             GPIO_COMPUTE_POLY => {
                 // SAFETY: ComputePolyDescriptor is a plain old
                 // repr(C) structure and has no pointers.
-                let desc: ComputePolyDescriptor = unsafe { self.memory.read_descriptor(value) };
-                let eval_u: Vec<Fp4> = self.memory.read_slice(&desc.eval_u);
-                let poly_mix = self.memory.read_value(desc.poly_mix);
-                let out: Vec<Fp> = self.memory.read_slice(&desc.out);
-                let mix: Vec<Fp> = self.memory.read_slice(&desc.mix);
+                let desc: ComputePolyDescriptor = unsafe { self.memory.read_descriptor(value)? };
+                let eval_u: Vec<Fp4> = self.memory.read_slice(&desc.eval_u)?;
+                let poly_mix = self.memory.read_value(desc.poly_mix)?;
+                let out: Vec<Fp> = self.memory.read_slice(&desc.out)?;
+                let mix: Vec<Fp> = self.memory.read_slice(&desc.mix)?;
 
                 let ctx = PolyExtContext { mix: poly_mix };
                 let args: &[&[Fp]] = &[&out, &mix];
                 let result = CIRCUIT.poly_ext(&ctx, &eval_u, args);
-                self.send_to_guest(bytemuck::bytes_of(&result.tot));
+                self.send_to_guest(bytemuck::bytes_of(&result.tot))?;
             }
             GPIO_POLY_EVAL => {
                 // SAFETY: PolyEvalDescriptor is a plain old
                 // repr(C) structure and has no pointers.
-                let desc: PolyEvalDescriptor = unsafe { self.memory.read_descriptor(value) };
-                let coeffs: Vec<Fp4> = self.memory.read_slice(&desc.coeffs);
-                let x: Fp4 = self.memory.read_value(desc.x);
-                let y: Fp = self.memory.read_value(desc.y);
+                let desc: PolyEvalDescriptor = unsafe { self.memory.read_descriptor(value)? };
+                let coeffs: Vec<Fp4> = self.memory.read_slice(&desc.coeffs)?;
+                let x: Fp4 = self.memory.read_value(desc.x)?;
+                let y: Fp = self.memory.read_value(desc.y)?;
 
                 let mut mul_fp = Fp::ONE;
                 let mut mul_fp4 = Fp4::ONE;
@@ -616,13 +951,13 @@ impl<'a, H: IoHandler> MachineContext<'a, H> {
                     mul_fp *= y;
                     mul_fp4 *= x;
                 }
-                self.send_to_guest(bytemuck::bytes_of(&tot));
+                self.send_to_guest(bytemuck::bytes_of(&tot))?;
             }
             GPIO_FFPU => {
                 // SAFETY: FfpuDescriptor is a plain old
                 // repr(C) structure and has no pointers.
-                let desc: FfpuDescriptor = unsafe { self.memory.read_descriptor(value) };
-                let code: Vec<u32> = self.memory.read_slice(&desc.code);
+                let desc: FfpuDescriptor = unsafe { self.memory.read_descriptor(value)? };
+                let code: Vec<u32> = self.memory.read_slice(&desc.code)?;
                 log::debug!(
                     "GPIO_FFPU> code: 0x{:08X}:{}, args: 0x{:08X}:{}",
                     desc.code.addr,
@@ -630,27 +965,30 @@ impl<'a, H: IoHandler> MachineContext<'a, H> {
                     desc.args.addr,
                     desc.args.size
                 );
-                let desc_args = self.memory.read_slices(&desc.args);
+                let desc_args = self.memory.read_slices(&desc.args)?;
                 let mut args: Vec<Vec<Fp4>> = desc_args
                     .iter()
                     .map(|desc| self.memory.load_region_fp4(desc.addr, desc.size))
-                    .collect();
+                    .collect::<Result<_>>()?;
+                // The result is returned in the fifth argument.
+                if args.len() < 5 {
+                    return Err(anyhow!("FFPU expects 5 arguments, got {}", args.len())
+                        .context(ProverError::GuestFault));
+                }
                 let mut args: Vec<&mut [Fp4]> = args.iter_mut().map(|x| x.as_mut_slice()).collect();
-                debug!("args[0]: {:?}", args[0]);
-                debug!("args[1]: {:?}", args[1]);
-                debug!("args[2]: {:?}", args[2]);
-                debug!("args[3]: {:?}", args[3]);
-                debug!("args[4]: {:?}", args[4]);
+                for (i, arg) in args.iter().enumerate() {
+                    debug!("args[{i}]: {arg:?}");
+                }
                 ffpu_execute(&code, &mut args);
                 debug!("args[4]: {:?}", args[4]);
-                self.send_to_guest(bytemuck::cast_slice(&args[4]));
+                self.send_to_guest(bytemuck::cast_slice(&args[4]))?;
             }
             _ => {}
         };
         Ok(())
     }
 
-    fn process_sha(&mut self, desc: &SHADescriptor) {
+    fn process_sha(&mut self, desc: &SHADescriptor) -> Result<()> {
         let sha_type: u16 = ((desc.type_count & 0xFFFF) >> 4) as u16;
         let count: u16 = (desc.type_count & 0xFFFF) as u16;
         debug!(
@@ -661,13 +999,13 @@ impl<'a, H: IoHandler> MachineContext<'a, H> {
         let sha = risc0_zkp::core::sha::default_implementation();
         let words = self
             .memory
-            .load_region_u32(desc.source as u32, (count * 64) as u32);
+            .load_region_u32(desc.source as u32, u32::from(count) * 64)?;
         let digest = sha.hash_raw_words(bytemuck::cast_slice(words.as_slice()));
 
         debug!("Digest result is {:X?}", digest.as_slice());
 
         self.memory
-            .store_region_u32(desc.digest as u32, digest.as_slice());
+            .store_region_u32(desc.digest as u32, digest.as_slice())
     }
 }
 
@@ -872,23 +1210,126 @@ where
 
 pub struct RV32Executor<'a, H: IoHandler> {
     elf: &'a Program,
+    max_cycles: Option<usize>,
     pub executor: Executor<CircuitImpl, MachineContext<'a, H>>,
 }
 
 impl<'a, H: IoHandler> RV32Executor<'a, H> {
-    pub fn new(circuit: &'static CircuitImpl, elf: &'a Program, io: &'a mut H) -> Self {
+    pub fn new(
+        circuit: &'static CircuitImpl,
+        elf: &'a Program,
+        io: &'a mut H,
+        max_cycles: Option<usize>,
+    ) -> Self {
         debug!("image.size(): {}", elf.image.len());
         let machine = MachineContext::new(io);
         let min_po2 = log2_ceil(elf.image.len() + 3 + ZK_CYCLES);
         let executor = Executor::new(circuit, machine, min_po2, MAX_CYCLES_PO2);
-        Self { elf, executor }
+        Self {
+            elf,
+            max_cycles,
+            executor,
+        }
     }
 
     pub fn run(&mut self) -> Result<()> {
-        load_code(self.elf.entry, &self.elf.image, |chunk, fini| {
-            self.executor.step(chunk, fini)
-        })?;
-        self.executor.finalize();
+        self.run_with_rng(&mut thread_rng())
+    }
+
+    // Like run, but draws the zero-knowledge padding from `rng`.
+    pub fn run_with_rng(&mut self, rng: &mut impl Rng) -> Result<()> {
+        let executor = &mut self.executor;
+        let max_cycles = self.max_cycles;
+        let result = load_code(self.elf.entry, &self.elf.image, |chunk, fini| {
+            if !executor.halted {
+                // Check before the executor grows the trace, or tries and
+                // fails to.
+                let full = executor.cycle + fini + ZK_CYCLES >= executor.steps;
+                let over_budget = max_cycles.map_or(false, |max| {
+                    executor.cycle >= max || (full && executor.steps * 2 > max)
+                });
+                if over_budget || (full && executor.steps >= 1 << MAX_CYCLES_PO2) {
+                    return Err(ProverError::CycleLimitExceeded {
+                        limit: max_cycles.unwrap_or(1 << MAX_CYCLES_PO2),
+                        cycle: executor.cycle,
+                    }
+                    .into());
+                }
+            }
+            let cycle = executor.cycle;
+            let stepped = executor.step(chunk, fini)?;
+            if stepped {
+                let machine = &mut executor.custom;
+                let result = machine.on_step(&executor.data, executor.steps, cycle);
+                machine.callback_failed |= result.is_err();
+                result?;
+            }
+            Ok(stepped)
+        });
+        if let Err(err) = result {
+            if executor.custom.callback_failed || err.is::<ProverError>() {
+                return Err(err);
+            }
+            // Anything else was rejected by the circuit, which only happens
+            // if the guest breaks its rules.
+            return Err(err.context(ProverError::GuestFault));
+        }
+        self.executor.custom.flush_trace()?;
+        if self.executor.custom.trace_enabled {
+            let cycle = self.executor.halt_cycle as u32;
+            self.executor.custom.io.on_trace_exit(cycle)?;
+        }
+        self.executor.custom.debug_exit(None)?;
+        self.executor.finalize_with_rng(rng);
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use risc0_zkvm_platform::{io::SliceDescriptor, memory::MEM_SIZE};
+
+    use super::MemoryState;
+    use crate::host::ProverError;
+
+    #[test]
+    fn load_errors() {
+        let mut memory = MemoryState::new();
+        memory.store_u32(0x1000, 0x1234).unwrap();
+        memory.store_u32(0x1004, 8).unwrap();
+        assert_eq!(memory.load_u32(0x1000).unwrap(), 0x1234);
+
+        let error_of = |result: anyhow::Result<u32>| -> ProverError {
+            result.err().unwrap().downcast().unwrap()
+        };
+        assert_eq!(
+            error_of(memory.load_u32(0x1008)),
+            ProverError::UninitializedMemory { addr: 0x1008 }
+        );
+        assert_eq!(
+            error_of(memory.load_u32(MEM_SIZE as u32)),
+            ProverError::MemoryOutOfRange {
+                addr: MEM_SIZE as u32
+            }
+        );
+
+        // SAFETY: SliceDescriptor is a plain old repr(C) structure and has no
+        // pointers.
+        let read = |addr| unsafe { memory.read_descriptor::<SliceDescriptor>(addr) };
+        let desc = read(0x1000).unwrap();
+        assert_eq!((desc.size, desc.addr), (0x1234, 8));
+        let err = read(0x1002).err().unwrap();
+        assert_eq!(
+            err.downcast::<ProverError>().unwrap(),
+            ProverError::UnalignedAccess {
+                addr: 0x1002,
+                pc: 0
+            }
+        );
+        let err = read(0x1004).err().unwrap();
+        assert_eq!(
+            err.downcast::<ProverError>().unwrap(),
+            ProverError::UninitializedMemory { addr: 0x1008 }
+        );
+    }
+}
@@ -15,22 +15,33 @@
 mod cpu_eval;
 pub mod exec;
 mod ffpu;
+mod session;
 
-use std::io::Write;
+use std::{io::Write, mem};
 
-use anyhow::{bail, Result};
+use anyhow::Result;
+use rand::{rngs::StdRng, thread_rng, RngCore, SeedableRng};
 use risc0_zkp::{
-    core::sha::default_implementation,
+    core::{blake3, poseidon},
+    field::baby_bear::BabyBear,
     hal::{cpu::CpuHal, EvalCheck, Hal},
-    prove::adapter::ProveAdapter,
 };
 use risc0_zkvm_platform::{
     io::{SENDRECV_CHANNEL_INITIAL_INPUT, SENDRECV_CHANNEL_STDERR, SENDRECV_CHANNEL_STDOUT},
     memory::MEM_SIZE,
 };
 
-use self::cpu_eval::CpuEvalCheck;
-use crate::{elf::Program, host::ProverOpts, method_id::MethodId, receipt::Receipt, CIRCUIT};
+pub use self::{cpu_eval::CpuEvalCheck, session::ExecutionSession};
+use crate::{
+    elf::Program,
+    host::{
+        GuestState, Key, KeyMode, KeyProvider, NoKeyProvider, ProverError, ProverOpts,
+        SendRecvReply, TraceEvent,
+    },
+    method_id::MethodId,
+    receipt::{HashFn, Receipt},
+    CIRCUIT,
+};
 
 pub struct Prover<'a> {
     elf: Program,
@@ -66,40 +77,72 @@ impl<'a> Prover<'a> {
     }
 
     pub fn run(&mut self) -> Result<Receipt> {
-        let hal = CpuHal::new();
         let eval = CpuEvalCheck::new(&CIRCUIT);
-        self.run_with_hal(&hal, &eval)
+        match self.inner.opts.hash {
+            HashFn::Sha256 => self.run_with_hal(&CpuHal::new(), &eval),
+            HashFn::Poseidon => self.run_with_hal(&CpuHal::new_with_sha(poseidon::Impl {}), &eval),
+            HashFn::Blake3 => self.run_with_hal(&CpuHal::new_with_sha(blake3::Impl {}), &eval),
+        }
     }
 
-    pub fn run_with_hal<H: Hal, E: EvalCheck<H>>(&mut self, hal: &H, eval: &E) -> Result<Receipt> {
-        let skip_seal = self.inner.opts.skip_seal;
-
-        let mut executor = exec::RV32Executor::new(&CIRCUIT, &self.elf, &mut self.inner);
-        executor.run()?;
+    pub fn run_with_hal<H: Hal<Field = BabyBear>, E: EvalCheck<H>>(
+        &mut self,
+        hal: &H,
+        eval: &E,
+    ) -> Result<Receipt> {
+        let session = self.execute()?;
 
-        let mut prover = ProveAdapter::new(&mut executor.executor);
-        let sha = default_implementation();
-
-        let seal = if skip_seal {
-            risc0_zkp::prove::prove_without_seal(sha, &mut prover);
-            Vec::new()
-        } else {
-            risc0_zkp::prove::prove(hal, sha, &mut prover, eval)
-        };
+        if self.inner.opts.skip_seal {
+            return session.into_unsealed_receipt();
+        }
 
-        // Attach the full version of the output journal & construct receipt object
-        let receipt = Receipt {
-            journal: self.inner.commit.clone(),
-            seal,
-        };
+        let receipt = session.prove(hal, eval)?;
 
-        if !skip_seal {
-            // Verify receipt to make sure it works
-            receipt.verify(&self.method_id)?;
-        }
+        // Verify receipt to make sure it works
+        receipt.verify(&self.method_id)?;
 
         Ok(receipt)
     }
+
+    /// Execute the guest without generating a seal, returning an
+    /// [ExecutionSession] which can be proven later.
+    pub fn execute(&mut self) -> Result<ExecutionSession> {
+        let max_cycles = self.inner.opts.max_cycles;
+        let rng_seed = self.inner.opts.rng_seed;
+        let mut rng: Box<dyn RngCore> = match rng_seed {
+            Some(seed) => {
+                log::warn!("Using a fixed RNG seed; the seal will not be zero-knowledge");
+                Box::new(StdRng::seed_from_u64(seed))
+            }
+            None => Box::new(thread_rng()),
+        };
+        let mut executor =
+            exec::RV32Executor::new(&CIRCUIT, &self.elf, &mut self.inner, max_cycles);
+        executor.run_with_rng(&mut rng)?;
+
+        let trace = &mut executor.executor;
+        let code = mem::take(&mut trace.code);
+        let data = mem::take(&mut trace.data);
+        let circuit_output = mem::take(&mut trace.output);
+        let cycles = trace.halt_cycle;
+        let po2 = trace.po2;
+
+        Ok(ExecutionSession {
+            code,
+            data,
+            circuit_output,
+            // Attach the full version of the output journal
+            journal: self.inner.commit.clone(),
+            output: self.inner.output.clone(),
+            cycles,
+            po2,
+            // Proving gets its own stream, so that its noise doesn't repeat
+            // the padding in the trace.
+            rng_seed: rng_seed.map(|_| rng.next_u64()),
+            hash: self.inner.opts.hash,
+            params: self.inner.opts.params,
+        })
+    }
 }
 
 struct ProverImpl<'a> {
@@ -107,40 +150,93 @@ struct ProverImpl<'a> {
     pub output: Vec<u8>,
     pub commit: Vec<u32>,
     pub opts: ProverOpts<'a>,
+    key_provider: Box<dyn KeyProvider + 'a>,
 }
 
 impl<'a> ProverImpl<'a> {
-    fn new(opts: ProverOpts<'a>) -> Self {
+    fn new(mut opts: ProverOpts<'a>) -> Self {
+        let key_provider = opts
+            .key_provider
+            .take()
+            .unwrap_or_else(|| Box::new(NoKeyProvider));
         Self {
             input: Vec::new(),
             output: Vec::new(),
             commit: Vec::new(),
             opts,
+            key_provider,
         }
     }
 }
 
 impl<'a> exec::IoHandler for ProverImpl<'a> {
-    fn on_txrx(&mut self, channel: u32, buf: &[u8]) -> Result<Vec<u8>> {
-        if let Some(cb) = self.opts.sendrecv_callbacks.get(&channel) {
-            return Ok(cb(channel, buf));
+    fn on_txrx(&mut self, channel: u32, buf: &[u8], reply: &mut SendRecvReply) -> Result<()> {
+        if let Some(handler) = self.opts.sendrecv_handlers.get_mut(&channel) {
+            return handler.on_sendrecv(channel, buf, reply);
         }
         match channel {
             SENDRECV_CHANNEL_INITIAL_INPUT => {
                 log::debug!("SENDRECV_CHANNEL_INITIAL_INPUT: {}", buf.len());
-                Ok(self.input.clone())
+                reply.write(&self.input)
             }
             SENDRECV_CHANNEL_STDOUT => {
                 log::debug!("SENDRECV_CHANNEL_STDOUT: {}", buf.len());
                 self.output.extend(buf);
-                Ok(Vec::new())
+                Ok(())
             }
             SENDRECV_CHANNEL_STDERR => {
                 log::debug!("SENDRECV_CHANNEL_STDERR: {}", buf.len());
-                std::io::stderr().lock().write_all(buf).unwrap();
-                Ok(Vec::new())
+                std::io::stderr().lock().write_all(buf)?;
+                Ok(())
             }
-            _ => bail!("Unknown channel: {channel}"),
+            _ => Err(ProverError::UnknownChannel(channel).into()),
+        }
+    }
+
+    fn on_get_key(&mut self, name: &str, mode: KeyMode) -> Result<Key> {
+        self.key_provider.get_key(name, mode)
+    }
+
+    fn is_trace_enabled(&self) -> bool {
+        self.opts.trace_callback.is_some()
+    }
+
+    fn on_trace(&mut self, event: &TraceEvent) -> Result<()> {
+        match self.opts.trace_callback.as_mut() {
+            Some(cb) => cb.on_instruction(event),
+            None => Ok(()),
+        }
+    }
+
+    fn on_trace_exit(&mut self, cycle: u32) -> Result<()> {
+        match self.opts.trace_callback.as_mut() {
+            Some(cb) => cb.on_exit(cycle),
+            None => Ok(()),
+        }
+    }
+
+    fn is_debug_enabled(&self) -> bool {
+        self.opts.debugger.is_some()
+    }
+
+    fn on_debug_step(&mut self, state: &GuestState) -> Result<()> {
+        match self.opts.debugger.as_mut() {
+            Some(debugger) => debugger.on_instruction(state),
+            None => Ok(()),
+        }
+    }
+
+    fn on_debug_exit(&mut self, state: &GuestState, fault: Option<&str>) -> Result<()> {
+        match self.opts.debugger.as_mut() {
+            Some(debugger) => debugger.on_exit(state, fault),
+            None => Ok(()),
+        }
+    }
+
+    fn on_log(&mut self, cycle: u32, msg: &str) -> Result<()> {
+        match self.opts.log_callback.as_mut() {
+            Some(cb) => cb.on_log(cycle, msg),
+            None => exec::log_message(cycle, msg),
         }
     }
 
@@ -150,6 +246,6 @@ impl<'a> exec::IoHandler for ProverImpl<'a> {
     }
 
     fn on_fault(&mut self, msg: &str) -> Result<()> {
-        bail!("{}", msg)
+        Err(ProverError::GuestPanic(msg.to_string()).into())
     }
 }
@@ -0,0 +1,165 @@
+// Copyright 2022 Risc0, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::{bail, Result};
+use rand::{rngs::StdRng, SeedableRng};
+use risc0_zkp::{
+    adapter::{CircuitDef, CustomStep},
+    core::{blake3, fp::Fp, poseidon, sha::default_implementation},
+    field::baby_bear::BabyBear,
+    hal::{EvalCheck, Hal},
+    prove::{adapter::ProveAdapter, executor::Executor, prove_without_seal},
+    ProofParams,
+};
+use risc0_zkvm_circuit::CircuitImpl;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    receipt::{HashFn, Receipt, ReceiptHeader},
+    CIRCUIT,
+};
+
+/// The result of executing a guest method without proving it.
+///
+/// A session holds the full execution trace, so it can be inspected (e.g. for
+/// cycle counts), serialized to disk, and later turned into a [Receipt] with
+/// [ExecutionSession::prove].
+#[derive(Serialize, Deserialize)]
+pub struct ExecutionSession {
+    /// The code columns of the execution trace.
+    pub code: Vec<Fp>,
+
+    /// The data columns of the execution trace.
+    pub data: Vec<Fp>,
+
+    /// The globals written by the circuit, which become the seal's output.
+    pub circuit_output: Vec<Fp>,
+
+    /// The journal committed by the guest.
+    pub journal: Vec<u32>,
+
+    /// The private output written by the guest.
+    pub output: Vec<u8>,
+
+    /// The number of cycles executed before the guest halted.
+    pub cycles: usize,
+
+    /// The log base 2 of the size of the execution trace.
+    pub po2: usize,
+
+    /// The seed for the noise added while proving, if the session came
+    /// from a prover with
+    /// [ProverOpts::with_rng_seed](crate::host::ProverOpts::with_rng_seed).
+    /// A seeded session is not zero-knowledge.
+    #[serde(default)]
+    pub rng_seed: Option<u64>,
+
+    /// The hash for the seal's Merkle trees and Fiat-Shamir, from
+    /// [ProverOpts::with_hash](crate::host::ProverOpts::with_hash).
+    #[serde(default)]
+    pub hash: HashFn,
+
+    /// The security parameters of the seal, from
+    /// [ProverOpts::with_proof_params](crate::host::ProverOpts::with_proof_params).
+    #[serde(default)]
+    pub params: ProofParams,
+}
+
+// All custom steps happen during execution; proving only accumulates over
+// the recorded trace, so reaching one of these indicates a bug.
+struct ProveOnlyStep;
+
+impl CustomStep for ProveOnlyStep {
+    fn call(&mut self, name: &str, _extra: &str, _args: &[Fp], _outs: &mut [Fp]) -> Result<()> {
+        bail!("Unexpected custom step while proving: {name}")
+    }
+}
+
+impl CircuitDef<ProveOnlyStep> for CircuitImpl {}
+
+impl ExecutionSession {
+    /// Generate a [Receipt] for this execution using the given [Hal], which
+    /// must build its Merkle trees with [ExecutionSession::hash].
+    pub fn prove<H: Hal<Field = BabyBear>, E: EvalCheck<H>>(
+        self,
+        hal: &H,
+        eval: &E,
+    ) -> Result<Receipt> {
+        self.params.validate()?;
+        let max_po2 = self.params.max_po2::<BabyBear>();
+        if self.po2 > max_po2 {
+            bail!(
+                "Trace of 2^{} cycles exceeds the 2^{max_po2} allowed by {:?}",
+                self.po2,
+                self.params
+            );
+        }
+        let mut executor = Executor::from_trace(
+            &CIRCUIT,
+            ProveOnlyStep,
+            self.code,
+            self.data,
+            self.circuit_output,
+            self.po2,
+        )?;
+        let mut adapter = match self.rng_seed {
+            Some(seed) => ProveAdapter::new_with_rng(&mut executor, StdRng::seed_from_u64(seed)),
+            None => ProveAdapter::new(&mut executor),
+        };
+        let params = &self.params;
+        let seal = match self.hash {
+            HashFn::Sha256 => {
+                risc0_zkp::prove::prove(hal, default_implementation(), &mut adapter, eval, params)
+            }
+            HashFn::Poseidon => {
+                risc0_zkp::prove::prove(hal, &poseidon::Impl {}, &mut adapter, eval, params)
+            }
+            HashFn::Blake3 => {
+                risc0_zkp::prove::prove(hal, &blake3::Impl {}, &mut adapter, eval, params)
+            }
+        };
+        Ok(Receipt {
+            header: ReceiptHeader::new_with_hash(&self.journal, self.po2 as u32, self.hash),
+            journal: self.journal,
+            seal,
+        })
+    }
+
+    /// Generate a [Receipt] without a seal, which can't be verified.  The
+    /// circuit's globals are still written to an IOP, and checked against
+    /// the journal as a seal's would be.
+    pub(crate) fn into_unsealed_receipt(self) -> Result<Receipt> {
+        let receipt = Receipt {
+            header: ReceiptHeader::new_with_hash(&self.journal, self.po2 as u32, self.hash),
+            journal: self.journal,
+            seal: Vec::new(),
+        };
+        let mut executor = Executor::from_trace(
+            &CIRCUIT,
+            ProveOnlyStep,
+            self.code,
+            self.data,
+            self.circuit_output,
+            self.po2,
+        )?;
+        let mut adapter = ProveAdapter::new(&mut executor);
+        let globals = match self.hash {
+            HashFn::Sha256 => prove_without_seal(default_implementation(), &mut adapter),
+            HashFn::Poseidon => prove_without_seal(&poseidon::Impl {}, &mut adapter),
+            HashFn::Blake3 => prove_without_seal(&blake3::Impl {}, &mut adapter),
+        };
+        receipt.check_journal(&receipt.header.journal_digest, &globals)?;
+        Ok(receipt)
+    }
+}
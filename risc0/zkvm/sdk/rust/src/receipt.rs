@@ -12,19 +12,189 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use alloc::vec::Vec;
+use alloc::{format, string::String, vec::Vec};
 
-use anyhow::Result;
-use serde::{Deserialize, Serialize};
+use anyhow::{bail, Result};
+#[cfg(feature = "verify")]
+use risc0_zkp::{
+    adapter::CircuitInfo,
+    core::{fp::Fp, sha::DIGEST_WORDS},
+    field::baby_bear::BabyBear,
+    verify::VerificationError,
+};
+use risc0_zkp::{core::sha::Digest, ProofParams};
+#[cfg(feature = "verify")]
+use risc0_zkvm_circuit::CircuitImpl;
+#[cfg(feature = "verify")]
+use risc0_zkvm_platform::WORD_SIZE;
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::method_id::MethodId;
 
-#[derive(Deserialize, Serialize, Clone)]
+/// The first word of every serialized [Receipt] ("R0RC" in little-endian).
+pub const RECEIPT_MAGIC: u32 = u32::from_le_bytes(*b"R0RC");
+
+/// The receipt format version written by this crate.  Readers reject
+/// receipts with a newer version; fields added to [ReceiptHeader] without
+/// bumping the version are ignored by older readers.
+///
+/// Version 2 added [ReceiptHeader::hash]; older readers would check a seal
+/// made with another hash as if it used SHA-256, so they must reject it.
+///
+/// Version 3 records the [ProofParams] at the end of the seal.  Seals from
+/// older versions were all made with the default parameters, which are
+/// appended when they are read.  The default parameters are not committed to
+/// the transcript (see [ProofParams::is_committed]), so those seals verify
+/// unchanged.
+pub const RECEIPT_VERSION: u32 = 3;
+
+/// Identifies the circuit which produces and verifies seals.
+pub const CIRCUIT_ID: &str = "risc0-rv32im";
+
+/// The hash used for the Merkle trees and Fiat-Shamir of a seal.
+///
+/// The journal digest is always SHA-256, since the guest computes it.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HashFn {
+    /// SHA-256, which is the cheapest to prove on a CPU.
+    #[default]
+    Sha256,
+
+    /// Poseidon over the base field of the circuit, which is the cheapest
+    /// to verify inside the zkVM; see [risc0_zkp::core::poseidon].
+    Poseidon,
+
+    /// BLAKE3, which is faster than SHA-256 to prove on a CPU, but has no
+    /// accelerator in the zkVM; see [risc0_zkp::core::blake3].
+    Blake3,
+}
+
+/// Describes how the seal of a [Receipt] was produced.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct ReceiptHeader {
+    pub circuit_id: String,
+    pub po2: u32,
+    pub journal_digest: Digest,
+    pub hash: HashFn,
+}
+
+// A [ReceiptHeader] as written by version 1, whose seals all use SHA-256.
+#[derive(Deserialize)]
+struct ReceiptHeaderV1 {
+    circuit_id: String,
+    po2: u32,
+    journal_digest: Digest,
+}
+
+#[derive(Clone)]
 pub struct Receipt {
+    pub header: ReceiptHeader,
     pub journal: Vec<u32>,
     pub seal: Vec<u32>,
 }
 
+// The serialized form of a [Receipt].  The header is stored as a
+// length-prefixed block of words so that readers can skip fields added
+// after they were built.
+#[derive(Deserialize, Serialize)]
+struct ReceiptEnvelope {
+    magic: u32,
+    version: u32,
+    header: Vec<u32>,
+    journal: Vec<u32>,
+    seal: Vec<u32>,
+}
+
+impl ReceiptHeader {
+    /// Construct a header for a journal proven by this crate's circuit.
+    #[cfg(not(target_arch = "riscv32"))]
+    pub fn new(journal: &[u32], po2: u32) -> Self {
+        Self::new_with_hash(journal, po2, HashFn::Sha256)
+    }
+
+    /// Like [ReceiptHeader::new], but for a seal made with `hash`.
+    #[cfg(not(target_arch = "riscv32"))]
+    pub fn new_with_hash(journal: &[u32], po2: u32, hash: HashFn) -> Self {
+        ReceiptHeader {
+            circuit_id: CIRCUIT_ID.into(),
+            po2,
+            journal_digest: journal_digest(journal),
+            hash,
+        }
+    }
+}
+
+// The SHA-256 of `journal`, which is what the guest commits to whatever hash
+// its seal uses.
+#[cfg(not(target_arch = "riscv32"))]
+fn journal_digest(journal: &[u32]) -> Digest {
+    use sha2::{Digest as _, Sha256};
+
+    let journal_hash = Sha256::digest(bytemuck::cast_slice::<u32, u8>(journal));
+    let mut digest = Digest::default();
+    for (word, bytes) in digest
+        .as_mut_slice()
+        .iter_mut()
+        .zip(journal_hash.chunks_exact(4))
+    {
+        *word = u32::from_be_bytes(bytes.try_into().unwrap());
+    }
+    digest
+}
+
+impl Serialize for Receipt {
+    fn serialize<S: Serializer>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error> {
+        let header = crate::serde::to_vec(&self.header).map_err(ser::Error::custom)?;
+        ReceiptEnvelope {
+            magic: RECEIPT_MAGIC,
+            version: RECEIPT_VERSION,
+            header,
+            journal: self.journal.clone(),
+            seal: self.seal.clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Receipt {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> core::result::Result<Self, D::Error> {
+        let envelope = ReceiptEnvelope::deserialize(deserializer)?;
+        if envelope.magic != RECEIPT_MAGIC {
+            return Err(de::Error::custom(
+                "Not a receipt, or a receipt written before receipts were versioned",
+            ));
+        }
+        if envelope.version > RECEIPT_VERSION {
+            return Err(de::Error::custom(format!(
+                "Receipt format version {} is newer than the supported version {}",
+                envelope.version, RECEIPT_VERSION
+            )));
+        }
+        let malformed = |err| de::Error::custom(format!("Malformed receipt header: {err}"));
+        let header = if envelope.version < 2 {
+            let header: ReceiptHeaderV1 =
+                crate::serde::from_slice(&envelope.header).map_err(malformed)?;
+            ReceiptHeader {
+                circuit_id: header.circuit_id,
+                po2: header.po2,
+                journal_digest: header.journal_digest,
+                hash: HashFn::Sha256,
+            }
+        } else {
+            crate::serde::from_slice(&envelope.header).map_err(malformed)?
+        };
+        let mut seal = envelope.seal;
+        if envelope.version < 3 && !seal.is_empty() {
+            seal.extend(ProofParams::default().to_words());
+        }
+        Ok(Receipt {
+            header,
+            journal: envelope.journal,
+            seal,
+        })
+    }
+}
+
 // FIXME: Remove this temporary conversion once our API is the same between
 // FFI and rust-based provers.
 impl From<&MethodId> for MethodId {
@@ -36,7 +206,7 @@ impl From<&MethodId> for MethodId {
 #[cfg(feature = "verify")]
 pub fn verify_with_hal<'a, M, H>(hal: &H, method_id: &'a M, seal: &[u32]) -> Result<()>
 where
-    H: risc0_zkp::verify::VerifyHal,
+    H: risc0_zkp::verify::VerifyHal<Field = BabyBear>,
     M: ?Sized,
     MethodId: From<&'a M>,
 {
@@ -64,7 +234,58 @@ where
         method_id.table[which] == *merkle_root
     };
 
-    verify(hal, &CIRCUIT, seal, check_code).map_err(|err| anyhow!("Verification failed: {:?}", err))
+    let params = seal_params(seal)?;
+    verify(hal, &CIRCUIT, seal, &params, check_code)
+        .map_err(|err| anyhow!("Verification failed: {:?}", err))
+}
+
+// Read the parameters a seal was made with, rejecting seals which are less
+// secure than those made with the default parameters.
+#[cfg(feature = "verify")]
+fn seal_params(seal: &[u32]) -> Result<ProofParams> {
+    let params = ProofParams::from_seal(seal)?;
+    let min_bits = ProofParams::default().security_bits();
+    if params.security_bits() < min_bits {
+        bail!(
+            "Seal has {} bits of security, but at least {min_bits} are required",
+            params.security_bits()
+        );
+    }
+    Ok(params)
+}
+
+// The number of result registers written by the guest: either the journal
+// itself or its digest, followed by the journal length in bytes.
+#[cfg(feature = "verify")]
+const RESULT_WORDS: usize = DIGEST_WORDS + 1;
+
+// Compute the result registers a guest writes for the given journal; see
+// `Env::finalize` in risc0-zkvm-guest.
+#[cfg(feature = "verify")]
+fn expected_output(journal: &[u32], journal_digest: &Digest) -> [u32; RESULT_WORDS] {
+    let mut words = [0; RESULT_WORDS];
+    if journal.len() <= DIGEST_WORDS {
+        words[..journal.len()].copy_from_slice(journal);
+    } else {
+        words[..DIGEST_WORDS].copy_from_slice(journal_digest.as_slice());
+    }
+    words[DIGEST_WORDS] = (journal.len() * WORD_SIZE) as u32;
+    words
+}
+
+// Decode the result registers written by the guest from the globals at
+// the start of the seal.  Each register is split into a low and a high
+// half-word.
+#[cfg(feature = "verify")]
+fn seal_output(seal: &[u32]) -> [u32; RESULT_WORDS] {
+    let out: &[Fp] = bytemuck::cast_slice(&seal[..CircuitImpl::OUTPUT_SIZE]);
+    let mut words = [0; RESULT_WORDS];
+    for (word, halves) in words.iter_mut().zip(out.chunks_exact(2)) {
+        let low: u32 = halves[0].into();
+        let high: u32 = halves[1].into();
+        *word = low | high << 16;
+    }
+    words
 }
 
 impl Receipt {
@@ -74,23 +295,84 @@ impl Receipt {
         M: ?Sized,
         MethodId: From<&'a M>,
     {
-        use risc0_zkp::{core::sha::default_implementation, verify::CpuVerifyHal};
+        use risc0_zkp::{
+            core::{blake3, poseidon, sha::default_implementation},
+            verify::CpuVerifyHal,
+        };
 
         use crate::CIRCUIT;
 
-        let sha = default_implementation();
-        let hal = CpuVerifyHal::new(sha, &CIRCUIT);
-        self.verify_with_hal(&hal, method_id)
+        match self.header.hash {
+            HashFn::Sha256 => self.verify_with_hal(
+                &CpuVerifyHal::new(default_implementation(), &CIRCUIT),
+                method_id,
+            ),
+            HashFn::Poseidon => {
+                self.verify_with_hal(&CpuVerifyHal::new(&poseidon::Impl {}, &CIRCUIT), method_id)
+            }
+            HashFn::Blake3 => {
+                self.verify_with_hal(&CpuVerifyHal::new(&blake3::Impl {}, &CIRCUIT), method_id)
+            }
+        }
     }
 
+    /// Verify this receipt with `hal`, which must use the hash named by
+    /// [ReceiptHeader::hash].  The journal is always hashed with SHA-256,
+    /// whatever the seal uses.  Guests have no SHA-256 besides the one in
+    /// `hal`, so they can only verify receipts sealed with SHA-256.
     #[cfg(feature = "verify")]
     pub fn verify_with_hal<'a, M, H>(&self, hal: &H, method_id: &'a M) -> Result<()>
     where
-        H: risc0_zkp::verify::VerifyHal,
+        H: risc0_zkp::verify::VerifyHal<Field = BabyBear>,
         M: ?Sized,
         MethodId: From<&'a M>,
     {
-        verify_with_hal(hal, method_id, &self.seal)
+        self.check_header()?;
+        verify_with_hal(hal, method_id, &self.seal)?;
+        #[cfg(not(target_arch = "riscv32"))]
+        let journal_digest = journal_digest(&self.journal);
+        #[cfg(target_arch = "riscv32")]
+        let journal_digest = match self.header.hash {
+            HashFn::Sha256 => risc0_zkp::core::sha::Sha::hash_words(hal.sha(), &self.journal),
+            hash => bail!("Guests can only verify receipts sealed with SHA-256, not {hash:?}"),
+        };
+        self.check_journal(&journal_digest, &self.seal)
+    }
+
+    // The seal only commits to the guest's result registers, so make sure
+    // the ones at the start of `seal` describe the journal we were handed.
+    #[cfg(feature = "verify")]
+    pub(crate) fn check_journal(&self, journal_digest: &Digest, seal: &[u32]) -> Result<()> {
+        if seal_output(seal) != expected_output(&self.journal, journal_digest) {
+            return Err(anyhow::Error::msg(VerificationError::JournalMismatch));
+        }
+        if *journal_digest != self.header.journal_digest {
+            bail!("Receipt header journal digest does not match the journal");
+        }
+        Ok(())
+    }
+
+    // Make sure this receipt was produced by a circuit we can verify and
+    // that the header describes the seal we were given.
+    #[cfg(feature = "verify")]
+    fn check_header(&self) -> Result<()> {
+        if self.header.circuit_id != CIRCUIT_ID {
+            bail!(
+                "Receipt was produced by circuit {:?}, but this verifier supports {:?}",
+                self.header.circuit_id,
+                CIRCUIT_ID
+            );
+        }
+        if let Some(po2) = self.seal.get(CircuitImpl::OUTPUT_SIZE) {
+            if *po2 != self.header.po2 {
+                bail!(
+                    "Receipt header claims po2 {}, but the seal has po2 {}",
+                    self.header.po2,
+                    po2
+                );
+            }
+        }
+        Ok(())
     }
 
     // Compatible API with FFI-based prover.